@@ -0,0 +1,28 @@
+use cfg_aliases::cfg_aliases;
+
+fn main() {
+    cfg_aliases! {
+        // The targets with a freedesktop.org desktop, shown with the `linux` module
+        freedesktop: {
+            any(
+                target_os = "linux",
+                target_os = "freebsd",
+                target_os = "dragonfly",
+                target_os = "netbsd",
+                target_os = "openbsd",
+                target_os = "illumos",
+                target_os = "solaris"
+            )
+        },
+        // The targets without a platform module, where nothing can be shown
+        unsupported: {
+            not(any(
+                freedesktop,
+                target_os = "macos",
+                target_abi = "macabi",
+                target_os = "redox",
+                windows
+            ))
+        },
+    }
+}
@@ -0,0 +1,206 @@
+//! Building the arguments of the programs that are run to show and open items, from paths and
+//! URIs that may come from anywhere.
+//!
+//! Programs are always started directly rather than through a shell, so a path is passed as a
+//! single argument whatever characters it contains. What's left to handle here is that it must
+//! not be taken for an option, and escaping it for the programs that split their arguments
+//! further: `dbus-send` arrays, AppleScript strings and Windows command lines.
+
+use std::{borrow::Cow, ffi::OsStr, path::Path};
+
+/// Returns `path` in a form that can't be taken for an option.
+///
+/// A relative path starting with `-` gets `./` in front, which names the same item. On Windows,
+/// where `/` starts options too, a leading `/` is replaced by `\`, which means the same. Absolute
+/// paths and URIs never start with either, so they are returned as they are.
+#[cfg_attr(unsupported, allow(dead_code))]
+pub(crate) fn path<P: AsRef<Path> + ?Sized>(path: &P) -> Cow<'_, OsStr> {
+    let path = path.as_ref();
+    let lossy = path.as_os_str().to_string_lossy();
+    if lossy.starts_with('-') {
+        return Cow::Owned(Path::new(".").join(path).into_os_string());
+    }
+    #[cfg(windows)]
+    if let Some(rest) = lossy.strip_prefix('/') {
+        let mut escaped = std::ffi::OsString::from("\\");
+        escaped.push(rest);
+        return Cow::Owned(escaped);
+    }
+    Cow::Borrowed(path.as_os_str())
+}
+
+/// Checks that `uri` can be passed as an argument.
+///
+/// A URI starts with its scheme, so one starting with `-` is really an option. Control characters
+/// such as newlines aren't allowed in URIs either, and would be taken as separators by programs
+/// that read lines.
+#[cfg(freedesktop)]
+pub(crate) fn uri(uri: &str) -> Result<&str, crate::Error> {
+    if uri.starts_with('-') || uri.chars().any(char::is_control) {
+        Err(crate::Error::InvalidPath)
+    } else {
+        Ok(uri)
+    }
+}
+
+/// Formats `items` as the value of a `dbus-send` array of strings.
+///
+/// `dbus-send` splits arrays on commas without a way to escape them, so commas are
+/// percent-encoded, which leaves URIs meaning the same.
+#[cfg(freedesktop)]
+pub(crate) fn dbus_send_array(items: &[&str]) -> String {
+    items
+        .iter()
+        .map(|item| item.replace(',', "%2C"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Quotes `s` as an AppleScript string literal.
+///
+/// Line breaks and tabs are escaped as well as quotes and backslashes, so that the literal can't
+/// end a line of the script.
+#[cfg(any(target_os = "macos", target_abi = "macabi"))]
+pub(crate) fn applescript_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Quotes `arg` as a single argument of a Windows command line, the way `CommandLineToArgvW` and
+/// the C runtime split them.
+///
+/// Backslashes are only special before a quote, so they are doubled there, including before the
+/// closing quote, which a folder path such as `C:\Users\` would otherwise escape.
+#[cfg(windows)]
+pub(crate) fn windows_arg(arg: &OsStr) -> std::ffi::OsString {
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+    const QUOTE: u16 = b'"' as u16;
+    const BACKSLASH: u16 = b'\\' as u16;
+    let mut out = vec![QUOTE];
+    let mut backslashes = 0;
+    for c in arg.encode_wide() {
+        match c {
+            BACKSLASH => backslashes += 1,
+            QUOTE => {
+                out.resize(out.len() + backslashes + 1, BACKSLASH);
+                backslashes = 0;
+            }
+            _ => backslashes = 0,
+        }
+        out.push(c);
+    }
+    out.resize(out.len() + backslashes, BACKSLASH);
+    out.push(QUOTE);
+    std::ffi::OsString::from_wide(&out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_with_leading_dash() {
+        assert_eq!(path("-rf"), OsStr::new("./-rf"));
+        assert_eq!(path("--"), OsStr::new("./--"));
+        assert_eq!(path("--help"), OsStr::new("./--help"));
+        assert_eq!(path("-"), OsStr::new("./-"));
+    }
+
+    #[test]
+    fn path_left_as_is() {
+        assert!(matches!(path("a-b"), Cow::Borrowed(_)));
+        assert_eq!(path("file -name"), OsStr::new("file -name"));
+        assert_eq!(path("line\nbreak"), OsStr::new("line\nbreak"));
+        assert_eq!(path("\"quoted\""), OsStr::new("\"quoted\""));
+        assert_eq!(path("back\\slash"), OsStr::new("back\\slash"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn path_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let arg = path(OsStr::from_bytes(b"-\xff\xfe"));
+        assert_eq!(arg.as_bytes(), b"./-\xff\xfe");
+        let arg = path(OsStr::from_bytes(b"/tmp/\xff"));
+        assert_eq!(arg.as_bytes(), b"/tmp/\xff");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn path_with_leading_slash() {
+        assert_eq!(path("/s"), OsStr::new("\\s"));
+        assert_eq!(path("C:\\Users"), OsStr::new("C:\\Users"));
+    }
+
+    #[cfg(freedesktop)]
+    #[test]
+    fn uri_checks() {
+        assert_eq!(uri("file:///tmp/-rf").unwrap(), "file:///tmp/-rf");
+        assert_eq!(uri("file:///a%22b%5C").unwrap(), "file:///a%22b%5C");
+        assert!(matches!(uri("--"), Err(crate::Error::InvalidPath)));
+        assert!(matches!(
+            uri("-file:///tmp"),
+            Err(crate::Error::InvalidPath)
+        ));
+        assert!(matches!(
+            uri("file:///tmp/a\nb"),
+            Err(crate::Error::InvalidPath)
+        ));
+        assert!(matches!(
+            uri("file:///tmp/a\rb"),
+            Err(crate::Error::InvalidPath)
+        ));
+    }
+
+    #[cfg(freedesktop)]
+    #[test]
+    fn dbus_send_arrays() {
+        assert_eq!(dbus_send_array(&[]), "");
+        assert_eq!(
+            dbus_send_array(&["file:///a,b", "file:///c"]),
+            "file:///a%2Cb,file:///c"
+        );
+        // Only commas are special to dbus-send
+        assert_eq!(
+            dbus_send_array(&["--", "file:///\"\\'"]),
+            "--,file:///\"\\'"
+        );
+    }
+
+    #[cfg(any(target_os = "macos", target_abi = "macabi"))]
+    #[test]
+    fn applescript_strings() {
+        assert_eq!(applescript_string("-a"), "\"-a\"");
+        assert_eq!(applescript_string("a\"b"), "\"a\\\"b\"");
+        assert_eq!(applescript_string("a\\b"), "\"a\\\\b\"");
+        assert_eq!(applescript_string("a\nb\rc\td"), "\"a\\nb\\rc\\td\"");
+        assert_eq!(applescript_string("\\\""), "\"\\\\\\\"\"");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_args() {
+        let arg = |s: &str| windows_arg(OsStr::new(s)).into_string().unwrap();
+        assert_eq!(arg("--"), "\"--\"");
+        assert_eq!(arg("a b"), "\"a b\"");
+        assert_eq!(arg("a\"b"), "\"a\\\"b\"");
+        assert_eq!(arg("C:\\Users\\"), "\"C:\\Users\\\\\"");
+        assert_eq!(arg("a\\\"b"), "\"a\\\\\\\"b\"");
+        assert_eq!(arg("a\\b"), "\"a\\b\"");
+        assert_eq!(arg("line\nbreak"), "\"line\nbreak\"");
+    }
+}
@@ -0,0 +1,127 @@
+//! Backends registered by the application, which are tried along with the built-in ones.
+
+use std::sync::{Arc, Mutex};
+
+use crate::{Error, Selection, ShowOptions, ShowOutcome};
+
+/// A way of showing items that the application provides, such as a bridge to the desktop of a
+/// remote session or a file manager for a virtual filesystem.
+///
+/// Backends are registered with [`register_backend`](crate::register_backend), and tried for every
+/// call that shows items, before or after the built-in ones depending on their priority.
+///
+/// ```no_run
+/// use showfile::{Action, Backend, Error, SelectedItem, Selection, ShowOptions, ShowOutcome};
+///
+/// /// Shows the items of a virtual filesystem in its own browser.
+/// struct VfsBrowser;
+///
+/// impl Backend for VfsBrowser {
+///     fn name(&self) -> &str {
+///         "vfs-browser"
+///     }
+///
+///     fn show(&self, selection: &Selection, _options: &ShowOptions) -> Result<ShowOutcome, Error> {
+///         let mut uris = Vec::new();
+///         for item in selection.iter() {
+///             match item {
+///                 SelectedItem::Uri(uri) if uri.starts_with("vfs:") => uris.push(uri.to_owned()),
+///                 // Leave everything else to the other backends
+///                 _ => return Err(Error::Unsupported),
+///             }
+///         }
+///         // ... open the browser on `uris` ...
+///         let mut outcome = ShowOutcome::default();
+///         outcome.actions.push(Action::Call {
+///             function: "vfs-browser".to_owned(),
+///             args: uris,
+///         });
+///         Ok(outcome)
+///     }
+/// }
+///
+/// showfile::register_backend(10, VfsBrowser);
+/// showfile::show_uri_in_file_manager("vfs:///projects/report.pdf")?;
+/// # Ok::<(), Error>(())
+/// ```
+pub trait Backend: Send + Sync {
+    /// Returns the name of the backend, such as `remote-desktop`.
+    fn name(&self) -> &str;
+
+    /// Shows the items of `selection`, which have already been checked against the `options`.
+    ///
+    /// Returns [`Error::Unsupported`] to leave the items to the next backend, which any other
+    /// error does as well. The outcome should have an [`Action`](crate::Action) describing what
+    /// was done, so that [`ShowOutcome::backend`] can tell which backend showed the items.
+    fn show(&self, selection: &Selection, options: &ShowOptions) -> Result<ShowOutcome, Error>;
+}
+
+/// A backend registered with [`register_backend`](crate::register_backend).
+struct Registered {
+    priority: i32,
+    backend: Arc<dyn Backend>,
+}
+
+/// The registered backends, from the highest priority to the lowest.
+static REGISTRY: Mutex<Vec<Registered>> = Mutex::new(Vec::new());
+
+pub(crate) fn register(priority: i32, backend: Arc<dyn Backend>) {
+    let mut registry = REGISTRY.lock().unwrap();
+    // After the backends with the same priority, so that they are tried in the order they were
+    // registered
+    let index = registry.partition_point(|registered| registered.priority >= priority);
+    registry.insert(index, Registered { priority, backend });
+}
+
+pub(crate) fn unregister(name: &str) -> bool {
+    let mut registry = REGISTRY.lock().unwrap();
+    let len = registry.len();
+    registry.retain(|registered| registered.backend.name() != name);
+    registry.len() != len
+}
+
+/// Shows the items with the registered backends and `builtin`, which shows them with the built-in
+/// backends, in order of priority until one of them succeeds.
+///
+/// `selection` makes the selection passed to the registered backends, and is only called if there
+/// are any. If they all fail, the first error other than [`Error::Unsupported`] is returned.
+pub(crate) fn show(
+    selection: impl FnOnce() -> Selection,
+    options: &ShowOptions,
+    builtin: impl FnOnce() -> Result<ShowOutcome, Error>,
+) -> Result<ShowOutcome, Error> {
+    let backends = REGISTRY
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|registered| (registered.priority, registered.backend.clone()))
+        .collect::<Vec<_>>();
+    if backends.is_empty() {
+        return builtin();
+    }
+    let selection = selection();
+    let mut builtin = Some(builtin);
+    let mut error = None;
+    let mut record = |res: Result<ShowOutcome, Error>| match res {
+        Ok(outcome) => Some(outcome),
+        Err(Error::Unsupported) => None,
+        Err(e) => {
+            error.get_or_insert(e);
+            None
+        }
+    };
+    for (priority, backend) in backends {
+        if priority <= 0 {
+            if let Some(outcome) = builtin.take().and_then(|builtin| record(builtin())) {
+                return Ok(outcome);
+            }
+        }
+        if let Some(outcome) = record(backend.show(&selection, options)) {
+            return Ok(outcome);
+        }
+    }
+    if let Some(outcome) = builtin.and_then(|builtin| record(builtin())) {
+        return Ok(outcome);
+    }
+    Err(error.unwrap_or(Error::Unsupported))
+}
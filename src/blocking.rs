@@ -0,0 +1,18 @@
+//! Functions that block until the call has returned.
+//!
+//! These are the same as the functions at the crate root, collected here so that it's obvious at
+//! the call site which calls may block. See [`nonblocking`](crate::nonblocking) for versions that
+//! run in the background.
+
+#[cfg(any(target_os = "macos", target_abi = "macabi"))]
+pub use crate::show_bookmark_in_file_manager;
+#[cfg(feature = "rfd")]
+pub use crate::show_file_handle_in_file_manager;
+#[cfg(windows)]
+pub use crate::show_shell_location;
+pub use crate::{
+    capabilities, doctor, file_manager_available, is_accessible, location_status, open_path_with,
+    open_path_with_default_app, portals, show_current_dir, show_current_exe, show_latest_in_dir,
+    show_matching, show_path_in_file_manager, show_properties, show_selection_in_file_manager,
+    show_uri_in_file_manager, show_uris_in_file_manager, show_with_backend, supports_uri_scheme,
+};
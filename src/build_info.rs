@@ -0,0 +1,214 @@
+//! What this build of the crate contains, for telling whether a user's binary can do something.
+
+use std::fmt;
+
+use crate::platform;
+
+/// The Cargo features of this crate, and whether each was enabled in this build.
+const FEATURES: &[(&str, bool)] = &[
+    ("rustbus", cfg!(feature = "rustbus")),
+    ("zbus", cfg!(feature = "zbus")),
+    ("gio", cfg!(feature = "gio")),
+    ("dbus", cfg!(feature = "dbus")),
+    ("dbus-send", cfg!(feature = "dbus-send")),
+    ("raw-window-handle", cfg!(feature = "raw-window-handle")),
+    ("tokio", cfg!(feature = "tokio")),
+    ("metrics", cfg!(feature = "metrics")),
+    ("opentelemetry", cfg!(feature = "opentelemetry")),
+    ("rfd", cfg!(feature = "rfd")),
+    ("static", cfg!(feature = "static")),
+    ("strict", cfg!(feature = "strict")),
+];
+
+/// How this crate was built, as returned by [`build_info`](crate::build_info).
+///
+/// The [`Display`](fmt::Display) implementation formats it on a few lines, as printed by
+/// `showfile --version`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BuildInfo {
+    /// The version of this crate.
+    pub version: &'static str,
+    /// The operating system it was built for, as in [`std::env::consts::OS`].
+    pub os: &'static str,
+    /// The Cargo features that were enabled, such as `zbus` or `raw-window-handle`.
+    pub features: Vec<&'static str>,
+    /// The ways of showing files that were compiled in.
+    pub backends: Vec<BackendInfo>,
+}
+
+/// A way of showing files that was compiled in, as listed in [`BuildInfo::backends`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct BackendInfo {
+    /// The name of the backend. On Linux, this is a name taken by
+    /// [`ShowOptions::backends`](crate::ShowOptions::backends), such as `file-manager1` or
+    /// `xdg-open`, or `documents-portal` for the XDG Documents portal, which doesn't show files
+    /// itself but maps the paths of files shared into a sandbox back to their paths on the host.
+    /// On other platforms, this is the system function or command it calls, such as
+    /// `SHOpenFolderAndSelectItems`.
+    pub name: &'static str,
+    /// The crate the backend makes its calls with, if any.
+    pub library: Option<Library>,
+}
+
+/// A crate that a backend makes its calls with, as in [`BackendInfo::library`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Library {
+    /// The name of the crate, such as `zbus`.
+    pub name: &'static str,
+    /// The version of the crate this crate depends on, such as `3`. Cargo picks the latest
+    /// compatible release, so the exact version is in the application's `Cargo.lock`.
+    pub version: &'static str,
+}
+
+impl BackendInfo {
+    /// Describes the backend `name`, which makes its calls with the crate `library` at the
+    /// version given in `Cargo.toml`, if any.
+    #[cfg_attr(unsupported, allow(dead_code))]
+    pub(crate) const fn new(
+        name: &'static str,
+        library: Option<(&'static str, &'static str)>,
+    ) -> Self {
+        Self {
+            name,
+            library: match library {
+                Some((name, version)) => Some(Library { name, version }),
+                None => None,
+            },
+        }
+    }
+}
+
+impl BuildInfo {
+    pub(crate) fn get() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            features: FEATURES
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| *name)
+                .collect(),
+            backends: platform::backends(),
+        }
+    }
+}
+
+impl fmt::Display for BackendInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.library {
+            Some(library) => write!(f, "{} ({} {})", self.name, library.name, library.version),
+            None => f.write_str(self.name),
+        }
+    }
+}
+
+impl fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "showfile {} for {}", self.version, self.os)?;
+        if self.features.is_empty() {
+            writeln!(f, "features: none")?;
+        } else {
+            writeln!(f, "features: {}", self.features.join(", "))?;
+        }
+        let backends = self
+            .backends
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if backends.is_empty() {
+            writeln!(f, "backends: none")
+        } else {
+            writeln!(f, "backends: {}", backends.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the features listed in `[features]` of `Cargo.toml`, with what each enables.
+    fn manifest_features() -> Vec<(String, Vec<String>)> {
+        let manifest = include_str!("../Cargo.toml");
+        let section = manifest.split_once("\n[features]\n").unwrap().1;
+        let section = section.split("\n[").next().unwrap();
+        let mut features = Vec::new();
+        let mut entry = String::new();
+        for line in section.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            entry.push_str(line);
+            if !entry.ends_with(']') {
+                continue;
+            }
+            let (name, list) = entry.split_once('=').unwrap();
+            let enabled = list.split('"').skip(1).step_by(2).map(str::to_owned);
+            features.push((name.trim().to_owned(), enabled.collect()));
+            entry.clear();
+        }
+        features
+    }
+
+    /// Returns `feature` with everything it enables, directly or through other features.
+    fn enabled_by(feature: &str) -> Vec<String> {
+        let features = manifest_features();
+        let mut enabled = vec![feature.to_owned()];
+        let mut i = 0;
+        while let Some(name) = enabled.get(i) {
+            if let Some((_, list)) = features.iter().find(|(f, _)| f == name) {
+                for item in list {
+                    if !enabled.contains(item) {
+                        enabled.push(item.clone());
+                    }
+                }
+            }
+            i += 1;
+        }
+        enabled
+    }
+
+    #[test]
+    fn features_are_listed() {
+        let names = manifest_features()
+            .into_iter()
+            .map(|(name, _)| name)
+            .filter(|name| name != "default")
+            .collect::<Vec<_>>();
+        let listed = FEATURES.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(names, listed);
+    }
+
+    #[test]
+    fn static_profile_uses_rustbus() {
+        let enabled = enabled_by("static");
+        assert!(enabled.iter().any(|f| f == "rustbus"));
+        for linked in [
+            "gio",
+            "dep:gio-sys",
+            "dep:glib-sys",
+            "dep:gobject-sys",
+            "dbus",
+        ] {
+            assert!(
+                !enabled.iter().any(|f| f == linked),
+                "static enables {linked}"
+            );
+        }
+    }
+
+    #[cfg(all(freedesktop, feature = "static"))]
+    #[test]
+    fn static_build() {
+        let info = BuildInfo::get();
+        assert!(info.features.contains(&"rustbus"));
+        assert!(!info.features.contains(&"gio"));
+        for backend in info.backends {
+            let library = backend.library.map(|library| library.name);
+            assert!(matches!(library, None | Some("rustbus")), "{backend}");
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Values detected from the environment once, and kept until [`refresh`](crate::refresh).
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+/// Incremented by [`refresh`], which makes every cached value stale.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// A value that is detected the first time it is needed after each [`refresh`].
+pub(crate) struct Cached<T>(Mutex<Option<(u64, T)>>);
+
+impl<T: Clone> Cached<T> {
+    pub(crate) const fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    /// Returns the cached value, or the one returned by `detect` if there is none since the last
+    /// refresh.
+    pub(crate) fn get(&self, detect: impl FnOnce() -> T) -> T {
+        let generation = GENERATION.load(Ordering::Acquire);
+        if let Some((cached, value)) = &*self.0.lock().unwrap() {
+            if *cached == generation {
+                return value.clone();
+            }
+        }
+        // Detect without holding the lock, so that calls made from `detect` can't deadlock
+        let value = detect();
+        *self.0.lock().unwrap() = Some((generation, value.clone()));
+        value
+    }
+}
+
+pub(crate) fn refresh() {
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+}
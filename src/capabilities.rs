@@ -0,0 +1,21 @@
+/// What the file manager on the current platform supports.
+///
+/// Returned by [`capabilities`](crate::capabilities), so that applications can hide actions that
+/// would do nothing instead of offering them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// Several items can be selected at once in one window.
+    ///
+    /// When `false`, [`show_uris_in_file_manager`](crate::show_uris_in_file_manager) shows the
+    /// items one at a time, or only opens their folders.
+    pub multi_select: bool,
+    /// A folder can be opened to show its contents, rather than being selected in its parent.
+    pub open_directory: bool,
+    /// An item can be shown with its name ready to be edited.
+    pub rename_mode: bool,
+    /// The file manager's properties dialog can be shown for an item.
+    pub properties_dialog: bool,
+    /// URIs other than `file://`, such as `smb://` or `sftp://`, can be shown.
+    pub uri_reveal: bool,
+}
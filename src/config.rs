@@ -0,0 +1,262 @@
+//! The user's configuration file, which sets the defaults of [`ShowOptions`](crate::ShowOptions)
+//! for every application.
+//!
+//! The file is `showfile.toml` in the user's configuration folder: `$XDG_CONFIG_HOME`, or
+//! `~/.config`, on Linux and Redox OS, `~/Library/Application Support` on macOS, and `%APPDATA%`
+//! on Windows. Only the parts of TOML needed for these settings are understood: top-level keys
+//! set to strings, booleans, or arrays of strings on one line. Tables, other keys and lines that
+//! can't be read are skipped, so that a mistake in the file never keeps applications from showing
+//! files.
+//!
+//! ```toml
+//! # Bundle identifier of the file manager to use on macOS
+//! file_manager = "com.binarynights.ForkLift"
+//! # Whether the file manager is brought to the front
+//! activate = false
+//! # Ways to show items on Linux, tried in order
+//! backends = ["dolphin", "file-manager1", "xdg-open"]
+//! ```
+
+use std::{env, fs, path::PathBuf};
+
+use crate::cache::Cached;
+
+/// The settings read from the configuration file, each unset if the file doesn't have it.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct UserConfig {
+    pub(crate) file_manager: Option<String>,
+    pub(crate) activate: Option<bool>,
+    pub(crate) backends: Option<Vec<String>>,
+}
+
+/// A value in the configuration file.
+enum Value {
+    String(String),
+    Bool(bool),
+    Array(Vec<String>),
+}
+
+impl UserConfig {
+    /// Returns the user's configuration, read from the file the first time it is needed after
+    /// each [`refresh`](crate::refresh).
+    pub(crate) fn get() -> Self {
+        static CONFIG: Cached<UserConfig> = Cached::new();
+        CONFIG.get(|| {
+            path()
+                .and_then(|path| fs::read_to_string(path).ok())
+                .map_or_else(Self::default, |text| Self::parse(&text))
+        })
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut config = Self::default();
+        let mut in_table = false;
+        for line in text.lines() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                // Every setting is a top-level key, so tables belong to something else
+                in_table = true;
+                continue;
+            }
+            if in_table || line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let Some((value, rest)) = parse_value(value.trim_start()) else {
+                continue;
+            };
+            let rest = rest.trim_start();
+            if !rest.is_empty() && !rest.starts_with('#') {
+                continue;
+            }
+            match (key.trim(), value) {
+                ("file_manager", Value::String(s)) => config.file_manager = Some(s),
+                ("activate", Value::Bool(b)) => config.activate = Some(b),
+                ("backends", Value::Array(a)) => config.backends = Some(a),
+                _ => {}
+            }
+        }
+        config
+    }
+}
+
+/// Returns where the configuration file is on this platform.
+fn path() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let dir = PathBuf::from(env::var_os("APPDATA")?);
+    #[cfg(any(target_os = "macos", target_abi = "macabi"))]
+    let dir = PathBuf::from(env::var_os("HOME")?).join("Library/Application Support");
+    #[cfg(all(not(target_os = "macos"), not(target_abi = "macabi"), not(windows)))]
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| Some(PathBuf::from(env::var_os("HOME")?).join(".config")))?;
+    Some(dir.join("showfile.toml"))
+}
+
+/// Reads the value at the start of `s`, returning it with what follows it.
+fn parse_value(s: &str) -> Option<(Value, &str)> {
+    if let Some(rest) = s.strip_prefix("true") {
+        return Some((Value::Bool(true), rest));
+    }
+    if let Some(rest) = s.strip_prefix("false") {
+        return Some((Value::Bool(false), rest));
+    }
+    let Some(mut rest) = s.strip_prefix('[') else {
+        let (s, rest) = parse_string(s)?;
+        return Some((Value::String(s), rest));
+    };
+    let mut items = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if let Some(rest) = rest.strip_prefix(']') {
+            return Some((Value::Array(items), rest));
+        }
+        let (item, after) = parse_string(rest)?;
+        items.push(item);
+        rest = after.trim_start();
+        match rest.strip_prefix(',') {
+            Some(after) => rest = after,
+            None if rest.starts_with(']') => {}
+            None => return None,
+        }
+    }
+}
+
+/// Reads the basic or literal string at the start of `s`, returning it with what follows it.
+fn parse_string(s: &str) -> Option<(String, &str)> {
+    if let Some(s) = s.strip_prefix('\'') {
+        let (literal, rest) = s.split_once('\'')?;
+        return Some((literal.to_owned(), rest));
+    }
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    let mut out = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((out, &s[i + 2..])),
+            '\\' => out.push(match chars.next()?.1 {
+                '"' => '"',
+                '\\' => '\\',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => {
+                    let code = (0..4)
+                        .map(|_| chars.next().map(|(_, c)| c))
+                        .collect::<Option<String>>()?;
+                    char::from_u32(u32::from_str_radix(&code, 16).ok()?)?
+                }
+                _ => return None,
+            }),
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings() {
+        let config = UserConfig::parse(
+            "file_manager = \"org.gnome.Nautilus\"\n\
+             activate = false\n\
+             backends = [\"dolphin\", 'xdg-open']\n",
+        );
+        assert_eq!(config.file_manager.as_deref(), Some("org.gnome.Nautilus"));
+        assert_eq!(config.activate, Some(false));
+        assert_eq!(
+            config.backends,
+            Some(vec!["dolphin".to_owned(), "xdg-open".to_owned()])
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            parse_string(r#""a\"b\\c\n\r\t" rest"#),
+            Some(("a\"b\\c\n\r\t".to_owned(), " rest"))
+        );
+        assert_eq!(
+            parse_string(r#""\u00e9t\u00E9""#),
+            Some(("été".to_owned(), ""))
+        );
+        assert_eq!(parse_string("\"é\" x"), Some(("é".to_owned(), " x")));
+        // Literal strings have no escapes
+        assert_eq!(
+            parse_string(r"'C:\Users\'"),
+            Some((r"C:\Users\".to_owned(), ""))
+        );
+        assert_eq!(parse_string(r#""\x""#), None);
+        assert_eq!(parse_string(r#""\u00""#), None);
+        assert_eq!(parse_string(r#""\uD800""#), None);
+    }
+
+    #[test]
+    fn comments() {
+        let config = UserConfig::parse(
+            "# A comment\n\
+             activate = true # brought to the front\n\
+             file_manager = \"a#b\"# no space\n\
+             backends = [\"dolphin\"] # only Dolphin\n",
+        );
+        assert_eq!(config.activate, Some(true));
+        assert_eq!(config.file_manager.as_deref(), Some("a#b"));
+        assert_eq!(config.backends, Some(vec!["dolphin".to_owned()]));
+    }
+
+    #[test]
+    fn arrays() {
+        let array = |s| match parse_value(s) {
+            Some((Value::Array(items), rest)) => Some((items, rest)),
+            _ => None,
+        };
+        assert_eq!(array("[]"), Some((vec![], "")));
+        assert_eq!(array("[ ]"), Some((vec![], "")));
+        assert_eq!(
+            array("[\"a\",'b',]x"),
+            Some((vec!["a".to_owned(), "b".to_owned()], "x"))
+        );
+        assert_eq!(array("[\"a,b\"]"), Some((vec!["a,b".to_owned()], "")));
+        assert_eq!(array("[\"a\" \"b\"]"), None);
+        assert_eq!(array("[\"a\","), None);
+        assert_eq!(array("[true]"), None);
+    }
+
+    #[test]
+    fn unknown_keys_and_tables() {
+        let config = UserConfig::parse(
+            "editor = \"vim\"\n\
+             activate = false\n\
+             [other]\n\
+             file_manager = \"org.kde.dolphin\"\n",
+        );
+        assert_eq!(config.activate, Some(false));
+        assert_eq!(config.file_manager, None);
+        assert_eq!(config.backends, None);
+    }
+
+    #[test]
+    fn malformed() {
+        let config = UserConfig::parse(
+            "activate = yes\n\
+             activate = falsey\n\
+             file_manager = \"unterminated\n\
+             file_manager = 'a' 'b'\n\
+             backends = \"dolphin\"\n\
+             backends = [\"dolphin\"\n\
+             file_manager\n\
+             = \"x\"\n",
+        );
+        assert_eq!(config.activate, None);
+        assert_eq!(config.file_manager, None);
+        assert_eq!(config.backends, None);
+        // The lines that can be read are still used
+        let config = UserConfig::parse("activate = nope\nactivate = false\n");
+        assert_eq!(config.activate, Some(false));
+    }
+}
@@ -0,0 +1,229 @@
+//! Finding the files to show for the helpers that don't take the path itself.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::Error;
+
+fn from_io(err: io::Error) -> Error {
+    if err.kind() == io::ErrorKind::NotFound {
+        Error::NotFound
+    } else {
+        Error::backend(err)
+    }
+}
+
+/// Returns the files in `dir` with the time they were last modified.
+///
+/// Symbolic links are followed, and entries that disappear while reading or that aren't files are
+/// skipped.
+fn files(dir: &Path) -> Result<impl Iterator<Item = (PathBuf, SystemTime)>, Error> {
+    let entries = fs::read_dir(dir).map_err(from_io)?;
+    Ok(entries.filter_map(|entry| {
+        let path = entry.ok()?.path();
+        let metadata = fs::metadata(&path).ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+        Some((path, metadata.modified().ok()?))
+    }))
+}
+
+/// Checks whether `name` matches the glob `pattern`.
+///
+/// `*` matches any run of characters, `?` any single character, and `[...]` any character in the
+/// set, which can contain ranges such as `a-z` and be negated with a leading `!`. As in the shell,
+/// a leading `.` is only matched literally, so hidden files are only matched by patterns starting
+/// with `.`.
+fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    if name.first() == Some(&'.') && pattern.first() != Some(&'.') {
+        return false;
+    }
+    let (mut p, mut n) = (0, 0);
+    // Where to resume after the last `*` if the rest doesn't match
+    let mut star = None;
+    while n < name.len() {
+        // How much of the pattern matched the next character of the name
+        let step = match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(1),
+            Some('[') => match_class(&pattern[p + 1..], name[n]).map(|len| len + 1),
+            Some(&c) if c == name[n] => Some(1),
+            _ => None,
+        };
+        match (step, star) {
+            (Some(len), _) => {
+                p += len;
+                n += 1;
+            }
+            (None, Some((star_p, star_n))) => {
+                star = Some((star_p, star_n + 1));
+                p = star_p;
+                n = star_n + 1;
+            }
+            (None, None) => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Matches `c` against the character class at the start of `class`, just after the `[`.
+///
+/// Returns the length of the class including the closing `]`, or `None` if `c` isn't in it. A
+/// class without a closing `]` matches nothing.
+fn match_class(class: &[char], c: char) -> Option<usize> {
+    let negated = class.first() == Some(&'!');
+    let start = usize::from(negated);
+    // A `]` right after the opening bracket is part of the set
+    let end = start + 1 + class.get(start + 1..)?.iter().position(|&c| c == ']')?;
+    let set = &class[start..end];
+    let mut found = false;
+    let mut i = 0;
+    while i < set.len() {
+        if set.get(i + 1) == Some(&'-') && i + 2 < set.len() {
+            found |= (set[i]..=set[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            found |= set[i] == c;
+            i += 1;
+        }
+    }
+    (found != negated).then_some(end + 1)
+}
+
+/// Returns the entries in `dir` whose names match the glob `pattern`, sorted by name, keeping at
+/// most `limit` of them.
+pub(crate) fn matching(dir: &Path, pattern: &str, limit: usize) -> Result<Vec<PathBuf>, Error> {
+    if pattern.is_empty() || pattern.contains(std::path::is_separator) {
+        return Err(Error::InvalidPath);
+    }
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let mut paths = fs::read_dir(dir)
+        .map_err(from_io)?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().to_str()?.chars().collect::<Vec<_>>();
+            glob_match(&pattern, &name).then(|| entry.path())
+        })
+        .collect::<Vec<_>>();
+    if paths.is_empty() {
+        return Err(Error::NotFound);
+    }
+    paths.sort_unstable();
+    paths.truncate(limit);
+    Ok(paths)
+}
+
+/// Returns the most recently modified file in `dir`.
+pub(crate) fn latest_file(dir: &Path) -> Result<PathBuf, Error> {
+    files(dir)?
+        .max_by_key(|(_, modified)| *modified)
+        .map(|(path, _)| path)
+        .ok_or(Error::NotFound)
+}
+
+/// Returns the path of the running executable, with symbolic links resolved.
+///
+/// Inside a macOS app bundle, this is the bundle rather than the executable in it, since that is
+/// what users see in Finder.
+pub(crate) fn current_exe() -> Result<PathBuf, Error> {
+    let exe = env::current_exe().map_err(from_io)?;
+    // The executable was replaced or deleted while running, as Linux reports it
+    if exe.to_str().is_some_and(|s| s.ends_with(" (deleted)")) && !exe.exists() {
+        return Err(Error::NotFound);
+    }
+    let exe = fs::canonicalize(exe).map_err(from_io)?;
+    let bundle = exe
+        .parent()
+        .filter(|dir| dir.ends_with("Contents/MacOS"))
+        .and_then(|dir| dir.parent()?.parent())
+        .filter(|bundle| bundle.extension().is_some_and(|ext| ext == "app"))
+        .map(Path::to_owned);
+    Ok(bundle.unwrap_or(exe))
+}
+
+/// Returns the working directory of the process.
+pub(crate) fn current_dir() -> Result<PathBuf, Error> {
+    env::current_dir().map_err(from_io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        let pattern = pattern.chars().collect::<Vec<_>>();
+        let name = name.chars().collect::<Vec<_>>();
+        glob_match(&pattern, &name)
+    }
+
+    #[test]
+    fn star() {
+        assert!(matches("*", "report.pdf"));
+        assert!(matches("*.pdf", "report.pdf"));
+        assert!(matches("report*", "report"));
+        assert!(matches("**", "a"));
+        assert!(!matches("*.pdf", "report.pdf.txt"));
+        // The first `.pdf` doesn't end the name, so the `*` has to take it in
+        assert!(matches("*.pdf", "a.pdf.b.pdf"));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(matches("*ab", "aaab"));
+        assert!(!matches("a*b*c", "aXbYc."));
+    }
+
+    #[test]
+    fn question_mark() {
+        assert!(matches("?", "a"));
+        assert!(!matches("?", ""));
+        assert!(!matches("?", "ab"));
+        assert!(matches("IMG_????.jpg", "IMG_0042.jpg"));
+        assert!(!matches("IMG_????.jpg", "IMG_042.jpg"));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(matches("[a-z]", "q"));
+        assert!(!matches("[a-z]", "Q"));
+        assert!(matches("[abc]x", "bx"));
+        assert!(matches("v[0-9][0-9]", "v42"));
+        assert!(matches("[!x]", "y"));
+        assert!(!matches("[!x]", "x"));
+        assert!(!matches("[!a-z]", "m"));
+        // A `]` first in the set, and a `-` at the end, are literal
+        assert!(matches("[]]", "]"));
+        assert!(matches("[a-]", "-"));
+        assert!(matches("*[0-9].log", "app-3.log"));
+    }
+
+    #[test]
+    fn unterminated_class() {
+        assert!(!matches("[a", "a"));
+        assert!(!matches("[a", "[a"));
+        assert!(!matches("*[", "x["));
+        assert!(!matches("[!", "x"));
+    }
+
+    #[test]
+    fn hidden_files() {
+        assert!(!matches("*", ".bashrc"));
+        assert!(!matches("?bashrc", ".bashrc"));
+        assert!(matches(".*", ".bashrc"));
+        assert!(matches("*rc", "a.bashrc"));
+    }
+
+    #[test]
+    fn non_ascii() {
+        assert!(matches("r?sum?.pdf", "résumé.pdf"));
+        assert!(matches("*.txt", "日本語.txt"));
+        assert!(matches("[à-ÿ]*", "élan"));
+        assert!(!matches("[!é]*", "été"));
+        assert!(matches("?", "😀"));
+    }
+}
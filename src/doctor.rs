@@ -0,0 +1,160 @@
+//! A report on the environment, for bug reports about files that didn't show.
+
+use std::fmt;
+
+use crate::{platform, BuildInfo, Capabilities, Handler, Portal, SandboxKind};
+
+/// What this crate found out about the environment, as returned by [`doctor`](crate::doctor).
+///
+/// Each check only applies to some platforms, and is `None` on the others. The
+/// [`Display`](fmt::Display) implementation formats the report as one `name: value` line per
+/// check, ready to be pasted into a bug report.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Diagnostics {
+    /// How this crate was built, as returned by [`build_info`](crate::build_info).
+    pub build: BuildInfo,
+    /// The type of the graphical session from `XDG_SESSION_TYPE`, such as `wayland` or `x11`.
+    /// Only set on Linux.
+    pub session_type: Option<String>,
+    /// The desktop environment from `XDG_CURRENT_DESKTOP`, such as `ubuntu:GNOME`. Only set on
+    /// Linux.
+    pub desktop: Option<String>,
+    /// The application sandbox the process runs in, as returned by
+    /// [`is_sandboxed`](crate::is_sandboxed).
+    pub sandbox: Option<SandboxKind>,
+    /// Whether the D-Bus session bus could be connected to. Only set on Linux.
+    pub session_bus: Option<bool>,
+    /// The process that owns the `org.freedesktop.FileManager1` name on the session bus, if one
+    /// is running. Only set on Linux.
+    pub file_manager1_owner: Option<Handler>,
+    /// The XDG desktop portal interfaces on the session bus, as returned by
+    /// [`portals`](crate::portals).
+    pub portals: Vec<Portal>,
+    /// Whether COM could be initialized on the thread that makes shell calls. Only set on
+    /// Windows.
+    pub com_initialized: Option<bool>,
+    /// Whether Finder is running, and so can be reached to show files. Only set on macOS.
+    pub finder_running: Option<bool>,
+    /// Whether a file manager is available, as returned by
+    /// [`file_manager_available`](crate::file_manager_available).
+    pub file_manager_available: bool,
+    /// What the file manager supports, as returned by [`capabilities`](crate::capabilities).
+    pub capabilities: Capabilities,
+    /// The errors the checks ran into, such as why the session bus couldn't be connected to.
+    pub problems: Vec<String>,
+}
+
+impl Diagnostics {
+    pub(crate) fn probe() -> Self {
+        let mut diagnostics = Self {
+            build: BuildInfo::get(),
+            session_type: None,
+            desktop: None,
+            sandbox: platform::sandbox_kind(),
+            session_bus: None,
+            file_manager1_owner: None,
+            portals: Vec::new(),
+            com_initialized: None,
+            finder_running: None,
+            file_manager_available: platform::file_manager_available(),
+            capabilities: platform::capabilities(),
+            problems: Vec::new(),
+        };
+        platform::diagnose(&mut diagnostics);
+        match platform::portals() {
+            Ok(portals) => diagnostics.portals = portals,
+            // Already reported as a problem with the session bus
+            Err(_) if diagnostics.session_bus == Some(false) => {}
+            Err(e) => diagnostics
+                .problems
+                .push(format!("portals: {}", crate::error::report(&e))),
+        }
+        diagnostics
+    }
+}
+
+/// Formats `value` as `yes` or `no`.
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.build)?;
+        if let Some(session_type) = &self.session_type {
+            writeln!(f, "session type: {session_type}")?;
+        }
+        if let Some(desktop) = &self.desktop {
+            writeln!(f, "desktop: {desktop}")?;
+        }
+        match self.sandbox {
+            Some(sandbox) => writeln!(f, "sandbox: {sandbox:?}")?,
+            None => writeln!(f, "sandbox: none")?,
+        }
+        if let Some(session_bus) = self.session_bus {
+            writeln!(f, "session bus: {}", yes_no(session_bus))?;
+            match &self.file_manager1_owner {
+                Some(Handler {
+                    id,
+                    bus_name: Some(bus_name),
+                    ..
+                }) => writeln!(f, "FileManager1 owner: {id} ({bus_name})")?,
+                Some(handler) => writeln!(f, "FileManager1 owner: {}", handler.id)?,
+                None => writeln!(f, "FileManager1 owner: none")?,
+            }
+            let portals = self
+                .portals
+                .iter()
+                .map(|portal| format!("{} {}", portal.interface, portal.version))
+                .collect::<Vec<_>>();
+            if portals.is_empty() {
+                writeln!(f, "portals: none")?;
+            } else {
+                writeln!(f, "portals: {}", portals.join(", "))?;
+            }
+        }
+        if let Some(com_initialized) = self.com_initialized {
+            writeln!(f, "COM initialized: {}", yes_no(com_initialized))?;
+        }
+        if let Some(finder_running) = self.finder_running {
+            writeln!(f, "Finder running: {}", yes_no(finder_running))?;
+        }
+        writeln!(
+            f,
+            "file manager available: {}",
+            yes_no(self.file_manager_available)
+        )?;
+        let Capabilities {
+            multi_select,
+            open_directory,
+            rename_mode,
+            properties_dialog,
+            uri_reveal,
+        } = self.capabilities;
+        let capabilities = [
+            ("multi_select", multi_select),
+            ("open_directory", open_directory),
+            ("rename_mode", rename_mode),
+            ("properties_dialog", properties_dialog),
+            ("uri_reveal", uri_reveal),
+        ]
+        .iter()
+        .filter(|(_, supported)| *supported)
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>();
+        if capabilities.is_empty() {
+            writeln!(f, "capabilities: none")?;
+        } else {
+            writeln!(f, "capabilities: {}", capabilities.join(", "))?;
+        }
+        for problem in &self.problems {
+            writeln!(f, "problem: {problem}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,406 @@
+use std::{fmt, io};
+
+use crate::platform;
+
+/// An error returned when a file could not be shown in the file manager.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The path or URI could not be converted into a form understood by the file manager.
+    InvalidPath,
+    /// The file or folder does not exist.
+    NotFound,
+    /// The current user isn't allowed to access the file or folder, or one of the folders leading
+    /// to it. Returned by [`is_accessible`](crate::is_accessible).
+    PermissionDenied,
+    /// The path or URI is outside of the folders allowed by the [`ShowPolicy`](crate::ShowPolicy)
+    /// set with [`ShowOptions::policy`](crate::ShowOptions::policy). Nothing was passed to the
+    /// system.
+    PolicyViolation,
+    /// The selection has more items, or items in more folders, than allowed by
+    /// [`ShowOptions::item_limit`](crate::ShowOptions::item_limit) and
+    /// [`ShowOptions::window_limit`](crate::ShowOptions::window_limit). Nothing was shown.
+    TooManyItems,
+    /// The process is not running in an interactive session, so a file manager window would not
+    /// be visible to the user. Returned on Windows when running as a service or in session 0.
+    NoInteractiveSession,
+    /// No file manager is available. Returned on Linux when nothing provides the
+    /// `org.freedesktop.FileManager1` D-Bus interface and no fallback could be used.
+    NoFileManager,
+    /// No application is available to open the file.
+    NoApplication,
+    /// The path is outside of the app sandbox and the app has not been granted access to it.
+    /// Returned on macOS when running inside the App Sandbox. Access can be granted with the
+    /// user-selected files entitlement, or by showing a security-scoped bookmark instead.
+    SandboxAccessDenied,
+    /// The process is running as a different user than the desktop session and can't reach it.
+    /// Returned on Linux when running under `sudo` or `pkexec` as a user other than root, or when
+    /// the session user's bus can't be found.
+    SessionUserMismatch,
+    /// The file manager didn't respond before the deadline set with
+    /// [`ShowOptions::timeout`](crate::ShowOptions::timeout).
+    TimedOut,
+    /// The request is not supported by the platform, or by the features this crate was built
+    /// with. Returned on Linux for calls that need D-Bus when no D-Bus backend feature is enabled.
+    Unsupported,
+    /// The underlying platform API reported an error.
+    ///
+    /// The platform error is kept as the [source](std::error::Error::source) of this error, such
+    /// as a `windows::core::Error` on Windows or the D-Bus error reply on Linux. The message of
+    /// this error doesn't repeat it, so that reporters that print the chain of sources, such as
+    /// `anyhow`, show it once.
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// The category of an [`Error`], for choosing how to recover from it.
+///
+/// Unlike the variants of [`Error`], this also classifies backend errors where possible, such as a
+/// D-Bus call that timed out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The path or URI is invalid, see [`Error::InvalidPath`].
+    InvalidPath,
+    /// The file or folder does not exist.
+    NotFound,
+    /// There is no graphical session to show a window in, or it belongs to another user.
+    NoGraphicalSession,
+    /// No file manager is available, see [`Error::NoFileManager`].
+    NoFileManager,
+    /// No application is available to open the file, see [`Error::NoApplication`].
+    NoApplication,
+    /// Access to the file, or to the file manager, was denied.
+    PermissionDenied,
+    /// The file manager did not respond in time, see [`Error::TimedOut`].
+    TimedOut,
+    /// Too many items were given at once, see [`Error::TooManyItems`].
+    TooManyItems,
+    /// The file manager, or this build, does not support the request, see
+    /// [`Error::Unsupported`].
+    Unsupported,
+    /// Any other error reported by the platform.
+    Backend,
+}
+
+impl ErrorKind {
+    /// Returns the name of this kind in snake case, such as `not_found`, for labeling metrics and
+    /// spans, and in machine-readable output.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::InvalidPath => "invalid_path",
+            Self::NotFound => "not_found",
+            Self::NoGraphicalSession => "no_graphical_session",
+            Self::NoFileManager => "no_file_manager",
+            Self::NoApplication => "no_application",
+            Self::PermissionDenied => "permission_denied",
+            Self::TimedOut => "timed_out",
+            Self::TooManyItems => "too_many_items",
+            Self::Unsupported => "unsupported",
+            Self::Backend => "backend",
+        }
+    }
+}
+
+/// A suggestion for fixing what caused an [`Error`], as returned by [`Error::hint`].
+///
+/// Each hint has a stable [label](Self::label) for choosing what to show in code, and its
+/// [`Display`](fmt::Display) implementation is a short sentence that can be shown to users as is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Hint {
+    /// Pass an absolute path, or a well-formed URI.
+    CheckPath,
+    /// The item was moved or deleted, so the list it came from should be refreshed.
+    RefreshLocation,
+    /// Ask the user for access to the item, or to the folders leading to it.
+    RequestAccess,
+    /// Allow the folder in the [`ShowPolicy`](crate::ShowPolicy), or don't offer to show the
+    /// item.
+    AdjustPolicy,
+    /// Show fewer items at once, or raise the limits set with
+    /// [`ShowOptions::item_limit`](crate::ShowOptions::item_limit) and
+    /// [`ShowOptions::window_limit`](crate::ShowOptions::window_limit).
+    ShowFewerItems,
+    /// Run the application in the user's desktop session, rather than as a service or through
+    /// `sudo`.
+    RunInDesktopSession,
+    /// Install a file manager, such as one that provides `org.freedesktop.FileManager1` on Linux,
+    /// or enable [`ShowOptions::fallback`](crate::ShowOptions::fallback).
+    InstallFileManager,
+    /// Install an application that opens this type of file, or set a default one.
+    InstallApplication,
+    /// Grant the app sandbox access to the item, with the user-selected files entitlement or a
+    /// security-scoped bookmark.
+    GrantSandboxAccess,
+    /// Try again once the file manager has started, or raise
+    /// [`ShowOptions::timeout`](crate::ShowOptions::timeout).
+    RetryLater,
+    /// Build with a D-Bus feature, such as `rustbus`, to show files on Linux.
+    EnableDBusFeature,
+    /// Start a D-Bus session bus, or make `DBUS_SESSION_BUS_ADDRESS` point to the running one.
+    StartSessionBus,
+    /// Hide the action, since the file manager or platform can't do it, see
+    /// [`capabilities`](crate::capabilities).
+    CheckCapabilities,
+}
+
+impl Hint {
+    /// Returns the name of this hint in snake case, such as `install_file_manager`, for choosing
+    /// what to show in code, and in machine-readable output.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::CheckPath => "check_path",
+            Self::RefreshLocation => "refresh_location",
+            Self::RequestAccess => "request_access",
+            Self::AdjustPolicy => "adjust_policy",
+            Self::ShowFewerItems => "show_fewer_items",
+            Self::RunInDesktopSession => "run_in_desktop_session",
+            Self::InstallFileManager => "install_file_manager",
+            Self::InstallApplication => "install_application",
+            Self::GrantSandboxAccess => "grant_sandbox_access",
+            Self::RetryLater => "retry_later",
+            Self::EnableDBusFeature => "enable_dbus_feature",
+            Self::StartSessionBus => "start_session_bus",
+            Self::CheckCapabilities => "check_capabilities",
+        }
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::CheckPath => "pass an absolute path or a well-formed URI",
+            Self::RefreshLocation => "the item was moved or deleted, refresh the list it came from",
+            Self::RequestAccess => "ask for access to the item or the folders leading to it",
+            Self::AdjustPolicy => {
+                "allow the folder in the show policy, or don't offer to show the item"
+            }
+            Self::ShowFewerItems => "show fewer items at once, or raise the item and window limits",
+            Self::RunInDesktopSession => {
+                "run the application in the desktop session, not as a service or with sudo"
+            }
+            Self::InstallFileManager if cfg!(freedesktop) => {
+                "no FileManager1 provider, install Nautilus or Dolphin, or enable the fallback"
+            }
+            Self::InstallFileManager => "install a file manager, or enable the fallback",
+            Self::InstallApplication => "install an application that opens this type of file",
+            Self::GrantSandboxAccess => {
+                "grant access with the user-selected files entitlement or a bookmark"
+            }
+            Self::RetryLater => "try again once the file manager has started, or raise the timeout",
+            Self::EnableDBusFeature => "build with a D-Bus feature such as `rustbus` to show files",
+            Self::StartSessionBus => {
+                "no D-Bus session bus, log into a desktop session or set DBUS_SESSION_BUS_ADDRESS"
+            }
+            Self::CheckCapabilities => "not supported here, hide the action",
+        })
+    }
+}
+
+impl Error {
+    pub(crate) fn backend(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Backend(err.into())
+    }
+
+    /// Returns the `HRESULT` reported by the Windows API call that failed, if any.
+    ///
+    /// This is set for errors from shell functions such as `SHParseDisplayName` and
+    /// `SHOpenFolderAndSelectItems`. The value is the raw 32-bit code, so for example
+    /// `E_ACCESSDENIED` is `0x80070005_u32 as i32`. Only available on Windows.
+    #[cfg(windows)]
+    pub fn hresult(&self) -> Option<i32> {
+        match self {
+            Self::Backend(err) => err
+                .downcast_ref::<windows::core::Error>()
+                .map(|err| err.code().0),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the D-Bus error reply the call failed with, if any.
+    ///
+    /// For example, `org.freedesktop.DBus.Error.NoReply` means the file manager was found but
+    /// didn't reply, such as when it crashed. A missing file manager is reported as
+    /// [`Error::NoFileManager`] instead. Only available on Linux.
+    #[cfg(freedesktop)]
+    pub fn dbus_error_name(&self) -> Option<&str> {
+        self.method_error().map(|err| err.name.as_str())
+    }
+
+    /// Returns the environment the file manager was called in when this error happened.
+    ///
+    /// This is set for backend errors from calls that show items, so that the error can be
+    /// reported with what decides how files are shown, such as whether the process is in a
+    /// graphical session or a sandbox. The other variants don't carry any data, see
+    /// [`doctor`](crate::doctor) to get the same details for them. Only available on Linux.
+    #[cfg(freedesktop)]
+    pub fn environment(&self) -> Option<&crate::Environment> {
+        match self {
+            Self::Backend(err) => err
+                .downcast_ref::<crate::linux::EnvironmentError>()
+                .map(|err| &err.environment),
+            _ => None,
+        }
+    }
+
+    /// Returns the category of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::InvalidPath => ErrorKind::InvalidPath,
+            Self::NotFound => ErrorKind::NotFound,
+            Self::NoInteractiveSession | Self::SessionUserMismatch => ErrorKind::NoGraphicalSession,
+            Self::NoFileManager => ErrorKind::NoFileManager,
+            Self::NoApplication => ErrorKind::NoApplication,
+            Self::TimedOut => ErrorKind::TimedOut,
+            Self::TooManyItems => ErrorKind::TooManyItems,
+            Self::Unsupported => ErrorKind::Unsupported,
+            Self::PermissionDenied | Self::SandboxAccessDenied | Self::PolicyViolation => {
+                ErrorKind::PermissionDenied
+            }
+            Self::Backend(err) => {
+                let err = without_environment(&**err);
+                if let Some(err) = err.downcast_ref::<io::Error>() {
+                    match err.kind() {
+                        io::ErrorKind::TimedOut => return ErrorKind::TimedOut,
+                        io::ErrorKind::PermissionDenied => return ErrorKind::PermissionDenied,
+                        io::ErrorKind::Unsupported => return ErrorKind::Unsupported,
+                        _ => {}
+                    }
+                }
+                platform::backend_error_kind(err).unwrap_or(ErrorKind::Backend)
+            }
+        }
+    }
+
+    /// Returns a suggestion for fixing what caused this error, if there is one.
+    ///
+    /// This is meant for showing users something they can act on rather than only that showing
+    /// the file failed, such as that no file manager is installed. Backend errors get a hint from
+    /// their [kind](Self::kind) where possible, and on Linux, a backend error from a process that
+    /// has no session bus suggests starting one.
+    ///
+    /// ```no_run
+    /// if let Err(e) = showfile::show_path_in_file_manager("/home/alice/hello.txt") {
+    ///     match e.hint() {
+    ///         Some(hint) => eprintln!("Couldn't show the file: {e} ({hint})"),
+    ///         None => eprintln!("Couldn't show the file: {e}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn hint(&self) -> Option<Hint> {
+        Some(match self {
+            Self::InvalidPath => Hint::CheckPath,
+            Self::NotFound => Hint::RefreshLocation,
+            Self::PermissionDenied => Hint::RequestAccess,
+            Self::PolicyViolation => Hint::AdjustPolicy,
+            Self::TooManyItems => Hint::ShowFewerItems,
+            Self::NoInteractiveSession | Self::SessionUserMismatch => Hint::RunInDesktopSession,
+            Self::NoFileManager => Hint::InstallFileManager,
+            Self::NoApplication => Hint::InstallApplication,
+            Self::SandboxAccessDenied => Hint::GrantSandboxAccess,
+            Self::TimedOut => Hint::RetryLater,
+            // Calls that need D-Bus are unsupported without a D-Bus backend
+            Self::Unsupported
+                if cfg!(all(
+                    freedesktop,
+                    not(any(
+                        feature = "rustbus",
+                        feature = "zbus",
+                        feature = "gio",
+                        feature = "dbus",
+                        feature = "dbus-send"
+                    ))
+                )) =>
+            {
+                Hint::EnableDBusFeature
+            }
+            Self::Unsupported => Hint::CheckCapabilities,
+            Self::Backend(_) => match self.kind() {
+                ErrorKind::InvalidPath => Hint::CheckPath,
+                ErrorKind::NotFound => Hint::RefreshLocation,
+                ErrorKind::NoGraphicalSession => Hint::RunInDesktopSession,
+                ErrorKind::NoFileManager => Hint::InstallFileManager,
+                ErrorKind::NoApplication => Hint::InstallApplication,
+                ErrorKind::PermissionDenied => Hint::RequestAccess,
+                ErrorKind::TimedOut => Hint::RetryLater,
+                ErrorKind::TooManyItems => Hint::ShowFewerItems,
+                ErrorKind::Unsupported => Hint::CheckCapabilities,
+                ErrorKind::Backend => return self.backend_hint(),
+            },
+        })
+    }
+
+    /// Returns a hint for a backend error that couldn't be classified.
+    fn backend_hint(&self) -> Option<Hint> {
+        #[cfg(freedesktop)]
+        {
+            // Connecting to the bus failed, rather than a call on it
+            let session_bus_address = match self.environment() {
+                Some(environment) => environment.session_bus_address,
+                None => std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some(),
+            };
+            if !session_bus_address && self.method_error().is_none() {
+                return Some(Hint::StartSessionBus);
+            }
+        }
+        None
+    }
+}
+
+/// Returns the error reported by the platform, without the environment attached to it on Linux.
+pub(crate) fn without_environment<'a>(
+    err: &'a (dyn std::error::Error + Send + Sync + 'static),
+) -> &'a (dyn std::error::Error + Send + Sync + 'static) {
+    #[cfg(freedesktop)]
+    if let Some(err) = err.downcast_ref::<crate::linux::EnvironmentError>() {
+        return &*err.source;
+    }
+    err
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPath => f.write_str("invalid path or URI"),
+            Self::NotFound => f.write_str("file not found"),
+            Self::PermissionDenied => f.write_str("permission denied"),
+            Self::PolicyViolation => {
+                f.write_str("path is outside of the folders allowed by policy")
+            }
+            Self::NoInteractiveSession => f.write_str("no interactive session available"),
+            Self::NoFileManager => f.write_str("no file manager available"),
+            Self::NoApplication => f.write_str("no application available to open the file"),
+            Self::SandboxAccessDenied => {
+                f.write_str("path is not accessible from the app sandbox, missing entitlement")
+            }
+            Self::SessionUserMismatch => {
+                f.write_str("running as a different user than the desktop session")
+            }
+            Self::TimedOut => f.write_str("the file manager did not respond in time"),
+            Self::TooManyItems => f.write_str("too many items or folders to show at once"),
+            Self::Unsupported => f.write_str("not supported by this build or platform"),
+            Self::Backend(_) => f.write_str("file manager backend error"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Backend(err) => Some(&**err),
+            _ => None,
+        }
+    }
+}
+
+/// Formats `err` followed by its sources, separated by `: `, for reports that are plain strings.
+pub(crate) fn report(err: &(dyn std::error::Error + 'static)) -> String {
+    let mut report = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        report.push_str(": ");
+        report.push_str(&err.to_string());
+        source = err.source();
+    }
+    report
+}
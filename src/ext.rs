@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use crate::{Error, Selection};
+
+/// Adds a method to show a path in the file manager.
+///
+/// ```no_run
+/// use showfile::ShowfileExt;
+///
+/// std::path::PathBuf::from("/home/alice/hello.txt").show_in_file_manager()?;
+/// # Ok::<(), showfile::Error>(())
+/// ```
+pub trait ShowfileExt {
+    /// Tries to show this path in a file manager.
+    ///
+    /// See [`show_path_in_file_manager`](crate::show_path_in_file_manager) for details.
+    fn show_in_file_manager(&self) -> Result<(), Error>;
+}
+
+impl ShowfileExt for Path {
+    fn show_in_file_manager(&self) -> Result<(), Error> {
+        crate::show_path_in_file_manager(self)
+    }
+}
+
+/// Adds a method to show all the paths from an iterator in the file manager at once.
+///
+/// ```no_run
+/// use showfile::ShowfileIteratorExt;
+///
+/// ["/home/alice/hello.txt", "/home/alice/world.txt"]
+///     .into_iter()
+///     .show_all_in_file_manager()?;
+/// # Ok::<(), showfile::Error>(())
+/// ```
+pub trait ShowfileIteratorExt: Iterator {
+    /// Tries to show all the paths in a file manager at once.
+    ///
+    /// The paths are collected into a [`Selection`], see
+    /// [`show_selection_in_file_manager`](crate::show_selection_in_file_manager) for details.
+    fn show_all_in_file_manager(self) -> Result<(), Error>;
+}
+
+impl<I> ShowfileIteratorExt for I
+where
+    I: Iterator,
+    I::Item: AsRef<Path>,
+{
+    fn show_all_in_file_manager(self) -> Result<(), Error> {
+        crate::show_selection_in_file_manager(&self.collect::<Selection>())
+    }
+}
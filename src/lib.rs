@@ -1,16 +1,22 @@
 //! # showfile
 //!
 //! A simple API to show the location of a file in the local file manager (Explorer, Finder, etc.).
-//! Supported platforms are Windows, macOS, Linux.
+//! Supported platforms are Windows, macOS (including Mac Catalyst), Linux, and Redox OS.
 //!
 //! ## Usage
 //!
 //! ```no_run
-//! showfile::show_path_in_file_manager("C:\\Users\\Alice\\hello.txt");
-//! showfile::show_path_in_file_manager("/Users/Bob/hello.txt");
-//! showfile::show_uri_in_file_manager("file:///home/charlie/hello.txt");
+//! showfile::show_path_in_file_manager("C:\\Users\\Alice\\hello.txt")?;
+//! showfile::show_path_in_file_manager("/Users/Bob/hello.txt")?;
+//! showfile::show_uri_in_file_manager("file:///home/charlie/hello.txt")?;
+//! # Ok::<(), showfile::Error>(())
 //! ```
 //!
+//! These functions block while the file manager is called. They are also available from the
+//! [`blocking`] module, and the [`nonblocking`] module has versions that run in the background.
+//! With [`ShowfileExt`] and [`ShowfileIteratorExt`] imported, they can also be called as methods
+//! on paths and on iterators of paths.
+//!
 //! # Feature Flags
 //!
 //! On Linux, D-Bus is used to invoke the file manager. The D-Bus crate in use can be selected with
@@ -19,9 +25,88 @@
 //! - [`rustbus`](https://github.com/KillingSpark/rustbus) (default)
 //! - [`zbus`](https://dbus2.github.io/zbus/)
 //! - [`gio`](https://gtk-rs.org/gtk-rs-core/stable/latest/docs/gio/)
+//! - [`dbus`](https://github.com/diwic/dbus-rs), which links to the system's libdbus
+//! - `dbus-send`, which runs the `dbus-send` command, or `busctl` if that isn't installed, for
+//!   each call instead of depending on a D-Bus crate
+//!
+//! At most one of these flags can be enabled when building for Linux. The D-Bus crates are
+//! target-specific dependencies, so these flags do nothing on Windows, macOS, and Redox OS:
+//! nothing they select is compiled there, and enabling several of them, such as through different
+//! crates in the dependency graph, isn't an error. If only targeting those platforms, it can be
+//! left at the default, or all of them can be turned off with `default-features = false`, though
+//! Cargo still lists the D-Bus crates in `Cargo.lock`, as it does with the dependencies of every
+//! target. With none of them enabled, Linux builds only use
+//! the fallbacks that run other programs, such as `xdg-open`, and return [`Error::Unsupported`]
+//! for anything that needs D-Bus.
+//!
+//! The `strict` flag turns this into a build error instead, for applications that would rather
+//! find out at compile time that they can't show files over D-Bus on Linux.
+//!
+//! The `raw-window-handle` flag adds [`ShowOptions::parent_window`], which takes a window handle
+//! from [`raw-window-handle`](https://docs.rs/raw-window-handle/0.6). On Linux, it also pulls in
+//! `wayland-client` and `x11rb` to request activation tokens for the window.
+//!
+//! ## Static builds
+//!
+//! The `static` flag selects the profile for fully static binaries, such as those linked against
+//! musl: it enables `rustbus`, which speaks the D-Bus protocol over the session bus socket itself,
+//! and refuses to build together with `gio`. Nothing is then linked against glibc, libdbus, or
+//! any other system library, and nothing is loaded at runtime either: with `raw-window-handle`,
+//! activation tokens are still requested on X11 with the pure Rust `x11rb`, but not on Wayland,
+//! which would need `libwayland-client`. When no file manager is available over D-Bus, the
+//! fallback only spawns `xdg-open`.
+//!
+//! ```toml
+//! showfile = { version = "0.1", default-features = false, features = ["static"] }
+//! ```
+//!
+//! The `tokio` flag detects blocking functions being called from a Tokio runtime, and tells the
+//! runtime to move its other tasks off the thread, or logs a warning through the
+//! [`log`](https://docs.rs/log) facade, as set with [`AsyncCallPolicy`].
+//!
+//! The `metrics` flag records calls that show items through the
+//! [`metrics`](https://docs.rs/metrics/0.24) facade, so they go wherever the application installed
+//! a recorder:
 //!
-//! One of these flags must be specified to build the project. These flags do nothing on Windows
-//! and macOS. If only targeting those platforms, it can be left at the default.
+//! - `showfile_reveals_total`: counter of calls to show items
+//! - `showfile_reveals_succeeded_total`: counter of calls that succeeded, labeled with the
+//!   `backend` that showed the items, such as `org.freedesktop.FileManager1`, `xdg-open` or
+//!   `SHOpenFolderAndSelectItems`
+//! - `showfile_reveals_failed_total`: counter of calls that failed, labeled with the
+//!   `error_kind`, such as `not_found`
+//! - `showfile_reveal_duration_seconds`: histogram of how long calls took, labeled with the
+//!   `result`, `ok` or `error`
+//!
+//! The `opentelemetry` flag wraps each call that shows items in a `showfile.reveal` span, started
+//! with the global tracer provider of [`opentelemetry`](https://docs.rs/opentelemetry/0.31) as a
+//! child of the current context, so that it is exported with the application's other spans. It
+//! has the `showfile.platform` attribute, and either `showfile.backend` and `showfile.outcome`,
+//! `shown` or `degraded` when there are [warnings](ShowOutcome::warnings), or
+//! `showfile.outcome` set to `error` with `showfile.error_kind` and an error status.
+//!
+//! The `rfd` flag adds `show_file_handle_in_file_manager` and related methods, which take the
+//! `FileHandle` returned by the async dialogs of [`rfd`](https://docs.rs/rfd/0.15). None of its
+//! backend features are enabled, so the application picks them as usual when depending on `rfd`.
+//!
+//! ## Command-line tool
+//!
+//! The `showfile` binary shows the paths and URIs given as arguments, for scripts and the terminal.
+//! With `-0`, it reads NUL-separated paths from standard input, so that the output of
+//! `find -print0` or `fd -0` can be piped in, and `--files-from` reads a list of them from a file.
+//! All of them are shown as one [`Selection`], within the same limits on items and folder windows:
+//!
+//! ```sh
+//! fd -0 -e pdf . ~/Documents | showfile -0
+//! ```
+//!
+//! With `--json`, it prints the outcome as a JSON object on one line instead: the backend that
+//! showed the items, whether they are selected, the application that handled them, and any
+//! warnings or errors, for scripts and bug reports.
+//!
+//! The other flags cover the rest of the crate, so that every way of showing items can be tried
+//! from the terminal: `--open-dir`, `--properties`, `--rename`, `--no-activate`, and
+//! `--backend=<name>` to pick the [backends](ShowOptions::backends) to try in order. See
+//! `showfile --help` for details.
 //!
 //! ## Details
 //!
@@ -29,213 +114,151 @@
 //!
 //! - Windows: [`SHOpenFolderAndSelectItems`](https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/nf-shlobj_core-shopenfolderandselectitems)
 //! - macOS: [`NSWorkspace activateFileViewerSelectingURLs:`](https://developer.apple.com/documentation/appkit/nsworkspace/1524549-activatefileviewerselecting)
-//! - Linux: [`org.freedesktop.FileManager1.ShowItems`](https://www.freedesktop.org/wiki/Specifications/file-manager-interface/)
-
-use std::path::Path;
-
-#[cfg(not(any(
-    all(feature = "rustbus", not(feature = "zbus"), not(feature = "gio")),
-    all(not(feature = "rustbus"), feature = "zbus", not(feature = "gio")),
-    all(not(feature = "rustbus"), not(feature = "zbus"), feature = "gio")
-)))]
-compile_error!("only one of `rustbus`, `zbus`, or `gio` must be selected");
-
-#[cfg_attr(target_os = "macos", link(name = "AppKit", kind = "framework"))]
-extern "C" {}
-
-#[cfg(target_os = "macos")]
-use objc::{class, msg_send, sel, sel_impl};
-#[cfg(target_os = "macos")]
-#[allow(non_camel_case_types)]
-type id = *mut objc::runtime::Object;
-#[cfg(target_os = "macos")]
-#[allow(non_upper_case_globals)]
-const nil: id = std::ptr::null_mut();
-
-#[cfg(target_os = "macos")]
-unsafe fn show_nsurl_in_file_manager(nsurl: id) {
-    let ws: id = msg_send![class!(NSWorkspace), sharedWorkspace];
-    let urls: id = msg_send![class!(NSArray), arrayWithObject:nsurl];
-    let urls: id = msg_send![urls, autorelease];
-    let _: () = msg_send![ws, activateFileViewerSelectingURLs:urls];
-}
+//!   (on Mac Catalyst, AppKit is loaded at runtime, falling back to `open -R`, and Finder is
+//!   asked through `osascript` when AppKit fails)
+//! - Linux, the BSDs, illumos and Solaris: [`org.freedesktop.FileManager1.ShowItems`](https://www.freedesktop.org/wiki/Specifications/file-manager-interface/)
+//! - Redox OS: the Orbital `file_manager`, which opens the containing folder
+//!
+//! On other targets, such as WASI, the crate still builds, and every call that would show or open
+//! something returns [`Error::Unsupported`].
 
-#[cfg(all(not(target_os = "macos"), not(windows), feature = "gio"))]
-unsafe fn gdbus_show_uri_in_file_manager(uri: *const std::ffi::c_char) {
-    use std::ptr::{null, null_mut};
+use std::{ffi::OsStr, path::Path};
 
-    let bus = gio_sys::g_bus_get_sync(gio_sys::G_BUS_TYPE_SESSION, null_mut(), null_mut());
-    if bus.is_null() {
-        return;
-    }
-    let uris = [uri, null()];
-    let args = glib_sys::g_variant_new(
-        b"(^ass)\0".as_ptr() as *const _,
-        uris.as_ptr(),
-        b"\0".as_ptr(),
-    );
-    let ret = gio_sys::g_dbus_connection_call_sync(
-        bus,
-        b"org.freedesktop.FileManager1\0".as_ptr() as *const _,
-        b"/org/freedesktop/FileManager1\0".as_ptr() as *const _,
-        b"org.freedesktop.FileManager1\0".as_ptr() as *const _,
-        b"ShowItems\0".as_ptr() as *const _,
-        args,
-        null(),
-        0,
-        -1,
-        null_mut(),
-        null_mut(),
-    );
-    if !ret.is_null() {
-        glib_sys::g_variant_unref(ret);
-    }
-    gobject_sys::g_object_unref(bus as *mut _);
-}
+#[cfg(all(
+    feature = "strict",
+    freedesktop,
+    not(any(
+        feature = "rustbus",
+        feature = "zbus",
+        feature = "gio",
+        feature = "dbus",
+        feature = "dbus-send"
+    ))
+))]
+compile_error!(
+    "`strict` requires one of `rustbus`, `zbus`, `gio`, `dbus`, or `dbus-send` on Linux"
+);
+
+#[cfg(all(
+    freedesktop,
+    any(
+        all(
+            feature = "rustbus",
+            any(
+                feature = "zbus",
+                feature = "gio",
+                feature = "dbus",
+                feature = "dbus-send"
+            )
+        ),
+        all(
+            feature = "zbus",
+            any(feature = "gio", feature = "dbus", feature = "dbus-send")
+        ),
+        all(feature = "gio", any(feature = "dbus", feature = "dbus-send")),
+        all(feature = "dbus", feature = "dbus-send")
+    )
+))]
+compile_error!("only one of `rustbus`, `zbus`, `gio`, `dbus`, or `dbus-send` can be selected");
+
+#[cfg(all(freedesktop, feature = "static", feature = "gio"))]
+compile_error!("the `static` profile uses `rustbus` and can't be combined with `gio`");
+
+mod args;
+mod backend;
+pub use backend::Backend;
+pub mod blocking;
+mod build_info;
+mod cache;
+pub use build_info::{BackendInfo, BuildInfo, Library};
+mod capabilities;
+pub use capabilities::Capabilities;
+mod config;
+mod dir;
+mod doctor;
+pub use doctor::Diagnostics;
+mod error;
+pub use error::{Error, ErrorKind, Hint};
+mod ext;
+pub use ext::{ShowfileExt, ShowfileIteratorExt};
+mod location;
+pub use location::LocationStatus;
+pub mod nonblocking;
+mod options;
+pub use options::{Automount, ShowOptions};
+mod outcome;
+pub use outcome::{Action, Handler, ShowOutcome, Warning};
+mod policy;
+pub use policy::ShowPolicy;
+mod process;
+pub use process::ChildEnv;
+pub mod raw;
+mod sandbox;
+pub use sandbox::{Portal, SandboxKind};
+mod selection;
+pub use selection::{SelectedItem, Selection};
+mod session;
+pub use session::RevealSession;
+mod stats;
+pub use stats::Stats;
+mod telemetry;
+mod threads;
+pub use threads::{AsyncCallPolicy, ThreadOptions};
+mod uri;
+pub use uri::{FileUri, FileUriError, UriCodec, UriEncoder};
+
+#[cfg(freedesktop)]
+mod linux;
+#[cfg(freedesktop)]
+use linux as platform;
+#[cfg(freedesktop)]
+pub use linux::Environment;
+#[cfg(any(target_os = "macos", target_abi = "macabi"))]
+mod macos;
+#[cfg(any(target_os = "macos", target_abi = "macabi"))]
+use macos as platform;
+#[cfg(target_os = "redox")]
+mod redox;
+#[cfg(target_os = "redox")]
+use redox as platform;
+#[cfg(windows)]
+mod win32;
+#[cfg(windows)]
+use win32 as platform;
+#[cfg(unsupported)]
+mod unsupported;
+#[cfg(unsupported)]
+use unsupported as platform;
 
 /// Tries to show `path` in a file manager.
 ///
-/// The path shold be an absolute path. Support for relative paths is platform-specific and may
-/// fail silently or cause the file manager to display an error message.
+/// The path shold be an absolute path. Support for relative paths is platform-specific and may fail
+/// or cause the file manager to display an error message. On Windows, verbatim (`\\?\`) paths and
+/// paths longer than `MAX_PATH` are supported, and paths on a disconnected network drive mapping
+/// are shown using the UNC path of the share. Entries inside a ZIP archive, such as
+/// `C:\data\archive.zip\inner\file.txt`, are shown in the archive without extracting it. When
+/// running as an MSIX packaged app, paths in the virtualized `AppData` folders are mapped to their
+/// real location in the package's `LocalCache` folder, since the file manager runs outside of the
+/// package. On macOS, apps running in the App Sandbox get [`Error::SandboxAccessDenied`] for paths
+/// they have not been granted access to, see `show_bookmark_in_file_manager`.
 ///
-/// This function may do nothing at all depending on the current system. The result is
-/// platform-specific if the path does not exist, is inaccessible, or if the file manager is
-/// unavailable. The file manager may display an error message if a non-existent path is provided.
+/// An error is returned if the path could not be converted or if the platform API reported a
+/// failure. A successful return does not guarantee that a file manager window was shown. The
+/// result is platform-specific if the path does not exist, is inaccessible, or if the file manager
+/// is unavailable. The file manager may display an error message if a non-existent path is
+/// provided.
 ///
 /// This function can block, so take care when calling from GUI programs. In those cases it should
 /// be called on another thread, or called using your runtime's API to wrap blocking calls such as
 /// [`tokio::task::spawn_blocking`](https://docs.rs/tokio/latest/tokio/task/fn.spawn_blocking.html)
 /// or [`gio::spawn_blocking`](https://gtk-rs.org/gtk-rs-core/stable/latest/docs/gio/fn.spawn_blocking.html).
-pub fn show_path_in_file_manager(path: impl AsRef<Path>) {
-    #[cfg(windows)]
-    unsafe {
-        use std::{
-            borrow::Cow,
-            path::{Component, Prefix},
-        };
-        use windows::{
-            core::{Result, HSTRING},
-            Win32::{System::Com::*, UI::Shell::*},
-        };
-
-        struct ComHandle(());
-        impl ComHandle {
-            fn new() -> Result<Self> {
-                unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED)? };
-                Ok(Self(()))
-            }
-        }
-        impl Drop for ComHandle {
-            fn drop(&mut self) {
-                unsafe {
-                    CoUninitialize();
-                }
-            }
-        }
-        std::thread_local! { static COM_HANDLE: Result<ComHandle> = ComHandle::new(); }
-        COM_HANDLE.with(|r| r.as_ref().map(|_| ()).unwrap());
-
-        let path = Cow::Borrowed(path.as_ref());
-
-        // SHParseDisplayName seems to fail with UNC paths, so convert them back
-        let mut components = path.components();
-        let path = match components.next() {
-            Some(Component::Prefix(prefix)) => match prefix.kind() {
-                Prefix::VerbatimUNC(server, share) => Cow::Owned(
-                    Path::new("\\\\").join(Path::new(server).join(share).join(components)),
-                ),
-                Prefix::VerbatimDisk(disk) => {
-                    let prefix = [disk, b':', b'\\'];
-                    let prefix = std::ffi::OsStr::from_encoded_bytes_unchecked(&prefix);
-                    Cow::Owned(Path::new(prefix).join(components))
-                }
-                Prefix::Verbatim(prefix) => {
-                    Cow::Owned(Path::new("\\\\").join(Path::new(prefix).join(components)))
-                }
-                _ => path,
-            },
-            _ => path,
-        };
-        let mut idlist = std::ptr::null_mut();
-        let res = SHParseDisplayName(
-            &HSTRING::from(path.as_os_str()),
-            None::<&IBindCtx>,
-            &mut idlist,
-            0,
-            None,
-        );
-        if res.is_ok() && !idlist.is_null() {
-            let _ = SHOpenFolderAndSelectItems(idlist, None, 0);
-            CoTaskMemFree(Some(idlist as *const _));
-        }
-    }
-
-    #[cfg(target_os = "macos")]
-    unsafe {
-        let path = path.as_ref().as_os_str().as_encoded_bytes();
-        let s: id = msg_send![class!(NSString), alloc];
-        let s: id = msg_send![
-            s,
-            initWithBytes:path.as_ptr()
-            length:path.len()
-            encoding:4 as id
-        ];
-        let s: id = msg_send![s, autorelease];
-        let url: id = msg_send![class!(NSURL), fileURLWithPath:s];
-        if url != nil {
-            show_nsurl_in_file_manager(url);
-        }
-    }
-
-    #[cfg(all(not(windows), not(target_os = "macos"), not(feature = "gio")))]
-    {
-        use std::path::Component;
-
-        let path = path.as_ref();
-        if path.is_relative() {
-            return;
-        }
-        let mut uri = String::with_capacity(path.as_os_str().as_encoded_bytes().len() + 7);
-        uri.push_str("file://");
-        let mut components = path.components().peekable();
-        if components.peek().is_none() {
-            return;
-        }
-        while let Some(component) = components.next() {
-            match component {
-                Component::RootDir => uri.push('/'),
-                Component::Prefix(_) => return,
-                _ => {
-                    let component = component.as_os_str().as_encoded_bytes();
-                    uri.push_str(&urlencoding::encode_binary(component));
-                    if components.peek().is_some() {
-                        uri.push('/');
-                    }
-                }
-            }
-        }
-        show_uri_in_file_manager(&uri);
-    }
-
-    #[cfg(all(not(windows), not(target_os = "macos"), feature = "gio"))]
-    unsafe {
-        let path = path.as_ref().as_os_str().as_encoded_bytes().to_vec();
-        let path = std::ffi::CString::new(path).unwrap_or_else(|e| {
-            let pos = e.nul_position();
-            let mut uri = e.into_vec();
-            uri.truncate(pos);
-            std::ffi::CString::new(uri).unwrap()
-        });
-        let file = gio_sys::g_file_new_for_path(path.as_ptr());
-        let uri = gio_sys::g_file_get_uri(file);
-        if !uri.is_null() {
-            if uri.read() != 0 {
-                gdbus_show_uri_in_file_manager(uri);
-            }
-            glib_sys::g_free(uri as *mut _);
-        }
-        gobject_sys::g_object_unref(file as *mut _);
-    }
+/// On Windows, the shell is called from an internal thread with its own COM apartment, so it
+/// doesn't matter how COM was initialized on the calling thread. The [`nonblocking`] module has
+/// versions of these functions that run in the background and can be awaited.
+/// With the `tokio` feature, calls made from a Tokio runtime are handled as set with
+/// [`ThreadOptions::async_calls`].
+pub fn show_path_in_file_manager(path: impl AsRef<Path>) -> Result<(), Error> {
+    ShowOptions::new().show_path(path).map(drop)
 }
 
 /// Tries to show `uri` in a file manager.
@@ -244,78 +267,474 @@ pub fn show_path_in_file_manager(path: impl AsRef<Path>) {
 /// manager may be able to browse network URIs such as with the ftp://` or `smb://` schemes. The
 /// file manager may fail silently or display an error message if given a non-supported URI scheme.
 ///
-/// This function may do nothing at all depending on the current system. The result is
-/// platform-specific if the path does not exist, is inaccessible, or if the file manager is
-/// unavailable. The file manager may display an error message if a non-existent path is provided.
+/// An error is returned if the URI could not be converted or if the platform API reported a
+/// failure. A successful return does not guarantee that a file manager window was shown. The
+/// result is platform-specific if the path does not exist, is inaccessible, or if the file manager
+/// is unavailable. The file manager may display an error message if a non-existent path is
+/// provided.
 ///
 /// This function can block, so take care when calling from GUI programs. In those cases it should
 /// be called on another thread, or called using your runtime's API to wrap blocking calls such as
 /// [`tokio::task::spawn_blocking`](https://docs.rs/tokio/latest/tokio/task/fn.spawn_blocking.html)
 /// or [`gio::spawn_blocking`](https://gtk-rs.org/gtk-rs-core/stable/latest/docs/gio/fn.spawn_blocking.html).
-pub fn show_uri_in_file_manager(uri: impl AsRef<str>) {
-    #[cfg(windows)]
-    show_path_in_file_manager(Path::new(uri.as_ref()));
-
-    #[cfg(target_os = "macos")]
-    unsafe {
-        let uri = uri.as_ref();
-        let s: id = msg_send![class!(NSString), alloc];
-        let s: id = msg_send![
-            s,
-            initWithBytes:uri.as_ptr()
-            length:uri.len()
-            encoding:4 as id
-        ];
-        let s: id = msg_send![s, autorelease];
-        let url: id = msg_send![class!(NSURL), URLWithString:s];
-        if url != nil {
-            show_nsurl_in_file_manager(url);
-        }
-    }
+pub fn show_uri_in_file_manager(uri: impl AsRef<str>) -> Result<(), Error> {
+    ShowOptions::new().show_uri(uri).map(drop)
+}
 
-    #[cfg(all(not(target_os = "macos"), not(windows), feature = "rustbus"))]
-    {
-        if let Ok(mut bus) = rustbus::RpcConn::session_conn(rustbus::connection::Timeout::Infinite)
-        {
-            let uri = uri.as_ref();
-            let mut msg = rustbus::MessageBuilder::new()
-                .call("ShowItems")
-                .on("/org/freedesktop/FileManager1")
-                .with_interface("org.freedesktop.FileManager1")
-                .at("org.freedesktop.FileManager1")
-                .build();
-            msg.body.push_param([uri].as_slice()).unwrap();
-            msg.body.push_param("").unwrap();
-            if let Ok(ctx) = bus.send_message(&mut msg) {
-                let _ = ctx.write_all();
-            }
-            drop(bus);
-        }
-    }
+/// Tries to open `path` with the default application for its type.
+///
+/// This is the companion to [`show_path_in_file_manager`] for "Open" actions, and the path is
+/// handled the same way. On Windows, this uses `ShellExecuteEx`. On macOS, this uses
+/// `NSWorkspace openURL:`. On Linux, this uses GIO's default app for the file's type when the
+/// `gio` feature is enabled, or `xdg-open` otherwise. Returns [`Error::NoApplication`] if nothing
+/// is registered to open the file.
+///
+/// ```no_run
+/// showfile::open_path_with_default_app("/home/alice/report.pdf")?;
+/// # Ok::<(), showfile::Error>(())
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn open_path_with_default_app(path: impl AsRef<Path>) -> Result<(), Error> {
+    threads::blocking(|| platform::open_path(path.as_ref()))
+}
 
-    #[cfg(all(not(target_os = "macos"), not(windows), feature = "zbus"))]
-    {
-        let uri = uri.as_ref();
-        if let Ok(bus) = zbus::blocking::Connection::session() {
-            let _ = bus.call_method(
-                Some("org.freedesktop.FileManager1"),
-                "/org/freedesktop/FileManager1",
-                Some("org.freedesktop.FileManager1"),
-                "ShowItems",
-                &([uri].as_slice(), ""),
-            );
-        }
-    }
+/// Tries to show the file manager's properties dialog for `path`.
+///
+/// The path is handled the same way as for [`show_path_in_file_manager`]. On Windows, this uses
+/// `SHObjectProperties`, which shows the dialog from the calling process, so it closes when the
+/// process exits. On Linux, this calls `org.freedesktop.FileManager1.ShowItemProperties`, and
+/// returns [`Error::NoFileManager`] if nothing provides it. Returns [`Error::Unsupported`] on
+/// macOS and Redox OS, see [`Capabilities::properties_dialog`].
+///
+/// ```no_run
+/// showfile::show_properties("/home/alice/report.pdf")?;
+/// # Ok::<(), showfile::Error>(())
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn show_properties(path: impl AsRef<Path>) -> Result<(), Error> {
+    ShowOptions::new().show_properties(path).map(drop)
+}
+
+/// Tries to open `path` with a specific application.
+///
+/// This is for "Open with" actions next to [`show_path_in_file_manager`], and the path is handled
+/// the same way as for [`open_path_with_default_app`]. The application is identified by:
+///
+/// - On Windows, the path or name of its executable, such as `mspaint.exe`.
+/// - On macOS, its bundle identifier, such as `org.gimp.gimp-2.10`.
+/// - On Linux, its desktop file ID, with or without the `.desktop` suffix, such as
+///   `org.gimp.GIMP`. The application is launched with its desktop entry's `Exec` line.
+///
+/// Returns [`Error::NoApplication`] if the application can't be found or launched.
+///
+/// ```no_run
+/// # let app = "org.gimp.GIMP";
+/// showfile::open_path_with(app, "/home/alice/photo.png")?;
+/// # Ok::<(), showfile::Error>(())
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn open_path_with(app: &str, path: impl AsRef<Path>) -> Result<(), Error> {
+    threads::blocking(|| platform::open_path_with(app, path.as_ref()))
+}
+
+/// Tries to show several URIs in a file manager at once.
+///
+/// On Linux, all URIs are passed to the file manager in a single call, which usually opens one
+/// window per folder with the items selected. On Windows, one Explorer window is opened for each
+/// folder, with the items in that folder selected. On macOS, Finder selects all the items at once,
+/// unless another file viewer is configured, in which case they are shown one at a time. Does
+/// nothing if `uris` is empty. [`Error::TooManyItems`] is returned without showing anything if
+/// there are more than 50 URIs or they are in more than 10 folders, which can be changed with
+/// [`ShowOptions::item_limit`] and [`ShowOptions::window_limit`].
+///
+/// ```no_run
+/// showfile::show_uris_in_file_manager([
+///     "file:///home/alice/hello.txt",
+///     "file:///home/alice/world.txt",
+/// ])?;
+/// # Ok::<(), showfile::Error>(())
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn show_uris_in_file_manager<I>(uris: I) -> Result<(), Error>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    ShowOptions::new().show_uris(uris).map(drop)
+}
+
+/// Tries to show a file picked in an [`rfd`](https://docs.rs/rfd/0.15) dialog in a file manager.
+///
+/// The path of the handle is shown as in [`show_path_in_file_manager`], without converting it to
+/// a string and back. Requires the `rfd` feature.
+///
+/// ```no_run
+/// # async fn save() -> Result<(), showfile::Error> {
+/// if let Some(file) = rfd::AsyncFileDialog::new().save_file().await {
+///     // Write the file...
+///     showfile::show_file_handle_in_file_manager(&file)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+#[cfg(feature = "rfd")]
+pub fn show_file_handle_in_file_manager(handle: &rfd::FileHandle) -> Result<(), Error> {
+    ShowOptions::new().show_file_handle(handle).map(drop)
+}
+
+/// Tries to show the running executable in a file manager, with it selected.
+///
+/// This is meant for "Show install folder" menu items. Symbolic links to the executable are
+/// resolved, so the real install location is shown. On macOS, the app bundle is selected instead
+/// of the executable inside it. [`Error::NotFound`] is returned if the executable was deleted or
+/// replaced while running.
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn show_current_exe() -> Result<(), Error> {
+    ShowOptions::new().show_current_exe().map(drop)
+}
+
+/// Tries to show the working directory of the process in a file manager, selected in the folder
+/// containing it.
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn show_current_dir() -> Result<(), Error> {
+    ShowOptions::new().show_current_dir().map(drop)
+}
+
+/// Tries to show the most recently modified file in `dir` in a file manager, with it selected.
+///
+/// This is useful after saving a file with a generated name, such as a download or a screenshot.
+/// Only files are considered, following symbolic links. [`Error::NotFound`] is returned if `dir`
+/// doesn't exist or has no files in it.
+///
+/// ```no_run
+/// showfile::show_latest_in_dir("/home/alice/Pictures/Screenshots")?;
+/// # Ok::<(), showfile::Error>(())
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn show_latest_in_dir(dir: impl AsRef<Path>) -> Result<(), Error> {
+    ShowOptions::new().show_latest_in_dir(dir).map(drop)
+}
 
-    #[cfg(all(not(target_os = "macos"), not(windows), feature = "gio"))]
-    unsafe {
-        let uri = uri.as_ref();
-        let uri = std::ffi::CString::new(uri).unwrap_or_else(|e| {
-            let pos = e.nul_position();
-            let mut uri = e.into_vec();
-            uri.truncate(pos);
-            std::ffi::CString::new(uri).unwrap()
-        });
-        gdbus_show_uri_in_file_manager(uri.as_ptr());
+/// Tries to show the items in `dir` whose names match the glob `pattern` in a file manager, with
+/// all of them selected.
+///
+/// The pattern is matched against the names of the files and folders directly inside `dir`, and
+/// can't contain path separators. `*` matches any run of characters, `?` any single character, and
+/// `[...]` any character in the set, such as `[0-9]` or `[!._]`. Names starting with `.` are only
+/// matched by patterns that also start with `.`. At most [`ShowOptions::match_limit`] items, and
+/// no more than [`ShowOptions::item_limit`], are shown. [`Error::NotFound`] is returned if `dir`
+/// doesn't exist or nothing matches, and [`Error::InvalidPath`] if the pattern is empty or
+/// contains a path separator.
+///
+/// ```no_run
+/// showfile::show_matching("target/release", "report-*.pdf")?;
+/// # Ok::<(), showfile::Error>(())
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn show_matching(dir: impl AsRef<Path>, pattern: &str) -> Result<(), Error> {
+    ShowOptions::new().show_matching(dir, pattern).map(drop)
+}
+
+/// Tries to show a [`Selection`] of paths and URIs in a file manager at once.
+///
+/// Paths are handled as in [`show_path_in_file_manager`] and URIs as in
+/// [`show_uri_in_file_manager`], then all items are shown together as in
+/// [`show_uris_in_file_manager`]. Does nothing if the selection is empty. Items that don't exist
+/// or can't be converted are skipped, and an error is only returned if none could be shown. Use
+/// [`ShowOptions::show_selection`] to find out which items were skipped.
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn show_selection_in_file_manager(selection: &Selection) -> Result<(), Error> {
+    ShowOptions::new().show_selection(selection).map(drop)
+}
+
+/// Registers `backend` to be tried for every call that shows items, by priority.
+///
+/// The built-in backends have a priority of `0`: backends with a higher priority are tried before
+/// them, and the others after them if they fail, from the highest priority to the lowest, and in
+/// the order they were registered for the same priority. The first backend that succeeds shows the
+/// items, and the error of the first one that fails with something other than
+/// [`Error::Unsupported`] is returned if none does. This applies to every way of showing items,
+/// including [`ShowOptions`] and [`RevealSession`], but not to [`show_properties`] or to opening
+/// files. The items are checked against the options before any backend is tried.
+///
+/// This is meant to be called once at startup. See [`Backend`] for an example.
+pub fn register_backend(priority: i32, backend: impl Backend + 'static) {
+    backend::register(priority, std::sync::Arc::new(backend));
+}
+
+/// Shows a [`Selection`] of paths and URIs with `backend` alone.
+///
+/// Neither the built-in backends nor those registered with [`register_backend`] are tried, and
+/// `backend` is called directly rather than through a trait object. An application that only shows
+/// items this way leaves the built-in backends out of its binary, since nothing calls them. The
+/// items are checked as in [`show_selection_in_file_manager`], and nothing is shown if the
+/// selection is empty.
+///
+/// ```no_run
+/// # use showfile::{Backend, Error, Selection, ShowOptions, ShowOutcome};
+/// # #[derive(Clone, Copy)]
+/// # struct RemoteDesktop;
+/// # impl Backend for RemoteDesktop {
+/// #     fn name(&self) -> &str { "remote-desktop" }
+/// #     fn show(&self, _: &Selection, _: &ShowOptions) -> Result<ShowOutcome, Error> {
+/// #         Ok(ShowOutcome::default())
+/// #     }
+/// # }
+/// let mut selection = Selection::new();
+/// selection.path("/home/alice/hello.txt");
+/// showfile::show_with_backend(RemoteDesktop, &selection)?;
+/// # Ok::<(), Error>(())
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn show_with_backend<B: Backend + 'static>(
+    backend: B,
+    selection: &Selection,
+) -> Result<(), Error> {
+    ShowOptions::new()
+        .show_with_backend(backend, selection)
+        .map(drop)
+}
+
+/// Removes the backends named `name` that were registered with [`register_backend`].
+///
+/// Returns whether any was registered.
+pub fn unregister_backend(name: &str) -> bool {
+    backend::unregister(name)
+}
+
+/// Checks whether a file manager is available to show files in.
+///
+/// On Linux, this checks whether an `org.freedesktop.FileManager1` provider is running on the
+/// session bus, or can be started on demand by it (as Dolphin is on KDE), and can be called, which
+/// a strictly confined Snap can't do. The `xdg-open` fallback is not considered. On Windows, this
+/// checks whether the process is running in an interactive session. On macOS, a file manager is
+/// always available.
+pub fn file_manager_available() -> bool {
+    platform::file_manager_available()
+}
+
+/// Returns how this crate was built: its version, the Cargo features that were enabled, and the
+/// backends that were compiled in with the crates they use.
+///
+/// This tells whether a binary can show files a certain way at all, such as whether it was built
+/// with a D-Bus backend on Linux, or only runs `xdg-open`. It is also part of the report returned
+/// by [`doctor`].
+///
+/// ```
+/// let info = showfile::build_info();
+/// println!("{info}");
+/// # assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+/// ```
+pub fn build_info() -> BuildInfo {
+    BuildInfo::get()
+}
+
+/// Checks the environment the file manager is called in, and reports what was found.
+///
+/// This is meant to be included in bug reports when showing a file didn't work, so that the
+/// environment doesn't have to be asked about. On Linux, the session type and desktop environment
+/// are read from the environment, and the session bus is asked which process provides
+/// `org.freedesktop.FileManager1` and which XDG desktop portals are available. On Windows, COM is
+/// initialized on the thread that makes shell calls. On macOS, Finder is checked to be running.
+/// The sandbox, whether a file manager is available, and its [capabilities](capabilities) are
+/// reported on every platform. Checks that fail are listed in [`Diagnostics::problems`] instead of
+/// returning an error.
+///
+/// ```no_run
+/// eprintln!("Please include this in your bug report:\n{}", showfile::doctor());
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn doctor() -> Diagnostics {
+    Diagnostics::probe()
+}
+
+/// Returns how often items were shown since the process started: how many calls failed, how many
+/// only opened the folders or fell back to `xdg-open`, and which backends showed the items.
+/// Properties dialogs shown with [`show_properties`] aren't counted.
+///
+/// The counters are kept for every call, so that a long-running application can notice that
+/// items are not shown the way it expects, such as when no file manager is running for the user
+/// and every call falls back to `xdg-open`, and adapt, such as by telling the user or showing the
+/// items some other way.
+///
+/// ```no_run
+/// let stats = showfile::stats();
+/// if stats.fallbacks > 0 && stats.fallbacks == stats.reveals - stats.failed {
+///     eprintln!("No file manager is running, files are only shown in their folders");
+/// }
+/// ```
+pub fn stats() -> Stats {
+    Stats::get()
+}
+
+/// Detects the environment again the next time it is needed.
+///
+/// What doesn't change while the process runs is detected once and kept for later calls: the
+/// user's configuration file, and on Linux, the desktop environment, the sandbox, and the user
+/// that owns the desktop session when running under `sudo` or `pkexec`. An application that
+/// changes any of these, such as by setting `XDG_CURRENT_DESKTOP` or editing the configuration
+/// file, calls this for the change to be seen. Whether a file manager is running is still asked
+/// of the session bus when needed, since it can start and stop at any time.
+pub fn refresh() {
+    cache::refresh();
+}
+
+/// Returns what the file manager on the current platform supports.
+///
+/// On Linux, this depends on the `org.freedesktop.FileManager1` provider, which is introspected
+/// to find the methods it implements. If no provider is available, only what the `xdg-open`
+/// fallback can do is reported. On macOS, several items can only be selected at once in Finder,
+/// not in an alternative set as the `NSFileViewer` default.
+///
+/// ```no_run
+/// if showfile::capabilities().multi_select {
+///     // Offer "Show all in folder"
+/// }
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn capabilities() -> Capabilities {
+    platform::capabilities()
+}
+
+/// Checks whether the file manager can browse URIs with `scheme`, such as `smb` or `sftp`.
+///
+/// This can be used before offering to show a network location. The `file` scheme is always
+/// supported. On Linux, this checks the schemes GIO supports, which are provided by GVfs and used
+/// by most file managers. On Windows, this checks whether the scheme is registered to open in
+/// Explorer. On macOS, this asks Launch Services whether Finder, or the alternative set as the
+/// `NSFileViewer` default, opens the scheme.
+///
+/// ```no_run
+/// if showfile::supports_uri_scheme("smb") {
+///     showfile::show_uri_in_file_manager("smb://nas.local/share/report.pdf")?;
+/// }
+/// # Ok::<(), showfile::Error>(())
+/// ```
+pub fn supports_uri_scheme(scheme: &str) -> bool {
+    if !uri::is_scheme(scheme) {
+        return false;
     }
+    scheme.eq_ignore_ascii_case("file")
+        || platform::supports_uri_scheme(&scheme.to_ascii_lowercase())
+}
+
+/// Finds out where `location`, a path or a URI, is stored, and whether it can be reached.
+///
+/// This can be used before showing an item, to decide whether to show it, mount its volume first
+/// with [`ShowOptions::automount`], or tell the user to connect the drive it is on. Only the
+/// volume is checked for paths on local disks, not whether the item exists. A URI is any string
+/// that starts with a scheme of at least two characters followed by `:`, and `file:` URIs are
+/// checked as paths. [`Error::InvalidPath`] is returned for malformed `file:` URIs.
+///
+/// On Linux, the file system the path is on is looked up in `/proc/self/mounts`, and URIs of
+/// locations that GVfs mounts are checked for a mount. On Windows, the type of the drive is
+/// checked, and files that cloud storage providers such as OneDrive haven't downloaded are
+/// reported as placeholders. On macOS, the volume is checked with `NSURL` resource values, and
+/// iCloud Drive files that haven't been downloaded are reported as placeholders.
+///
+/// ```no_run
+/// use showfile::LocationStatus;
+///
+/// match showfile::location_status("/media/alice/USB/report.pdf")? {
+///     LocationStatus::RemovableMissing => eprintln!("Connect the drive to show the file"),
+///     _ => showfile::show_path_in_file_manager("/media/alice/USB/report.pdf")?,
+/// }
+/// # Ok::<(), showfile::Error>(())
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn location_status(location: impl AsRef<OsStr>) -> Result<LocationStatus, Error> {
+    location::status(location.as_ref())
+}
+
+/// Checks whether the current user can reach `path`, without opening it.
+///
+/// Handing the file manager an item that can't be reached can make it show an error dialog of its
+/// own, such as Explorer's "Location is not available". This only checks that the item can be
+/// looked up, which needs permission to search every folder leading to it, not that it can be
+/// read. [`Error::NotFound`] is returned if it doesn't exist, and [`Error::PermissionDenied`] if
+/// a folder on the way can't be searched.
+///
+/// ```no_run
+/// match showfile::is_accessible("/home/alice/hello.txt") {
+///     Ok(()) => showfile::show_path_in_file_manager("/home/alice/hello.txt")?,
+///     Err(showfile::Error::PermissionDenied) => eprintln!("You don't have access to the file"),
+///     Err(e) => return Err(e),
+/// }
+/// # Ok::<(), showfile::Error>(())
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn is_accessible(path: impl AsRef<Path>) -> Result<(), Error> {
+    location::check_access(path.as_ref())
+}
+
+/// Checks whether the current process is running in an application sandbox.
+///
+/// Sandboxed applications may not be able to select files in the file manager, or only files they
+/// have been granted access to. Returns `None` when not sandboxed, or on Windows.
+pub fn is_sandboxed() -> Option<SandboxKind> {
+    platform::sandbox_kind()
+}
+
+/// Returns the XDG desktop portal interfaces available on the session bus, with their versions.
+///
+/// This can be used to check up front what a sandboxed app can do. For example, revealing a file
+/// with the file selected from inside a sandbox requires version 3 or later of
+/// `org.freedesktop.portal.OpenURI`, which adds `OpenDirectory`. Returns an empty list if no portal
+/// service is running, and on platforms other than Linux.
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+pub fn portals() -> Result<Vec<Portal>, Error> {
+    platform::portals()
+}
+
+/// Tries to show a Windows shell location in Explorer.
+///
+/// `name` can be a `shell:` name such as `shell:RecycleBinFolder` or `shell:MyComputerFolder`, or
+/// a CLSID path such as `::{645FF040-5081-101B-9F08-00AA002F954E}`. These locations are part of
+/// the shell namespace and have no file system path, so they can't be passed to
+/// [`show_path_in_file_manager`].
+///
+/// ```no_run
+/// showfile::show_shell_location("shell:RecycleBinFolder")?;
+/// # Ok::<(), showfile::Error>(())
+/// ```
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+#[cfg(windows)]
+pub fn show_shell_location(name: impl AsRef<str>) -> Result<(), Error> {
+    let name = name.as_ref().to_owned();
+    ShowOptions::new()
+        .call(move |options| win32::show_shell_location(&name, options))
+        .map(drop)
+}
+
+/// Tries to show the file referenced by a security-scoped bookmark in Finder.
+///
+/// Apps running in the App Sandbox can only show files they have been granted access to.
+/// Bookmarks created with `NSURLBookmarkCreationWithSecurityScope` let an app keep that access
+/// across launches. The bookmark is resolved and access to the file is held while it is shown.
+///
+/// This function can block, see [`show_path_in_file_manager`] for details.
+#[cfg(any(target_os = "macos", target_abi = "macabi"))]
+pub fn show_bookmark_in_file_manager(bookmark: impl AsRef<[u8]>) -> Result<(), Error> {
+    let bookmark = bookmark.as_ref().to_vec();
+    ShowOptions::new()
+        .call(move |options| macos::show_bookmark(&bookmark, options))
+        .map(drop)
 }
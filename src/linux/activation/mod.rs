@@ -0,0 +1,90 @@
+//! Activation tokens that allow the file manager to take focus.
+//!
+//! Window managers only let a newly shown window take focus from the application that was in
+//! front if it can prove that the user asked for it. The proof is passed to the file manager as
+//! the `startup_id` argument of the FileManager1 methods. On X11, this is a startup notification
+//! id carrying the time of the last user interaction with the application's window, and the
+//! startup notification sequence is sent along so that window managers can show launch feedback.
+//! On Wayland, this is a token the compositor hands out for a surface of the application.
+
+use std::process::Command;
+
+use crate::ShowOptions;
+
+#[cfg(all(feature = "raw-window-handle", not(feature = "static")))]
+mod wayland;
+#[cfg(feature = "raw-window-handle")]
+mod x11;
+
+/// The startup id to pass to the file manager.
+///
+/// On X11, this holds the startup notification sequence the id belongs to, which is completed
+/// when this is dropped, so it should be kept until the file manager has been called.
+#[derive(Default)]
+pub(crate) struct StartupId {
+    id: String,
+    #[cfg(feature = "raw-window-handle")]
+    _sequence: Option<x11::StartupSequence>,
+}
+
+impl StartupId {
+    /// Returns the token set with [`ShowOptions::activation_token`], or derives one from
+    /// [`ShowOptions::parent_window`]. The id is empty if there is none.
+    pub(crate) fn new(options: &ShowOptions) -> Self {
+        if !options.activate {
+            return Self::default();
+        }
+        if let Some(token) = &options.activation_token {
+            return Self {
+                id: token.clone(),
+                #[cfg(feature = "raw-window-handle")]
+                _sequence: None,
+            };
+        }
+        #[cfg(feature = "raw-window-handle")]
+        if let Some(id) = Self::for_parent(&options.parent) {
+            return id;
+        }
+        Self::default()
+    }
+
+    #[cfg(feature = "raw-window-handle")]
+    fn for_parent(parent: &crate::options::ParentWindow) -> Option<Self> {
+        use raw_window_handle::RawWindowHandle;
+
+        let sequence = match (parent.window?, parent.display) {
+            // Static builds can't load libwayland-client
+            #[cfg(not(feature = "static"))]
+            (
+                RawWindowHandle::Wayland(window),
+                Some(raw_window_handle::RawDisplayHandle::Wayland(display)),
+            ) => {
+                // The caller promised that both stay valid when setting the display
+                let id = unsafe { wayland::activation_token(display.display, window.surface) }?;
+                return Some(Self {
+                    id,
+                    _sequence: None,
+                });
+            }
+            (RawWindowHandle::Xlib(window), _) => x11::StartupSequence::begin(window.window as u32),
+            (RawWindowHandle::Xcb(window), _) => x11::StartupSequence::begin(window.window.get()),
+            _ => None,
+        }?;
+        Some(Self {
+            id: sequence.id.clone(),
+            _sequence: Some(sequence),
+        })
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.id
+    }
+
+    /// Passes the token on to `cmd`, in the variables used on Wayland and X11.
+    pub(crate) fn apply(&self, cmd: &mut Command) {
+        if !self.id.is_empty() {
+            cmd.env("XDG_ACTIVATION_TOKEN", &self.id)
+                .env("DESKTOP_STARTUP_ID", &self.id);
+        }
+    }
+}
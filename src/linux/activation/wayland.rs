@@ -0,0 +1,94 @@
+use std::{ffi::c_void, ptr::NonNull};
+
+use wayland_client::{
+    backend::{Backend, ObjectId},
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::{wl_registry, wl_surface::WlSurface},
+    Connection, Dispatch, Proxy, QueueHandle,
+};
+use wayland_protocols::xdg::activation::v1::client::{
+    xdg_activation_token_v1::{self, XdgActivationTokenV1},
+    xdg_activation_v1::{self, XdgActivationV1},
+};
+
+/// Receives the token from the compositor.
+#[derive(Default)]
+struct State {
+    token: Option<String>,
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<XdgActivationV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &XdgActivationV1,
+        _: xdg_activation_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<XdgActivationTokenV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &XdgActivationTokenV1,
+        event: xdg_activation_token_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_activation_token_v1::Event::Done { token } = event {
+            state.token = Some(token);
+        }
+    }
+}
+
+/// Requests an activation token for `surface` with `xdg_activation_v1`.
+///
+/// The request is made on the application's connection, on a separate event queue so that it
+/// doesn't interfere with the application's own dispatching. Returns `None` if the compositor
+/// doesn't support the protocol.
+///
+/// # Safety
+///
+/// `display` must be a live `wl_display`, and `surface` a live `wl_surface` created on it.
+pub(super) unsafe fn activation_token(
+    display: NonNull<c_void>,
+    surface: NonNull<c_void>,
+) -> Option<String> {
+    let conn = Connection::from_backend(Backend::from_foreign_display(display.as_ptr().cast()));
+    let (globals, mut queue) = registry_queue_init::<State>(&conn).ok()?;
+    let qh = queue.handle();
+    let activation: XdgActivationV1 = globals.bind(&qh, 1..=1, ()).ok()?;
+    let request = activation.get_activation_token(&qh, ());
+    // Without a surface, the compositor may still hand out a token, but is less likely to honor it
+    let surface = ObjectId::from_ptr(WlSurface::interface(), surface.as_ptr().cast())
+        .and_then(|id| WlSurface::from_id(&conn, id));
+    if let Ok(surface) = &surface {
+        request.set_surface(surface);
+    }
+    request.commit();
+    let mut state = State::default();
+    while state.token.is_none() {
+        if queue.blocking_dispatch(&mut state).is_err() {
+            break;
+        }
+    }
+    request.destroy();
+    activation.destroy();
+    let _ = conn.flush();
+    state.token
+}
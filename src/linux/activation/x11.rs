@@ -0,0 +1,164 @@
+use std::{
+    process,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xproto::{
+            Atom, AtomEnum, ClientMessageEvent, ConnectionExt as _, CreateWindowAux, EventMask,
+            PropMode, Window, WindowClass,
+        },
+        Event,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+    COPY_FROM_PARENT,
+};
+
+/// A startup notification sequence for showing files, which is completed when dropped.
+///
+/// Window managers show launch feedback such as a busy cursor from when a sequence is started
+/// until it is completed. File managers that already have a window open often don't complete it
+/// themselves, so it is completed once the file manager has been called.
+pub(crate) struct StartupSequence {
+    conn: RustConnection,
+    root: Window,
+    /// The window the messages are sent from, which only exists for the sequence.
+    window: Window,
+    pub(super) id: String,
+}
+
+impl StartupSequence {
+    /// Starts a sequence whose id carries the time of the last user interaction with `parent`,
+    /// or the current server time if it isn't known.
+    pub(super) fn begin(parent: Window) -> Option<Self> {
+        static SEQUENCE: AtomicU32 = AtomicU32::new(0);
+        let (conn, screen) = x11rb::connect(None).ok()?;
+        let root = conn.setup().roots.get(screen)?.root;
+        let window = conn.generate_id().ok()?;
+        conn.create_window(
+            COPY_FROM_PARENT as u8,
+            window,
+            root,
+            -1,
+            -1,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            COPY_FROM_PARENT,
+            &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )
+        .ok()?;
+        let mut sequence = Self {
+            conn,
+            root,
+            window,
+            id: String::new(),
+        };
+        let time = sequence
+            .user_time(parent)
+            .or_else(|| sequence.server_time())?;
+        sequence.id = format!(
+            "showfile-{}-{}_TIME{time}",
+            process::id(),
+            SEQUENCE.fetch_add(1, Ordering::Relaxed)
+        );
+        sequence.send(&format!(
+            "new: ID=\"{}\" NAME=\"File Manager\" ICON=system-file-manager SCREEN={screen}",
+            sequence.id
+        ));
+        Some(sequence)
+    }
+
+    fn atom(&self, name: &str) -> Option<Atom> {
+        Some(
+            self.conn
+                .intern_atom(false, name.as_bytes())
+                .ok()?
+                .reply()
+                .ok()?
+                .atom,
+        )
+    }
+
+    /// Reads `_NET_WM_USER_TIME` from `window`, or from the window the toolkit keeps it on
+    /// instead.
+    fn user_time(&self, window: Window) -> Option<u32> {
+        let property = |window, property, ty: AtomEnum| {
+            self.conn
+                .get_property(false, window, property, ty, 0, 1)
+                .ok()?
+                .reply()
+                .ok()?
+                .value32()?
+                .next()
+                .filter(|&value| value != 0)
+        };
+        let time_window = self.atom("_NET_WM_USER_TIME_WINDOW")?;
+        let window = property(window, time_window, AtomEnum::WINDOW).unwrap_or(window);
+        property(window, self.atom("_NET_WM_USER_TIME")?, AtomEnum::CARDINAL)
+    }
+
+    /// Gets the current server time from the notification of an empty change to a property of
+    /// the sequence's window.
+    fn server_time(&self) -> Option<u32> {
+        self.conn
+            .change_property8(
+                PropMode::APPEND,
+                self.window,
+                AtomEnum::WM_NAME,
+                AtomEnum::STRING,
+                &[],
+            )
+            .ok()?;
+        self.conn.flush().ok()?;
+        loop {
+            match self.conn.wait_for_event().ok()? {
+                Event::PropertyNotify(event) if event.window == self.window => {
+                    return Some(event.time)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Broadcasts a startup notification message, which is split into client messages of 20
+    /// bytes including the terminating nul.
+    fn send(&self, message: &str) {
+        let (Some(begin), Some(more)) = (
+            self.atom("_NET_STARTUP_INFO_BEGIN"),
+            self.atom("_NET_STARTUP_INFO"),
+        ) else {
+            return;
+        };
+        let mut bytes = message.as_bytes().to_vec();
+        bytes.push(0);
+        for (i, chunk) in bytes.chunks(20).enumerate() {
+            let mut data = [0; 20];
+            data[..chunk.len()].copy_from_slice(chunk);
+            let event =
+                ClientMessageEvent::new(8, self.window, if i == 0 { begin } else { more }, data);
+            if self
+                .conn
+                .send_event(false, self.root, EventMask::PROPERTY_CHANGE, event)
+                .is_err()
+            {
+                return;
+            }
+        }
+        let _ = self.conn.flush();
+    }
+}
+
+impl Drop for StartupSequence {
+    fn drop(&mut self) {
+        if !self.id.is_empty() {
+            self.send(&format!("remove: ID=\"{}\"", self.id));
+        }
+        let _ = self.conn.destroy_window(self.window);
+        let _ = self.conn.flush();
+    }
+}
@@ -0,0 +1,78 @@
+//! Undoing the changes an AppImage makes to the environment, for the programs this crate runs.
+//!
+//! The `AppRun` script of an AppImage points variables such as `LD_LIBRARY_PATH` and
+//! `XDG_DATA_DIRS` into the image, mounted at `APPDIR`, so that the application finds its bundled
+//! libraries. Programs of the system such as `xdg-open` or the file manager inherit them, and load
+//! the bundled libraries instead of their own, which they often crash on.
+
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The variables that `AppRun` scripts set or prepend to, with lists separated by `:`.
+const INJECTED: &[&str] = &[
+    "LD_LIBRARY_PATH",
+    "LD_PRELOAD",
+    "PATH",
+    "XDG_DATA_DIRS",
+    "XDG_CONFIG_DIRS",
+    "PYTHONPATH",
+    "PYTHONHOME",
+    "PERLLIB",
+    "GSETTINGS_SCHEMA_DIR",
+    "GIO_MODULE_DIR",
+    "GTK_PATH",
+    "GTK_EXE_PREFIX",
+    "GTK_DATA_PREFIX",
+    "GDK_PIXBUF_MODULE_FILE",
+    "QT_PLUGIN_PATH",
+    "QML2_IMPORT_PATH",
+    "GST_PLUGIN_SYSTEM_PATH",
+    "GST_PLUGIN_SYSTEM_PATH_1_0",
+];
+
+/// Removes the entries that point into the AppImage the application runs from, if any, from the
+/// variables `cmd` inherits.
+///
+/// Variables that are left empty are removed. If only `APPIMAGE` is set, so the mount point isn't
+/// known, `LD_LIBRARY_PATH` and `LD_PRELOAD` are removed, since they are what breaks programs.
+pub(crate) fn sanitize(cmd: &mut Command) {
+    let appdir = env::var_os("APPDIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from);
+    let Some(appdir) = appdir else {
+        if env::var_os("APPIMAGE").is_some() {
+            cmd.env_remove("LD_LIBRARY_PATH").env_remove("LD_PRELOAD");
+        }
+        return;
+    };
+    for key in INJECTED {
+        let Some(value) = env::var_os(key) else {
+            continue;
+        };
+        if let Some(value) = strip(&value, &appdir) {
+            if value.is_empty() {
+                cmd.env_remove(key);
+            } else {
+                cmd.env(key, value);
+            }
+        }
+    }
+}
+
+/// Returns the `:` separated `list` without the entries under `appdir`, or `None` if it has none.
+fn strip(list: &OsString, appdir: &Path) -> Option<OsString> {
+    let entries = env::split_paths(list).collect::<Vec<_>>();
+    let kept = entries
+        .iter()
+        .filter(|entry| !entry.starts_with(appdir))
+        .collect::<Vec<_>>();
+    if kept.len() == entries.len() {
+        return None;
+    }
+    // Only fails if an entry contains `:`, which it didn't since it was split on them
+    env::join_paths(kept).ok()
+}
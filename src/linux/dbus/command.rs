@@ -0,0 +1,714 @@
+//! A backend that calls `dbus-send`, or `busctl` if that isn't installed, for each method call,
+//! so that no D-Bus crate is needed.
+//!
+//! Replies are parsed from the text the tools print, which only needs to cover the few reply
+//! types in [`ReplyType`].
+
+use std::{
+    collections::HashMap,
+    io,
+    path::Path,
+    process::{Command, Output},
+    time::Duration,
+};
+
+use super::{Arg, MethodCall, MethodError, Reply, ReplyType};
+use crate::{args, process, Error};
+
+/// The command line tool used to make calls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tool {
+    DbusSend,
+    Busctl,
+}
+
+impl Tool {
+    fn program(self) -> &'static str {
+        match self {
+            Self::DbusSend => "dbus-send",
+            Self::Busctl => "busctl",
+        }
+    }
+}
+
+pub(crate) struct Bus {
+    tool: Tool,
+    /// The address to connect to instead of the session bus, and whether it is a peer.
+    address: Option<(String, bool)>,
+    timeout: Option<Duration>,
+}
+
+/// Checks whether `program` is in one of the `PATH` directories.
+fn in_path(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| Path::new(&dir).join(program).is_file())
+    })
+}
+
+impl Bus {
+    pub(crate) fn session() -> Result<Self, Error> {
+        Self::new(None)
+    }
+
+    pub(crate) fn address(address: &str, peer: bool) -> Result<Self, Error> {
+        Self::new(Some((address.to_owned(), peer)))
+    }
+
+    fn new(address: Option<(String, bool)>) -> Result<Self, Error> {
+        let peer = matches!(address, Some((_, true)));
+        let tool = if in_path(Tool::DbusSend.program()) {
+            Tool::DbusSend
+        } else if in_path(Tool::Busctl.program()) && !peer {
+            // busctl always says Hello to a bus, so it can't talk to a peer
+            Tool::Busctl
+        } else {
+            return Err(Error::backend("neither dbus-send nor busctl was found"));
+        };
+        Ok(Self {
+            tool,
+            address,
+            timeout: None,
+        })
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
+
+    /// Builds the command line for `call`.
+    fn command(&self, call: &MethodCall<'_>) -> Command {
+        let mut cmd = process::command(self.tool.program());
+        match self.tool {
+            Tool::DbusSend => {
+                cmd.arg("--print-reply");
+                if let Some(timeout) = self.timeout {
+                    cmd.arg(format!("--reply-timeout={}", timeout.as_millis()));
+                }
+                match &self.address {
+                    None => cmd.arg("--session"),
+                    Some((address, false)) => cmd.arg(format!("--bus={address}")),
+                    Some((address, true)) => cmd.arg(format!("--peer={address}")),
+                };
+                cmd.arg(format!("--dest={}", call.dest))
+                    .arg(call.path)
+                    .arg(format!("{}.{}", call.iface, call.method));
+                for arg in call.args {
+                    match *arg {
+                        Arg::Bool(b) => cmd.arg(format!("boolean:{b}")),
+                        Arg::Str(s) => cmd.arg(format!("string:{s}")),
+                        // The only arrays passed are URIs
+                        Arg::StrArray(a) => {
+                            cmd.arg(format!("array:string:{}", args::dbus_send_array(a)))
+                        }
+                    };
+                }
+            }
+            Tool::Busctl => {
+                match &self.address {
+                    None => cmd.arg("--user"),
+                    Some((address, _)) => cmd.arg(format!("--address={address}")),
+                };
+                if let Some(timeout) = self.timeout {
+                    cmd.arg(format!("--timeout={}ms", timeout.as_millis()));
+                }
+                // Nothing after this is taken as an option, even if it starts with a dash
+                cmd.args(["--", "call", call.dest, call.path, call.iface, call.method]);
+                if !call.args.is_empty() {
+                    let signature = call
+                        .args
+                        .iter()
+                        .map(|arg| match arg {
+                            Arg::Bool(_) => "b",
+                            Arg::Str(_) => "s",
+                            Arg::StrArray(_) => "as",
+                        })
+                        .collect::<String>();
+                    cmd.arg(signature);
+                }
+                for arg in call.args {
+                    match *arg {
+                        Arg::Bool(b) => cmd.arg(b.to_string()),
+                        Arg::Str(s) => cmd.arg(s),
+                        Arg::StrArray(a) => cmd.arg(a.len().to_string()).args(a),
+                    };
+                }
+            }
+        }
+        cmd
+    }
+
+    /// Runs the command for `call`, returning its output on success, and an error reply as a
+    /// [`MethodError`].
+    fn send(&self, call: &MethodCall<'_>) -> Result<String, Error> {
+        let Output {
+            status,
+            stdout,
+            stderr,
+        } = self.command(call).output().map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => Error::backend("neither dbus-send nor busctl was found"),
+            _ => Error::backend(e),
+        })?;
+        if status.success() {
+            return String::from_utf8(stdout).map_err(Error::backend);
+        }
+        let stderr = String::from_utf8_lossy(&stderr);
+        let err = match self.tool {
+            Tool::DbusSend => MethodError::from_dbus_send(&stderr),
+            Tool::Busctl => MethodError::from_busctl(&stderr),
+        };
+        Err(match err {
+            Some(err) => Error::backend(err),
+            None => Error::backend(format!("{} failed: {}", self.tool.program(), stderr.trim())),
+        })
+    }
+
+    pub(crate) fn call_raw(
+        &mut self,
+        call: &MethodCall<'_>,
+        reply: ReplyType,
+    ) -> Result<Reply, Error> {
+        let output = self.send(call)?;
+        let parsed = match self.tool {
+            Tool::DbusSend => parse_dbus_send(&output, reply),
+            Tool::Busctl => parse_busctl(&output, reply),
+        };
+        parsed.ok_or_else(|| Error::backend("unexpected D-Bus reply type"))
+    }
+
+    pub(crate) fn get_property_raw(
+        &mut self,
+        call: &MethodCall<'_>,
+        reply: ReplyType,
+    ) -> Result<Reply, Error> {
+        // Both tools print the value inside the variant after a marker
+        let output = self.send(call)?;
+        let parsed = match self.tool {
+            Tool::DbusSend => parse_dbus_send(&output, reply),
+            Tool::Busctl => {
+                parse_busctl(output.trim_start().strip_prefix("v ").unwrap_or(""), reply)
+            }
+        };
+        parsed.ok_or_else(|| Error::backend("unexpected D-Bus property type"))
+    }
+}
+
+impl MethodError {
+    /// Parses the error that `busctl` prints, such as
+    /// `Call failed: The name ... was not provided by any .service files`.
+    ///
+    /// Only the message is printed, so the error name is guessed from the messages the bus
+    /// daemons use for the errors that are handled specially.
+    pub(crate) fn from_busctl(stderr: &str) -> Option<Self> {
+        let message = stderr.lines().next()?.split_once(": ")?.1;
+        let name = if message.contains("not provided by any .service files")
+            || message.contains("is not activatable")
+        {
+            "ServiceUnknown"
+        } else if message.contains("no such name") {
+            "NameHasNoOwner"
+        } else if message.contains("Unknown method") || message.contains("doesn't exist") {
+            "UnknownMethod"
+        } else if message.contains("Access denied") || message.contains("Permission denied") {
+            "AccessDenied"
+        } else if message.contains("timed out") {
+            "Timeout"
+        } else {
+            "Failed"
+        };
+        Some(Self {
+            name: format!("org.freedesktop.DBus.Error.{name}"),
+            message: Some(message.to_owned()),
+        })
+    }
+}
+
+/// Strips the quotes around a string printed by `dbus-send`, which doesn't escape anything.
+fn unquote(s: &str) -> Option<&str> {
+    s.strip_prefix('"')?.strip_suffix('"')
+}
+
+/// Parses the byte array printed by `dbus-send` starting at `line`, reading further lines from
+/// `lines` if it was printed in hex.
+fn dbus_send_bytes<'a>(line: &str, lines: &mut impl Iterator<Item = &'a str>) -> Option<Vec<u8>> {
+    // Empty arrays are printed without the type of their elements
+    if line == "array [" {
+        return (lines.next()? == "]").then(Vec::new);
+    }
+    let first = line.strip_prefix("array of bytes ")?;
+    // Printable strings are shown as text, with a note if there is a terminating nul
+    if let Some(text) = first.strip_suffix(" + \\0") {
+        let mut bytes = unquote(text)?.as_bytes().to_vec();
+        bytes.push(0);
+        return Some(bytes);
+    }
+    if let Some(text) = unquote(first) {
+        return Some(text.as_bytes().to_vec());
+    }
+    if first != "[" {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    for line in lines.map(str::trim) {
+        if line == "]" {
+            return Some(bytes);
+        }
+        for byte in line.split_whitespace() {
+            bytes.push(u8::from_str_radix(byte, 16).ok()?);
+        }
+    }
+    None
+}
+
+/// Parses the output of `dbus-send --print-reply`.
+fn parse_dbus_send(output: &str, reply: ReplyType) -> Option<Reply> {
+    // The first line describes the reply message
+    let body = output.split_once('\n').map_or("", |(_, body)| body).trim();
+    // Property values are prefixed with the type of the variant
+    let body = body.strip_prefix("variant").map_or(body, str::trim_start);
+    let mut lines = body.lines().map(str::trim);
+    Some(match reply {
+        ReplyType::Unit => Reply::Unit,
+        ReplyType::Bool => match body {
+            "boolean true" => Reply::Bool(true),
+            "boolean false" => Reply::Bool(false),
+            _ => return None,
+        },
+        ReplyType::U32 => Reply::U32(body.strip_prefix("uint32 ")?.parse().ok()?),
+        // Strings can span several lines, such as introspection data
+        ReplyType::Str => Reply::Str(unquote(body.strip_prefix("string ")?)?.to_owned()),
+        ReplyType::StrArray => Reply::StrArray(
+            lines
+                .filter_map(|line| Some(unquote(line.strip_prefix("string ")?)?.to_owned()))
+                .collect(),
+        ),
+        ReplyType::Bytes => Reply::Bytes(dbus_send_bytes(lines.next()?, &mut lines)?),
+        ReplyType::BytesDict => {
+            let mut dict = HashMap::new();
+            let mut key = None;
+            while let Some(line) = lines.next() {
+                if let Some(s) = line.strip_prefix("string ") {
+                    key = Some(unquote(s)?.to_owned());
+                } else if let Some(key) = key.take() {
+                    dict.insert(key, dbus_send_bytes(line, &mut lines)?);
+                }
+            }
+            Reply::BytesDict(dict)
+        }
+    })
+}
+
+/// Splits the output of `busctl` into words, unescaping quoted strings.
+fn busctl_words(output: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut chars = output.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut word = Vec::new();
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => match chars.next()? {
+                        'a' => word.push(b'\x07'),
+                        'b' => word.push(b'\x08'),
+                        'f' => word.push(b'\x0c'),
+                        'n' => word.push(b'\n'),
+                        'r' => word.push(b'\r'),
+                        't' => word.push(b'\t'),
+                        'v' => word.push(b'\x0b'),
+                        // Other bytes are escaped in octal
+                        c @ '0'..='7' => {
+                            let mut byte = c.to_digit(8)?;
+                            for _ in 0..2 {
+                                byte = byte * 8 + chars.next()?.to_digit(8)?;
+                            }
+                            word.push(u8::try_from(byte).ok()?);
+                        }
+                        c => word.extend(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                    },
+                    c => word.extend(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                }
+            }
+            words.push(String::from_utf8(word).ok()?);
+        } else {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                word.push(c);
+            }
+            words.push(word);
+        }
+    }
+    Some(words)
+}
+
+/// Reads the length of an array from the output of `busctl`.
+fn busctl_len(words: &mut impl Iterator<Item = String>) -> Option<usize> {
+    words.next()?.parse().ok()
+}
+
+/// Reads a byte array from the output of `busctl`, printed as its length and the decimal bytes.
+fn busctl_bytes(words: &mut impl Iterator<Item = String>) -> Option<Vec<u8>> {
+    let len = busctl_len(words)?;
+    (0..len).map(|_| words.next()?.parse().ok()).collect()
+}
+
+/// Parses the output of `busctl call`, which is the signature followed by the values, with the
+/// length before each array.
+fn parse_busctl(output: &str, reply: ReplyType) -> Option<Reply> {
+    let mut words = busctl_words(output)?.into_iter();
+    let signature = words.next().unwrap_or_default();
+    let expected = match reply {
+        ReplyType::Unit => return Some(Reply::Unit),
+        ReplyType::Bool => "b",
+        ReplyType::U32 => "u",
+        ReplyType::Str => "s",
+        ReplyType::StrArray => "as",
+        ReplyType::Bytes => "ay",
+        ReplyType::BytesDict => "a{say}",
+    };
+    if signature != expected {
+        return None;
+    }
+    Some(match reply {
+        ReplyType::Unit => Reply::Unit,
+        ReplyType::Bool => Reply::Bool(words.next()?.parse().ok()?),
+        ReplyType::U32 => Reply::U32(words.next()?.parse().ok()?),
+        ReplyType::Str => Reply::Str(words.next()?),
+        ReplyType::StrArray => {
+            let len = busctl_len(&mut words)?;
+            Reply::StrArray(words.take(len).collect())
+        }
+        ReplyType::Bytes => Reply::Bytes(busctl_bytes(&mut words)?),
+        ReplyType::BytesDict => {
+            let len = busctl_len(&mut words)?;
+            let mut dict = HashMap::new();
+            for _ in 0..len {
+                let key = words.next()?;
+                dict.insert(key, busctl_bytes(&mut words)?);
+            }
+            Reply::BytesDict(dict)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses the body of a reply printed by `dbus-send --print-reply`, after its first line.
+    fn dbus_send(body: &str, reply: ReplyType) -> Option<Reply> {
+        let output = format!(
+            "method return time=1792164232.643984 sender=:1.0 -> destination=:1.3 serial=9 \
+             reply_serial=2\n{body}"
+        );
+        parse_dbus_send(&output, reply)
+    }
+
+    fn dict(entries: &[(&str, &[u8])]) -> Reply {
+        Reply::BytesDict(
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_vec()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn dbus_send_scalars() {
+        assert_eq!(dbus_send("", ReplyType::Unit), Some(Reply::Unit));
+        assert_eq!(
+            dbus_send("   boolean true\n", ReplyType::Bool),
+            Some(Reply::Bool(true))
+        );
+        assert_eq!(
+            dbus_send("   boolean false\n", ReplyType::Bool),
+            Some(Reply::Bool(false))
+        );
+        assert_eq!(
+            dbus_send("   uint32 4294967295\n", ReplyType::U32),
+            Some(Reply::U32(u32::MAX))
+        );
+        assert_eq!(dbus_send("   uint32 4\n", ReplyType::Bool), None);
+        assert_eq!(dbus_send("   string \"true\"\n", ReplyType::U32), None);
+    }
+
+    #[test]
+    fn dbus_send_strings() {
+        // Nothing inside the quotes is escaped
+        assert_eq!(
+            dbus_send(
+                "   string \"Grüße, \"quoted\" \\ back\ttab\"\n",
+                ReplyType::Str
+            ),
+            Some(Reply::Str("Grüße, \"quoted\" \\ back\ttab".to_owned()))
+        );
+        assert_eq!(
+            dbus_send(
+                "   string \"<node>\n  <interface name=\"a\"/>\n</node>\"\n",
+                ReplyType::Str
+            ),
+            Some(Reply::Str(
+                "<node>\n  <interface name=\"a\"/>\n</node>".to_owned()
+            ))
+        );
+        assert_eq!(
+            dbus_send("   string \"\"\n", ReplyType::Str),
+            Some(Reply::Str(String::new()))
+        );
+        assert_eq!(
+            dbus_send(
+                "   array [\n      string \"org.freedesktop.FileManager1\"\n      string \
+                 \"ünïcode\"\n      string \"with space\"\n   ]\n",
+                ReplyType::StrArray
+            ),
+            Some(Reply::StrArray(vec![
+                "org.freedesktop.FileManager1".to_owned(),
+                "ünïcode".to_owned(),
+                "with space".to_owned()
+            ]))
+        );
+        assert_eq!(
+            dbus_send("   array [\n   ]\n", ReplyType::StrArray),
+            Some(Reply::StrArray(Vec::new()))
+        );
+    }
+
+    #[test]
+    fn dbus_send_bytes() {
+        assert_eq!(
+            dbus_send(
+                "   array of bytes \"/run/user/1000/doc\" + \\0\n",
+                ReplyType::Bytes
+            ),
+            Some(Reply::Bytes(b"/run/user/1000/doc\0".to_vec()))
+        );
+        assert_eq!(
+            dbus_send("   array of bytes \"plain\"\n", ReplyType::Bytes),
+            Some(Reply::Bytes(b"plain".to_vec()))
+        );
+        let binary = (0..40).chain([0xc3, 0xa9, 0xff]).collect::<Vec<u8>>();
+        assert_eq!(
+            dbus_send(
+                "   array of bytes [\n      00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f 10 \
+                 11 12 13 14 15 16 17\n      18 19 1a 1b 1c 1d 1e 1f 20 21 22 23 24 25 26 27 c3 \
+                 a9 ff\n   ]\n",
+                ReplyType::Bytes
+            ),
+            Some(Reply::Bytes(binary))
+        );
+        // Non-ASCII paths are printed in hex
+        assert_eq!(
+            dbus_send(
+                "   array of bytes [\n      2f 68 6f 6d 65 2f 61 6c 69 63 65 2f 52 c3 a9 73 75 \
+                 6d c3 a9 00\n   ]\n",
+                ReplyType::Bytes
+            ),
+            Some(Reply::Bytes("/home/alice/Résumé\0".as_bytes().to_vec()))
+        );
+        assert_eq!(
+            dbus_send("   array [\n   ]\n", ReplyType::Bytes),
+            Some(Reply::Bytes(Vec::new()))
+        );
+        assert_eq!(
+            dbus_send("   array of bytes [\n      00 01\n", ReplyType::Bytes),
+            None
+        );
+    }
+
+    #[test]
+    fn dbus_send_bytes_dict() {
+        assert_eq!(
+            dbus_send(
+                "   array [\n      dict entry(\n         string \"x1\"\n         array of bytes \
+                 [\n            01 02 ff\n         ]\n      )\n      dict entry(\n         \
+                 string \"abc\"\n         array of bytes \"/home/alice/Docs\" + \\0\n      )\n   \
+                 ]\n",
+                ReplyType::BytesDict
+            ),
+            Some(dict(&[
+                ("abc", b"/home/alice/Docs\0"),
+                ("x1", &[1, 2, 255])
+            ]))
+        );
+        assert_eq!(
+            dbus_send(
+                "   array [\n      dict entry(\n         string \"empty\"\n         array \
+                 [\n         ]\n      )\n   ]\n",
+                ReplyType::BytesDict
+            ),
+            Some(dict(&[("empty", b"")]))
+        );
+        assert_eq!(
+            dbus_send("   array [\n   ]\n", ReplyType::BytesDict),
+            Some(dict(&[]))
+        );
+    }
+
+    #[test]
+    fn dbus_send_properties() {
+        assert_eq!(
+            dbus_send("   variant       uint32 4\n", ReplyType::U32),
+            Some(Reply::U32(4))
+        );
+        assert_eq!(
+            dbus_send("   variant       string \"Dolphin ü\"\n", ReplyType::Str),
+            Some(Reply::Str("Dolphin ü".to_owned()))
+        );
+        assert_eq!(
+            dbus_send(
+                "   variant       array [\n         string \"a\"\n         string \"b\"\n      \
+                 ]\n",
+                ReplyType::StrArray
+            ),
+            Some(Reply::StrArray(vec!["a".to_owned(), "b".to_owned()]))
+        );
+        assert_eq!(
+            dbus_send("   variant       boolean true\n", ReplyType::Bool),
+            Some(Reply::Bool(true))
+        );
+    }
+
+    #[test]
+    fn busctl_escapes() {
+        assert_eq!(
+            busctl_words(r#"s "Gr\303\274\303\237e, \"quoted\" \\ back\ttab""#).unwrap(),
+            ["s", "Grüße, \"quoted\" \\ back\ttab"]
+        );
+        assert_eq!(
+            busctl_words(r#""\a\b\f\n\r\v\001" "" x"#).unwrap(),
+            ["\x07\x08\x0c\n\r\x0b\x01", "", "x"]
+        );
+        // Unterminated, out of range, or not UTF-8
+        assert_eq!(busctl_words(r#"s "abc"#), None);
+        assert_eq!(busctl_words(r#"s "\777""#), None);
+        assert_eq!(busctl_words(r#"s "\377""#), None);
+        assert_eq!(busctl_words(r#"s "\30""#), None);
+    }
+
+    #[test]
+    fn busctl_replies() {
+        assert_eq!(parse_busctl("", ReplyType::Unit), Some(Reply::Unit));
+        assert_eq!(
+            parse_busctl("b true\n", ReplyType::Bool),
+            Some(Reply::Bool(true))
+        );
+        assert_eq!(
+            parse_busctl("b false\n", ReplyType::Bool),
+            Some(Reply::Bool(false))
+        );
+        assert_eq!(
+            parse_busctl("u 4294967295\n", ReplyType::U32),
+            Some(Reply::U32(u32::MAX))
+        );
+        assert_eq!(
+            parse_busctl(
+                r#"s "<node>\n  <interface name=\"a\"/>\n</node>""#,
+                ReplyType::Str
+            ),
+            Some(Reply::Str(
+                "<node>\n  <interface name=\"a\"/>\n</node>".to_owned()
+            ))
+        );
+        assert_eq!(
+            parse_busctl("s \"\"\n", ReplyType::Str),
+            Some(Reply::Str(String::new()))
+        );
+        assert_eq!(
+            parse_busctl(
+                r#"as 3 "org.freedesktop.FileManager1" "\303\274n\303\257code" "with space""#,
+                ReplyType::StrArray
+            ),
+            Some(Reply::StrArray(vec![
+                "org.freedesktop.FileManager1".to_owned(),
+                "ünïcode".to_owned(),
+                "with space".to_owned()
+            ]))
+        );
+        assert_eq!(
+            parse_busctl("as 0\n", ReplyType::StrArray),
+            Some(Reply::StrArray(Vec::new()))
+        );
+        // The signature must match what was asked for
+        assert_eq!(parse_busctl("u 4\n", ReplyType::Bool), None);
+        assert_eq!(parse_busctl("as 0\n", ReplyType::Bytes), None);
+    }
+
+    #[test]
+    fn busctl_bytes() {
+        assert_eq!(
+            parse_busctl(
+                "ay 19 47 114 117 110 47 117 115 101 114 47 49 48 48 48 47 100 111 99 0\n",
+                ReplyType::Bytes
+            ),
+            Some(Reply::Bytes(b"/run/user/1000/doc\0".to_vec()))
+        );
+        assert_eq!(
+            parse_busctl(
+                "ay 21 47 104 111 109 101 47 97 108 105 99 101 47 82 195 169 115 117 109 195 169 \
+                 0\n",
+                ReplyType::Bytes
+            ),
+            Some(Reply::Bytes("/home/alice/Résumé\0".as_bytes().to_vec()))
+        );
+        assert_eq!(
+            parse_busctl("ay 0\n", ReplyType::Bytes),
+            Some(Reply::Bytes(Vec::new()))
+        );
+        assert_eq!(parse_busctl("ay 3 1 2\n", ReplyType::Bytes), None);
+        assert_eq!(parse_busctl("ay 1 256\n", ReplyType::Bytes), None);
+        assert_eq!(
+            parse_busctl(
+                "a{say} 2 \"abc\" 17 47 104 111 109 101 47 97 108 105 99 101 47 68 111 99 115 0 \
+                 \"x1\" 3 1 2 255\n",
+                ReplyType::BytesDict
+            ),
+            Some(dict(&[
+                ("abc", b"/home/alice/Docs\0"),
+                ("x1", &[1, 2, 255])
+            ]))
+        );
+        assert_eq!(
+            parse_busctl("a{say} 1 \"empty\" 0\n", ReplyType::BytesDict),
+            Some(dict(&[("empty", b"")]))
+        );
+        assert_eq!(
+            parse_busctl("a{say} 0\n", ReplyType::BytesDict),
+            Some(dict(&[]))
+        );
+    }
+
+    #[test]
+    fn busctl_errors() {
+        let name = |stderr: &str| MethodError::from_busctl(stderr).unwrap().name;
+        assert_eq!(
+            name("Call failed: The name org.example.Nope was not provided by any .service files\n"),
+            "org.freedesktop.DBus.Error.ServiceUnknown"
+        );
+        assert_eq!(
+            name("Call failed: Could not get owner of name 'org.example.Nope': no such name\n"),
+            "org.freedesktop.DBus.Error.NameHasNoOwner"
+        );
+        assert_eq!(
+            name("Call failed: Unknown method 'Nope'\n"),
+            "org.freedesktop.DBus.Error.UnknownMethod"
+        );
+        assert_eq!(
+            name("Call failed: Access denied\n"),
+            "org.freedesktop.DBus.Error.AccessDenied"
+        );
+        assert_eq!(
+            name("Call failed: Connection timed out\n"),
+            "org.freedesktop.DBus.Error.Timeout"
+        );
+        assert_eq!(
+            name("Call failed: Input/output error\n"),
+            "org.freedesktop.DBus.Error.Failed"
+        );
+        let err = MethodError::from_busctl("Call failed: Access denied\n").unwrap();
+        assert_eq!(err.message.as_deref(), Some("Access denied"));
+        assert!(MethodError::from_busctl("").is_none());
+        assert!(MethodError::from_busctl("garbage").is_none());
+    }
+}
@@ -0,0 +1,284 @@
+use std::{
+    ffi::{c_char, c_int, CStr, CString},
+    fmt,
+    ptr::{null, null_mut},
+    time::Duration,
+};
+
+use super::{Arg, MethodCall, MethodError, Reply, ReplyType};
+use crate::{Error, ErrorKind};
+
+/// A `GError` that isn't a D-Bus error reply.
+#[derive(Debug)]
+pub(crate) struct GioError {
+    pub domain: String,
+    pub code: i32,
+    pub message: String,
+}
+
+impl GioError {
+    /// Returns the category of a `G_IO_ERROR`.
+    pub(crate) fn kind(&self) -> Option<ErrorKind> {
+        if self.domain != "g-io-error-quark" {
+            return None;
+        }
+        match self.code {
+            gio_sys::G_IO_ERROR_NOT_FOUND => Some(ErrorKind::NotFound),
+            gio_sys::G_IO_ERROR_PERMISSION_DENIED => Some(ErrorKind::PermissionDenied),
+            gio_sys::G_IO_ERROR_NOT_SUPPORTED => Some(ErrorKind::Unsupported),
+            gio_sys::G_IO_ERROR_TIMED_OUT => Some(ErrorKind::TimedOut),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({} {})", self.message, self.domain, self.code)
+    }
+}
+
+impl std::error::Error for GioError {}
+
+/// A connection, and the timeout for calls in milliseconds, where -1 is the default.
+pub(crate) struct Bus(*mut gio_sys::GDBusConnection, c_int);
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        unsafe {
+            gobject_sys::g_object_unref(self.0 as *mut _);
+        }
+    }
+}
+
+pub(crate) fn to_cstring(s: impl Into<Vec<u8>>) -> CString {
+    CString::new(s).unwrap_or_else(|e| {
+        let pos = e.nul_position();
+        let mut s = e.into_vec();
+        s.truncate(pos);
+        CString::new(s).unwrap()
+    })
+}
+
+pub(crate) unsafe fn gerror_to_error(err: *mut glib_sys::GError) -> Error {
+    if gio_sys::g_dbus_error_is_remote_error(err) != 0 {
+        let name = gio_sys::g_dbus_error_get_remote_error(err);
+        gio_sys::g_dbus_error_strip_remote_error(err);
+        let name_str = CStr::from_ptr(name).to_string_lossy().into_owned();
+        glib_sys::g_free(name as *mut _);
+        let message = CStr::from_ptr((*err).message)
+            .to_string_lossy()
+            .into_owned();
+        glib_sys::g_error_free(err);
+        return Error::backend(MethodError {
+            name: name_str,
+            message: Some(message),
+        });
+    }
+    let domain = glib_sys::g_quark_to_string((*err).domain);
+    let err_info = GioError {
+        domain: if domain.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(domain).to_string_lossy().into_owned()
+        },
+        code: (*err).code,
+        message: CStr::from_ptr((*err).message)
+            .to_string_lossy()
+            .into_owned(),
+    };
+    glib_sys::g_error_free(err);
+    Error::backend(err_info)
+}
+
+unsafe fn to_variant(arg: &Arg<'_>) -> *mut glib_sys::GVariant {
+    match *arg {
+        Arg::Bool(b) => glib_sys::g_variant_new_boolean(b.into()),
+        Arg::Str(s) => glib_sys::g_variant_new_string(to_cstring(s).as_ptr()),
+        Arg::StrArray(a) => {
+            let strs = a.iter().map(|s| to_cstring(*s)).collect::<Vec<_>>();
+            let ptrs = strs.iter().map(|s| s.as_ptr()).collect::<Vec<_>>();
+            glib_sys::g_variant_new_strv(ptrs.as_ptr(), ptrs.len() as isize)
+        }
+    }
+}
+
+unsafe fn variant_to_string(value: *mut glib_sys::GVariant) -> String {
+    CStr::from_ptr(glib_sys::g_variant_get_string(value, null_mut()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+unsafe fn variant_to_bytes(value: *mut glib_sys::GVariant) -> Vec<u8> {
+    let mut len = 0;
+    let data = glib_sys::g_variant_get_fixed_array(value, &mut len, 1);
+    if data.is_null() {
+        return Vec::new();
+    }
+    std::slice::from_raw_parts(data as *const u8, len).to_vec()
+}
+
+impl Bus {
+    pub(crate) fn session() -> Result<Self, Error> {
+        unsafe {
+            let mut err = null_mut();
+            let bus = gio_sys::g_bus_get_sync(gio_sys::G_BUS_TYPE_SESSION, null_mut(), &mut err);
+            if bus.is_null() {
+                return Err(gerror_to_error(err));
+            }
+            Ok(Self(bus, -1))
+        }
+    }
+
+    pub(crate) fn address(address: &str, peer: bool) -> Result<Self, Error> {
+        let mut flags = gio_sys::G_DBUS_CONNECTION_FLAGS_AUTHENTICATION_CLIENT;
+        if !peer {
+            flags |= gio_sys::G_DBUS_CONNECTION_FLAGS_MESSAGE_BUS_CONNECTION;
+        }
+        unsafe {
+            let mut err = null_mut();
+            let bus = gio_sys::g_dbus_connection_new_for_address_sync(
+                to_cstring(address).as_ptr(),
+                flags,
+                null_mut(),
+                null_mut(),
+                &mut err,
+            );
+            if bus.is_null() {
+                return Err(gerror_to_error(err));
+            }
+            Ok(Self(bus, -1))
+        }
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.1 = timeout.as_millis().try_into().unwrap_or(c_int::MAX);
+    }
+
+    /// Sends `call` and waits for a reply of the GVariant type `reply_type`, which must be
+    /// nul-terminated.
+    unsafe fn send(
+        &mut self,
+        call: &MethodCall<'_>,
+        reply_type: Option<&[u8]>,
+    ) -> Result<*mut glib_sys::GVariant, Error> {
+        let args = call.args.iter().map(|a| to_variant(a)).collect::<Vec<_>>();
+        let args = glib_sys::g_variant_new_tuple(args.as_ptr(), args.len());
+        let mut err = null_mut();
+        let ret = gio_sys::g_dbus_connection_call_sync(
+            self.0,
+            to_cstring(call.dest).as_ptr(),
+            to_cstring(call.path).as_ptr(),
+            to_cstring(call.iface).as_ptr(),
+            to_cstring(call.method).as_ptr(),
+            args,
+            reply_type.map_or(null(), |t| t.as_ptr() as *const _),
+            gio_sys::G_DBUS_CALL_FLAGS_NONE,
+            self.1,
+            null_mut(),
+            &mut err,
+        );
+        if ret.is_null() {
+            return Err(gerror_to_error(err));
+        }
+        Ok(ret)
+    }
+
+    pub(crate) fn call_raw(
+        &mut self,
+        call: &MethodCall<'_>,
+        reply: ReplyType,
+    ) -> Result<Reply, Error> {
+        let reply_type: Option<&[u8]> = match reply {
+            ReplyType::Unit => None,
+            ReplyType::Bool => Some(b"(b)\0"),
+            ReplyType::U32 => Some(b"(u)\0"),
+            ReplyType::Str => Some(b"(s)\0"),
+            ReplyType::StrArray => Some(b"(as)\0"),
+            ReplyType::Bytes => Some(b"(ay)\0"),
+            ReplyType::BytesDict => Some(b"(a{say})\0"),
+        };
+        unsafe {
+            let ret = self.send(call, reply_type)?;
+            let value = match reply_type {
+                None => Ok(Reply::Unit),
+                Some(_) => {
+                    let child = glib_sys::g_variant_get_child_value(ret, 0);
+                    let value = decode(child, reply);
+                    glib_sys::g_variant_unref(child);
+                    value
+                }
+            };
+            glib_sys::g_variant_unref(ret);
+            value
+        }
+    }
+
+    pub(crate) fn get_property_raw(
+        &mut self,
+        call: &MethodCall<'_>,
+        reply: ReplyType,
+    ) -> Result<Reply, Error> {
+        unsafe {
+            let ret = self.send(call, Some(b"(v)\0"))?;
+            let child = glib_sys::g_variant_get_child_value(ret, 0);
+            let value = glib_sys::g_variant_get_variant(child);
+            let reply = decode(value, reply);
+            glib_sys::g_variant_unref(value);
+            glib_sys::g_variant_unref(child);
+            glib_sys::g_variant_unref(ret);
+            reply
+        }
+    }
+}
+
+/// Decodes `value`, checking that it has the expected type.
+unsafe fn decode(value: *mut glib_sys::GVariant, reply: ReplyType) -> Result<Reply, Error> {
+    let expected: &[u8] = match reply {
+        ReplyType::Unit => return Ok(Reply::Unit),
+        ReplyType::Bool => b"b\0",
+        ReplyType::U32 => b"u\0",
+        ReplyType::Str => b"s\0",
+        ReplyType::StrArray => b"as\0",
+        ReplyType::Bytes => b"ay\0",
+        ReplyType::BytesDict => b"a{say}\0",
+    };
+    if glib_sys::g_variant_is_of_type(value, expected.as_ptr() as *const _) == 0 {
+        return Err(Error::backend("unexpected D-Bus reply type"));
+    }
+    Ok(match reply {
+        ReplyType::Unit => Reply::Unit,
+        ReplyType::Bool => Reply::Bool(glib_sys::g_variant_get_boolean(value) != 0),
+        ReplyType::U32 => Reply::U32(glib_sys::g_variant_get_uint32(value)),
+        ReplyType::Str => Reply::Str(variant_to_string(value)),
+        ReplyType::StrArray => {
+            let mut len = 0;
+            let strv = glib_sys::g_variant_get_strv(value, &mut len);
+            let strs = (0..len)
+                .map(|i| {
+                    CStr::from_ptr(*strv.add(i) as *const c_char)
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect();
+            glib_sys::g_free(strv as *mut _);
+            Reply::StrArray(strs)
+        }
+        ReplyType::Bytes => Reply::Bytes(variant_to_bytes(value)),
+        ReplyType::BytesDict => {
+            let dict = (0..glib_sys::g_variant_n_children(value))
+                .map(|i| {
+                    let entry = glib_sys::g_variant_get_child_value(value, i);
+                    let key = glib_sys::g_variant_get_child_value(entry, 0);
+                    let bytes = glib_sys::g_variant_get_child_value(entry, 1);
+                    let item = (variant_to_string(key), variant_to_bytes(bytes));
+                    glib_sys::g_variant_unref(bytes);
+                    glib_sys::g_variant_unref(key);
+                    glib_sys::g_variant_unref(entry);
+                    item
+                })
+                .collect();
+            Reply::BytesDict(dict)
+        }
+    })
+}
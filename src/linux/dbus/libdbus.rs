@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use ::dbus::{
+    arg::{Arg as DBusArg, Get, Variant},
+    channel::{BusType, Channel},
+    Message,
+};
+
+use super::{Arg, MethodCall, MethodError, Reply, ReplyType};
+use crate::Error;
+
+/// The largest timeout libdbus accepts, which it treats as no timeout.
+const TIMEOUT: Duration = Duration::from_millis(i32::MAX as u64);
+
+pub(crate) struct Bus(Channel, Duration);
+
+fn from_dbus(err: ::dbus::Error) -> Error {
+    match err.name() {
+        Some(name) => Error::backend(MethodError {
+            name: name.to_owned(),
+            message: err.message().map(str::to_owned),
+        }),
+        None => Error::backend(err),
+    }
+}
+
+/// Reads the single value in a reply as `T`.
+fn read<'a, T: DBusArg + Get<'a>>(msg: &'a Message) -> Result<T, Error> {
+    msg.read1().map_err(Error::backend)
+}
+
+/// Reads the value in the variant returned by `Properties.Get` as `T`.
+fn read_variant<'a, T: DBusArg + Get<'a>>(msg: &'a Message) -> Result<T, Error> {
+    msg.read1::<Variant<T>>()
+        .map(|variant| variant.0)
+        .map_err(Error::backend)
+}
+
+impl Bus {
+    pub(crate) fn session() -> Result<Self, Error> {
+        Channel::get_private(BusType::Session)
+            .map(|channel| Self(channel, TIMEOUT))
+            .map_err(from_dbus)
+    }
+
+    pub(crate) fn address(address: &str, peer: bool) -> Result<Self, Error> {
+        let mut channel = Channel::open_private(address).map_err(from_dbus)?;
+        // No Hello call, there is no bus to register with
+        if !peer {
+            channel.register().map_err(from_dbus)?;
+        }
+        Ok(Self(channel, TIMEOUT))
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.1 = timeout.min(TIMEOUT);
+    }
+
+    /// Sends `call` and waits for the reply, returning an error reply as a [`MethodError`].
+    fn send(&mut self, call: &MethodCall<'_>) -> Result<Message, Error> {
+        let mut msg = Message::new_method_call(call.dest, call.path, call.iface, call.method)
+            .map_err(Error::backend)?;
+        for arg in call.args {
+            msg = match *arg {
+                Arg::Bool(b) => msg.append1(b),
+                Arg::Str(s) => msg.append1(s),
+                Arg::StrArray(a) => msg.append1(a),
+            };
+        }
+        self.0
+            .send_with_reply_and_block(msg, self.1)
+            .map_err(from_dbus)
+    }
+
+    pub(crate) fn call_raw(
+        &mut self,
+        call: &MethodCall<'_>,
+        reply: ReplyType,
+    ) -> Result<Reply, Error> {
+        let msg = self.send(call)?;
+        Ok(match reply {
+            ReplyType::Unit => Reply::Unit,
+            ReplyType::Bool => Reply::Bool(read(&msg)?),
+            ReplyType::U32 => Reply::U32(read(&msg)?),
+            ReplyType::Str => Reply::Str(read(&msg)?),
+            ReplyType::StrArray => Reply::StrArray(read(&msg)?),
+            ReplyType::Bytes => Reply::Bytes(read(&msg)?),
+            ReplyType::BytesDict => Reply::BytesDict(read(&msg)?),
+        })
+    }
+
+    pub(crate) fn get_property_raw(
+        &mut self,
+        call: &MethodCall<'_>,
+        reply: ReplyType,
+    ) -> Result<Reply, Error> {
+        let msg = self.send(call)?;
+        Ok(match reply {
+            ReplyType::Unit => Reply::Unit,
+            ReplyType::Bool => Reply::Bool(read_variant(&msg)?),
+            ReplyType::U32 => Reply::U32(read_variant(&msg)?),
+            ReplyType::Str => Reply::Str(read_variant(&msg)?),
+            ReplyType::StrArray => Reply::StrArray(read_variant(&msg)?),
+            ReplyType::Bytes => Reply::Bytes(read_variant(&msg)?),
+            ReplyType::BytesDict => Reply::BytesDict(read_variant(&msg)?),
+        })
+    }
+}
@@ -0,0 +1,479 @@
+//! A minimal D-Bus client on top of whichever D-Bus crate is selected.
+//!
+//! Each backend provides a `Bus` type with a `call_raw` method that sends a method call built
+//! from [`Arg`]s and decodes the reply into a [`Reply`], and a `get_property_raw` method that does
+//! the same for a `Properties.Get` call, unwrapping the variant it returns. Everything else is
+//! written once on top of that.
+
+// Without a backend, no call is ever made, so nothing reads the calls or builds replies
+#![cfg_attr(
+    not(any(
+        feature = "rustbus",
+        feature = "zbus",
+        feature = "gio",
+        feature = "dbus",
+        feature = "dbus-send"
+    )),
+    allow(dead_code)
+)]
+
+use std::{collections::HashMap, env, fmt, fs, path::PathBuf, process::Stdio};
+
+use crate::{
+    cache::Cached, error, options::DBusAddress, process, Action, BackendInfo, Error, ShowOptions,
+};
+
+#[cfg(feature = "dbus-send")]
+mod command;
+#[cfg(feature = "dbus-send")]
+pub(crate) use self::command::Bus;
+#[cfg(not(any(
+    feature = "rustbus",
+    feature = "zbus",
+    feature = "gio",
+    feature = "dbus",
+    feature = "dbus-send"
+)))]
+mod none;
+#[cfg(not(any(
+    feature = "rustbus",
+    feature = "zbus",
+    feature = "gio",
+    feature = "dbus",
+    feature = "dbus-send"
+)))]
+pub(crate) use self::none::Bus;
+#[cfg(feature = "dbus")]
+mod libdbus;
+#[cfg(feature = "dbus")]
+pub(crate) use self::libdbus::Bus;
+#[cfg(feature = "gio")]
+mod gio;
+#[cfg(feature = "gio")]
+pub(crate) use self::gio::{gerror_to_error, to_cstring, Bus, GioError};
+#[cfg(feature = "rustbus")]
+mod rustbus;
+#[cfg(feature = "rustbus")]
+pub(crate) use self::rustbus::Bus;
+#[cfg(feature = "zbus")]
+mod zbus;
+#[cfg(feature = "zbus")]
+pub(crate) use self::zbus::Bus;
+
+pub(crate) const FILE_MANAGER1: &str = "org.freedesktop.FileManager1";
+pub(crate) const FILE_MANAGER1_PATH: &str = "/org/freedesktop/FileManager1";
+const DBUS: &str = "org.freedesktop.DBus";
+const DBUS_PATH: &str = "/org/freedesktop/DBus";
+const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+
+/// Describes the backend `name` that makes D-Bus calls, with the crate it makes them with, or
+/// returns `None` if this build can't make D-Bus calls.
+pub(crate) fn backend_info(name: &'static str) -> Option<BackendInfo> {
+    // The versions are the ones in Cargo.toml
+    let (library, version) = if cfg!(feature = "rustbus") {
+        ("rustbus", "0.19")
+    } else if cfg!(feature = "zbus") {
+        ("zbus", "3")
+    } else if cfg!(feature = "gio") {
+        ("gio-sys", "0.18")
+    } else if cfg!(feature = "dbus") {
+        ("dbus", "0.9")
+    } else if cfg!(feature = "dbus-send") {
+        return Some(BackendInfo::new(name, None));
+    } else {
+        return None;
+    };
+    Some(BackendInfo::new(name, Some((library, version))))
+}
+
+/// An argument to a method call.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Arg<'a> {
+    Bool(bool),
+    Str(&'a str),
+    StrArray(&'a [&'a str]),
+}
+
+/// The expected signature of a method reply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ReplyType {
+    Unit,
+    Bool,
+    U32,
+    Str,
+    StrArray,
+    Bytes,
+    BytesDict,
+}
+
+/// A decoded method reply.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Reply {
+    Unit,
+    Bool(bool),
+    U32(u32),
+    Str(String),
+    StrArray(Vec<String>),
+    Bytes(Vec<u8>),
+    BytesDict(HashMap<String, Vec<u8>>),
+}
+
+/// A value that can be decoded from a method reply.
+pub(crate) trait FromReply: Sized {
+    const TYPE: ReplyType;
+    fn from_reply(reply: Reply) -> Option<Self>;
+}
+
+impl FromReply for () {
+    const TYPE: ReplyType = ReplyType::Unit;
+    fn from_reply(_reply: Reply) -> Option<Self> {
+        Some(())
+    }
+}
+
+impl FromReply for bool {
+    const TYPE: ReplyType = ReplyType::Bool;
+    fn from_reply(reply: Reply) -> Option<Self> {
+        match reply {
+            Reply::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+impl FromReply for u32 {
+    const TYPE: ReplyType = ReplyType::U32;
+    fn from_reply(reply: Reply) -> Option<Self> {
+        match reply {
+            Reply::U32(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+impl FromReply for String {
+    const TYPE: ReplyType = ReplyType::Str;
+    fn from_reply(reply: Reply) -> Option<Self> {
+        match reply {
+            Reply::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl FromReply for Vec<String> {
+    const TYPE: ReplyType = ReplyType::StrArray;
+    fn from_reply(reply: Reply) -> Option<Self> {
+        match reply {
+            Reply::StrArray(a) => Some(a),
+            _ => None,
+        }
+    }
+}
+
+impl FromReply for Vec<u8> {
+    const TYPE: ReplyType = ReplyType::Bytes;
+    fn from_reply(reply: Reply) -> Option<Self> {
+        match reply {
+            Reply::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+impl FromReply for HashMap<String, Vec<u8>> {
+    const TYPE: ReplyType = ReplyType::BytesDict;
+    fn from_reply(reply: Reply) -> Option<Self> {
+        match reply {
+            Reply::BytesDict(d) => Some(d),
+            _ => None,
+        }
+    }
+}
+
+/// A method call to send on the bus.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MethodCall<'a> {
+    pub dest: &'a str,
+    pub path: &'a str,
+    pub iface: &'a str,
+    pub method: &'a str,
+    pub args: &'a [Arg<'a>],
+}
+
+impl MethodCall<'_> {
+    /// Describes the call for [`ShowOutcome::actions`](crate::ShowOutcome::actions).
+    pub(crate) fn action(&self) -> Action {
+        Action::MethodCall {
+            destination: self.dest.to_owned(),
+            path: self.path.to_owned(),
+            interface: self.iface.to_owned(),
+            method: self.method.to_owned(),
+            args: self
+                .args
+                .iter()
+                .map(|arg| match arg {
+                    Arg::Bool(b) => b.to_string(),
+                    Arg::Str(s) => format!("{s:?}"),
+                    Arg::StrArray(a) => format!("{a:?}"),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An error reply to a method call.
+#[derive(Debug)]
+pub(crate) struct MethodError {
+    pub name: String,
+    pub message: Option<String>,
+}
+
+impl MethodError {
+    /// Parses the error that `dbus-send` prints, such as
+    /// `Error org.freedesktop.DBus.Error.ServiceUnknown: The name ... was not provided`.
+    pub(crate) fn from_dbus_send(stderr: &str) -> Option<Self> {
+        let line = stderr
+            .lines()
+            .find_map(|line| line.strip_prefix("Error "))?;
+        let (name, message) = match line.split_once(": ") {
+            Some((name, message)) => (name, Some(message.to_owned())),
+            None => (line, None),
+        };
+        Some(Self {
+            name: name.trim().to_owned(),
+            message,
+        })
+    }
+
+    /// Checks whether the destination of the call does not exist and can't be activated.
+    pub(crate) fn is_service_unknown(&self) -> bool {
+        matches!(
+            self.name.as_str(),
+            "org.freedesktop.DBus.Error.ServiceUnknown"
+                | "org.freedesktop.DBus.Error.NameHasNoOwner"
+        )
+    }
+}
+
+impl fmt::Display for MethodError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "{}: {message}", self.name),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+impl std::error::Error for MethodError {}
+
+impl Error {
+    /// Returns the D-Bus error reply this error was created from, if any.
+    pub(crate) fn method_error(&self) -> Option<&MethodError> {
+        match self {
+            Self::Backend(err) => error::without_environment(&**err).downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the session bus when `DBUS_SESSION_BUS_ADDRESS` isn't set, such as after logging in on
+/// a text console or when started by a service manager that doesn't pass it on.
+///
+/// This is the user bus that systemd and dbus-broker provide at `$XDG_RUNTIME_DIR/bus`, or
+/// failing that, the bus that `dbus-launch` starts for the X11 display, or finds if one was
+/// already started for it, as libdbus does on its own. The address is kept until
+/// [`refresh`](crate::refresh), so that `dbus-launch` only runs once.
+fn fallback_session_address() -> Option<String> {
+    static ADDRESS: Cached<Option<String>> = Cached::new();
+    ADDRESS.get(|| {
+        let user_bus = env::var_os("XDG_RUNTIME_DIR").map(|dir| PathBuf::from(dir).join("bus"));
+        match user_bus {
+            Some(path) if path.exists() => {
+                let path = path.to_str()?;
+                Some(format!("unix:path={}", escape_address_value(path)))
+            }
+            _ => autolaunch_address(),
+        }
+    })
+}
+
+/// Escapes `value` for a D-Bus address, where only a few characters can appear as they are.
+fn escape_address_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for b in value.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_/.\\*".contains(&b) {
+            escaped.push(b as char);
+        } else {
+            escaped.push_str(&format!("%{b:02x}"));
+        }
+    }
+    escaped
+}
+
+/// Asks `dbus-launch` for the session bus of the X11 display, which it starts if needed.
+fn autolaunch_address() -> Option<String> {
+    env::var_os("DISPLAY")?;
+    let machine_id = ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())?;
+    let output = process::command("dbus-launch")
+        .arg(format!("--autolaunch={}", machine_id.trim()))
+        .args(["--sh-syntax", "--close-stderr"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // Prints `DBUS_SESSION_BUS_ADDRESS='unix:...';` among other variables
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| {
+            let value = line.strip_prefix("DBUS_SESSION_BUS_ADDRESS='")?;
+            Some(value.split('\'').next()?.to_owned())
+        })
+        .filter(|address| !address.is_empty())
+}
+
+impl Bus {
+    /// Connects to the session bus, or the one found by [`fallback_session_address`] if
+    /// `DBUS_SESSION_BUS_ADDRESS` isn't set.
+    pub(crate) fn find_session() -> Result<Self, Error> {
+        if env::var_os("DBUS_SESSION_BUS_ADDRESS").is_none() {
+            if let Some(address) = fallback_session_address() {
+                return Self::address(&address, false);
+            }
+        }
+        Self::session()
+    }
+
+    /// Connects to the bus or peer set in `options`, or the session bus by default, with calls
+    /// timing out after [`ShowOptions::timeout`] if set.
+    pub(crate) fn for_options(options: &ShowOptions) -> Result<Self, Error> {
+        let mut bus = match &options.dbus_address {
+            None => Self::find_session(),
+            Some(DBusAddress::Bus(address)) => Self::address(address, false),
+            Some(DBusAddress::Peer(address)) => Self::address(address, true),
+        }?;
+        if let Some(timeout) = options.timeout {
+            bus.set_timeout(timeout);
+        }
+        Ok(bus)
+    }
+
+    /// Calls a method and decodes its reply as `T`.
+    pub(crate) fn call<T: FromReply>(&mut self, call: &MethodCall<'_>) -> Result<T, Error> {
+        let reply = self.call_raw(call, T::TYPE)?;
+        T::from_reply(reply).ok_or_else(|| Error::backend("unexpected D-Bus reply type"))
+    }
+
+    /// Reads a property with `org.freedesktop.DBus.Properties.Get` and decodes it as `T`.
+    pub(crate) fn get_property<T: FromReply>(
+        &mut self,
+        dest: &str,
+        path: &str,
+        iface: &str,
+        name: &str,
+    ) -> Result<T, Error> {
+        let call = MethodCall {
+            dest,
+            path,
+            iface: PROPERTIES,
+            method: "Get",
+            args: &[Arg::Str(iface), Arg::Str(name)],
+        };
+        let reply = self.get_property_raw(&call, T::TYPE)?;
+        T::from_reply(reply).ok_or_else(|| Error::backend("unexpected D-Bus property type"))
+    }
+
+    /// Returns the unique name of the connection that owns `name`.
+    pub(crate) fn get_name_owner(&mut self, name: &str) -> Result<String, Error> {
+        self.call(&MethodCall {
+            dest: DBUS,
+            path: DBUS_PATH,
+            iface: DBUS,
+            method: "GetNameOwner",
+            args: &[Arg::Str(name)],
+        })
+    }
+
+    /// Returns the process ID of the connection that owns `name`.
+    pub(crate) fn get_connection_unix_process_id(&mut self, name: &str) -> Result<u32, Error> {
+        self.call(&MethodCall {
+            dest: DBUS,
+            path: DBUS_PATH,
+            iface: DBUS,
+            method: "GetConnectionUnixProcessID",
+            args: &[Arg::Str(name)],
+        })
+    }
+
+    /// Checks whether `name` currently has an owner.
+    pub(crate) fn name_has_owner(&mut self, name: &str) -> Result<bool, Error> {
+        self.call(&MethodCall {
+            dest: DBUS,
+            path: DBUS_PATH,
+            iface: DBUS,
+            method: "NameHasOwner",
+            args: &[Arg::Str(name)],
+        })
+    }
+
+    /// Returns the names that currently have an owner on the bus.
+    pub(crate) fn list_names(&mut self) -> Result<Vec<String>, Error> {
+        self.call(&MethodCall {
+            dest: DBUS,
+            path: DBUS_PATH,
+            iface: DBUS,
+            method: "ListNames",
+            args: &[],
+        })
+    }
+
+    /// Returns the names that can be started on demand by the bus.
+    pub(crate) fn list_activatable_names(&mut self) -> Result<Vec<String>, Error> {
+        self.call(&MethodCall {
+            dest: DBUS,
+            path: DBUS_PATH,
+            iface: DBUS,
+            method: "ListActivatableNames",
+            args: &[],
+        })
+    }
+
+    /// Returns the introspection XML of the object at `path`.
+    pub(crate) fn introspect(&mut self, dest: &str, path: &str) -> Result<String, Error> {
+        self.call(&MethodCall {
+            dest,
+            path,
+            iface: "org.freedesktop.DBus.Introspectable",
+            method: "Introspect",
+            args: &[],
+        })
+    }
+
+    /// Calls one of the `org.freedesktop.FileManager1` methods, which all take a list of URIs and
+    /// a startup ID.
+    pub(crate) fn call_file_manager(
+        &mut self,
+        method: &str,
+        uris: &[&str],
+        startup_id: &str,
+    ) -> Result<Action, Error> {
+        let call = MethodCall {
+            dest: FILE_MANAGER1,
+            path: FILE_MANAGER1_PATH,
+            iface: FILE_MANAGER1,
+            method,
+            args: &[Arg::StrArray(uris), Arg::Str(startup_id)],
+        };
+        self.call::<()>(&call)
+            .map(|()| call.action())
+            .map_err(|e| match e.method_error() {
+                Some(err) if err.is_service_unknown() => Error::NoFileManager,
+                _ => e,
+            })
+    }
+}
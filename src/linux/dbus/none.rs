@@ -0,0 +1,39 @@
+//! Stands in for a D-Bus backend when none of the backend features is enabled.
+//!
+//! No connection can be made, so only the fallbacks that run other programs, such as `xdg-open`,
+//! are used.
+
+use super::{MethodCall, Reply, ReplyType};
+use crate::Error;
+
+pub(crate) enum Bus {}
+
+impl Bus {
+    pub(crate) fn session() -> Result<Self, Error> {
+        Err(Error::Unsupported)
+    }
+
+    pub(crate) fn address(_address: &str, _peer: bool) -> Result<Self, Error> {
+        Err(Error::Unsupported)
+    }
+
+    pub(crate) fn set_timeout(&mut self, _timeout: std::time::Duration) {
+        match *self {}
+    }
+
+    pub(crate) fn call_raw(
+        &mut self,
+        _call: &MethodCall<'_>,
+        _reply: ReplyType,
+    ) -> Result<Reply, Error> {
+        match *self {}
+    }
+
+    pub(crate) fn get_property_raw(
+        &mut self,
+        _call: &MethodCall<'_>,
+        _reply: ReplyType,
+    ) -> Result<Reply, Error> {
+        match *self {}
+    }
+}
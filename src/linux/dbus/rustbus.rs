@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use ::rustbus::{
+    connection::{parse_dbus_addr_str, Timeout},
+    message_builder::{MarshalledMessage, MessageType},
+    wire::unmarshal::traits::Variant,
+    DuplexConn, MessageBuilder, RpcConn,
+};
+
+use super::{Arg, MethodCall, MethodError, Reply, ReplyType};
+use crate::Error;
+
+pub(crate) struct Bus(RpcConn, Timeout);
+
+impl Bus {
+    pub(crate) fn session() -> Result<Self, Error> {
+        RpcConn::session_conn(Timeout::Infinite)
+            .map(|conn| Self(conn, Timeout::Infinite))
+            .map_err(Error::backend)
+    }
+
+    pub(crate) fn address(address: &str, peer: bool) -> Result<Self, Error> {
+        let addr = parse_dbus_addr_str(address).map_err(Error::backend)?;
+        if peer {
+            // No Hello call, there is no bus to register with
+            let conn = DuplexConn::connect_to_bus(addr, true).map_err(Error::backend)?;
+            Ok(Self(RpcConn::new(conn), Timeout::Infinite))
+        } else {
+            RpcConn::connect_to_path(addr, Timeout::Infinite)
+                .map(|conn| Self(conn, Timeout::Infinite))
+                .map_err(Error::backend)
+        }
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.1 = Timeout::Duration(timeout);
+    }
+
+    /// Sends `call` and waits for the reply, returning an error reply as a [`MethodError`].
+    fn send(&mut self, call: &MethodCall<'_>) -> Result<MarshalledMessage, Error> {
+        let mut msg = MessageBuilder::new()
+            .call(call.method)
+            .on(call.path)
+            .with_interface(call.iface)
+            .at(call.dest)
+            .build();
+        for arg in call.args {
+            match *arg {
+                Arg::Bool(b) => msg.body.push_param(b),
+                Arg::Str(s) => msg.body.push_param(s),
+                Arg::StrArray(a) => msg.body.push_param(a),
+            }
+            .map_err(Error::backend)?;
+        }
+        let ctx = self.0.send_message(&mut msg).map_err(Error::backend)?;
+        let serial = ctx.write_all().map_err(|(_, e)| Error::backend(e))?;
+        let msg = self
+            .0
+            .wait_response(serial, self.1)
+            .map_err(Error::backend)?;
+        if msg.typ == MessageType::Error {
+            return Err(Error::backend(MethodError {
+                name: msg.dynheader.error_name.clone().unwrap_or_default(),
+                message: msg.body.parser().get::<String>().ok(),
+            }));
+        }
+        Ok(msg)
+    }
+
+    pub(crate) fn call_raw(
+        &mut self,
+        call: &MethodCall<'_>,
+        reply: ReplyType,
+    ) -> Result<Reply, Error> {
+        let msg = self.send(call)?;
+        let mut parser = msg.body.parser();
+        Ok(match reply {
+            ReplyType::Unit => Reply::Unit,
+            ReplyType::Bool => Reply::Bool(parser.get().map_err(Error::backend)?),
+            ReplyType::U32 => Reply::U32(parser.get().map_err(Error::backend)?),
+            ReplyType::Str => Reply::Str(parser.get().map_err(Error::backend)?),
+            ReplyType::StrArray => Reply::StrArray(parser.get().map_err(Error::backend)?),
+            ReplyType::Bytes => Reply::Bytes(parser.get().map_err(Error::backend)?),
+            ReplyType::BytesDict => Reply::BytesDict(parser.get().map_err(Error::backend)?),
+        })
+    }
+
+    pub(crate) fn get_property_raw(
+        &mut self,
+        call: &MethodCall<'_>,
+        reply: ReplyType,
+    ) -> Result<Reply, Error> {
+        let msg = self.send(call)?;
+        let value = msg.body.parser().get::<Variant>().map_err(Error::backend)?;
+        Ok(match reply {
+            ReplyType::Unit => Reply::Unit,
+            ReplyType::Bool => Reply::Bool(value.get().map_err(Error::backend)?),
+            ReplyType::U32 => Reply::U32(value.get().map_err(Error::backend)?),
+            ReplyType::Str => Reply::Str(value.get().map_err(Error::backend)?),
+            ReplyType::StrArray => Reply::StrArray(value.get().map_err(Error::backend)?),
+            ReplyType::Bytes => Reply::Bytes(value.get().map_err(Error::backend)?),
+            ReplyType::BytesDict => Reply::BytesDict(value.get().map_err(Error::backend)?),
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    future::{poll_fn, Future},
+    pin::{pin, Pin},
+    sync::Arc,
+    task::Poll,
+    time::Duration,
+};
+
+use ::zbus::{
+    blocking::{Connection, ConnectionBuilder},
+    zvariant::{OwnedValue, StructureBuilder},
+    Message,
+};
+use async_io::Timer;
+
+use super::{Arg, MethodCall, MethodError, Reply, ReplyType};
+use crate::Error;
+
+pub(crate) struct Bus(Connection, Option<Duration>);
+
+fn from_zbus(err: ::zbus::Error) -> Error {
+    match err {
+        ::zbus::Error::MethodError(name, message, _) => Error::backend(MethodError {
+            name: name.to_string(),
+            message,
+        }),
+        err => Error::backend(err),
+    }
+}
+
+impl Bus {
+    pub(crate) fn session() -> Result<Self, Error> {
+        Connection::session()
+            .map(|conn| Self(conn, None))
+            .map_err(Error::backend)
+    }
+
+    pub(crate) fn address(address: &str, peer: bool) -> Result<Self, Error> {
+        let builder = ConnectionBuilder::address(address).map_err(Error::backend)?;
+        let builder = if peer { builder.p2p() } else { builder };
+        builder
+            .build()
+            .map(|conn| Self(conn, None))
+            .map_err(Error::backend)
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.1 = Some(timeout);
+    }
+
+    /// Waits for the reply to a method call, giving up with [`Error::TimedOut`] after the
+    /// timeout if one is set.
+    ///
+    /// zbus 3 has no timeout for method calls, so the call is raced against a timer instead.
+    fn wait(
+        &self,
+        reply: impl Future<Output = ::zbus::Result<Arc<Message>>>,
+    ) -> Result<Arc<Message>, Error> {
+        let Some(timeout) = self.1 else {
+            return async_io::block_on(reply).map_err(from_zbus);
+        };
+        let mut reply = pin!(reply);
+        let mut timer = Timer::after(timeout);
+        async_io::block_on(poll_fn(|cx| {
+            if let Poll::Ready(res) = reply.as_mut().poll(cx) {
+                return Poll::Ready(res.map_err(from_zbus));
+            }
+            match Pin::new(&mut timer).poll(cx) {
+                Poll::Ready(_) => Poll::Ready(Err(Error::TimedOut)),
+                Poll::Pending => Poll::Pending,
+            }
+        }))
+    }
+
+    pub(crate) fn call_raw(
+        &mut self,
+        call: &MethodCall<'_>,
+        reply: ReplyType,
+    ) -> Result<Reply, Error> {
+        let msg = if call.args.is_empty() {
+            self.wait(self.0.inner().call_method(
+                Some(call.dest),
+                call.path,
+                Some(call.iface),
+                call.method,
+                &(),
+            ))?
+        } else {
+            let body = call
+                .args
+                .iter()
+                .fold(StructureBuilder::new(), |body, arg| match *arg {
+                    Arg::Bool(b) => body.add_field(b),
+                    Arg::Str(s) => body.add_field(s),
+                    Arg::StrArray(a) => body.add_field(a.to_vec()),
+                })
+                .build();
+            self.wait(self.0.inner().call_method(
+                Some(call.dest),
+                call.path,
+                Some(call.iface),
+                call.method,
+                &body,
+            ))?
+        };
+        Ok(match reply {
+            ReplyType::Unit => Reply::Unit,
+            ReplyType::Bool => Reply::Bool(msg.body().map_err(Error::backend)?),
+            ReplyType::U32 => Reply::U32(msg.body().map_err(Error::backend)?),
+            ReplyType::Str => Reply::Str(msg.body().map_err(Error::backend)?),
+            ReplyType::StrArray => Reply::StrArray(msg.body().map_err(Error::backend)?),
+            ReplyType::Bytes => Reply::Bytes(msg.body().map_err(Error::backend)?),
+            ReplyType::BytesDict => Reply::BytesDict(msg.body().map_err(Error::backend)?),
+        })
+    }
+
+    pub(crate) fn get_property_raw(
+        &mut self,
+        call: &MethodCall<'_>,
+        reply: ReplyType,
+    ) -> Result<Reply, Error> {
+        let [Arg::Str(iface), Arg::Str(name)] = *call.args else {
+            return Err(Error::backend("invalid property arguments"));
+        };
+        let msg = self.wait(self.0.inner().call_method(
+            Some(call.dest),
+            call.path,
+            Some(call.iface),
+            call.method,
+            &(iface, name),
+        ))?;
+        let value = msg.body::<OwnedValue>().map_err(Error::backend)?;
+        Ok(match reply {
+            ReplyType::Unit => Reply::Unit,
+            ReplyType::Bool => Reply::Bool(value.try_into().map_err(Error::backend)?),
+            ReplyType::U32 => Reply::U32(value.try_into().map_err(Error::backend)?),
+            ReplyType::Str => Reply::Str(value.try_into().map_err(Error::backend)?),
+            ReplyType::StrArray => Reply::StrArray(value.try_into().map_err(Error::backend)?),
+            ReplyType::Bytes => Reply::Bytes(value.try_into().map_err(Error::backend)?),
+            ReplyType::BytesDict => Reply::BytesDict(value.try_into().map_err(Error::backend)?),
+        })
+    }
+}
@@ -0,0 +1,250 @@
+//! Launching applications from their desktop entries.
+//!
+//! This implements just enough of the
+//! [Desktop Entry Specification](https://specifications.freedesktop.org/desktop-entry-spec/latest/)
+//! to find an application by its desktop file ID and run its `Exec` line with a file.
+
+use std::{
+    ffi::{OsStr, OsString},
+    fs,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    process::Child,
+    sync::{mpsc, Mutex},
+};
+
+use super::user::SessionUser;
+use crate::{args, process, threads, Error, FileUri};
+
+/// Returns the XDG data directories, in order of precedence.
+pub(crate) fn data_dirs(user: Option<&SessionUser>) -> Vec<PathBuf> {
+    let data_home = match user {
+        Some(user) => Some(user.home().join(".local/share")),
+        None => std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| Path::new(dir).is_absolute())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share"))),
+    };
+    let data_dirs = std::env::var_os("XDG_DATA_DIRS")
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    data_home
+        .into_iter()
+        .chain(std::env::split_paths(&data_dirs).filter(|dir| dir.is_absolute()))
+        .collect()
+}
+
+/// Returns the `applications` directories to search for desktop entries, in order of precedence.
+fn application_dirs(user: Option<&SessionUser>) -> Vec<PathBuf> {
+    data_dirs(user)
+        .into_iter()
+        .map(|dir| dir.join("applications"))
+        .collect()
+}
+
+/// Finds the desktop entry for the desktop file ID `id`, such as `org.gimp.GIMP.desktop`.
+///
+/// Entries in subdirectories have their ID prefixed with the directory name and a `-`, so
+/// `kde4-dolphin.desktop` may be `kde4/dolphin.desktop`.
+fn find(id: &str, user: Option<&SessionUser>) -> Option<PathBuf> {
+    if id.is_empty() || id.contains('/') {
+        return None;
+    }
+    let candidates = std::iter::once(PathBuf::from(id)).chain(
+        id.match_indices('-')
+            .map(|(i, _)| Path::new(&id[..i]).join(&id[i + 1..])),
+    );
+    let candidates = candidates.collect::<Vec<_>>();
+    application_dirs(user).into_iter().find_map(|dir| {
+        candidates
+            .iter()
+            .map(|candidate| dir.join(candidate))
+            .find(|path| path.is_file())
+    })
+}
+
+/// Finds the desktop file ID of the running process `pid`, whose executable is named `exe`.
+///
+/// Processes launched from their desktop entry by GIO have it in their environment. Otherwise,
+/// the desktop entries are searched for one that runs the same executable.
+pub(crate) fn find_for_process(pid: u32, exe: Option<&str>) -> Option<String> {
+    let environ = fs::read(format!("/proc/{pid}/environ")).unwrap_or_default();
+    let launched = environ
+        .split(|&b| b == 0)
+        .find_map(|var| var.strip_prefix(b"GIO_LAUNCHED_DESKTOP_FILE="));
+    if let Some(file) = launched {
+        let file = Path::new(OsStr::from_bytes(file));
+        if let Some(name) = file.file_name().and_then(OsStr::to_str) {
+            return Some(name.to_owned());
+        }
+    }
+    let exe = exe?;
+    application_dirs(None).into_iter().find_map(|dir| {
+        let entries = fs::read_dir(&dir).ok()?;
+        entries.flatten().find_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.ends_with(".desktop") {
+                return None;
+            }
+            let entry = fs::read_to_string(entry.path()).ok()?;
+            let exec = value(&entry, "Exec")?;
+            let program = split_exec(&exec)?.into_iter().next()?;
+            (Path::new(&program).file_name()? == exe).then_some(name)
+        })
+    })
+}
+
+/// Returns the unescaped value of `key` in the `[Desktop Entry]` group.
+fn value(entry: &str, key: &str) -> Option<String> {
+    group_value(entry, "Desktop Entry", key)
+}
+
+/// Returns the unescaped value of `key` in `group` of a file in the desktop entry format.
+pub(crate) fn group_value(file: &str, group: &str, key: &str) -> Option<String> {
+    let mut in_group = false;
+    for line in file.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_group = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) == Some(group);
+            continue;
+        }
+        if !in_group {
+            continue;
+        }
+        let Some((k, v)) = line.split_once('=') else {
+            continue;
+        };
+        if k.trim_end() != key {
+            continue;
+        }
+        let mut value = String::with_capacity(v.len());
+        let mut chars = v.trim_start().chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                value.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('s') => value.push(' '),
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('r') => value.push('\r'),
+                Some(c) => value.push(c),
+                None => {}
+            }
+        }
+        return Some(value);
+    }
+    None
+}
+
+/// Splits an `Exec` value into arguments, following its quoting rules.
+fn split_exec(exec: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut arg = None::<String>;
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' => args.extend(arg.take()),
+            '"' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => arg.push(chars.next()?),
+                        c => arg.push(c),
+                    }
+                }
+            }
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(arg);
+    Some(args)
+}
+
+/// Builds the command line to open `path` with the desktop entry at `entry_path`.
+fn exec_args(entry_path: &Path, path: &Path) -> Result<Vec<OsString>, Error> {
+    let entry = fs::read_to_string(entry_path).map_err(|_| Error::NoApplication)?;
+    let exec = value(&entry, "Exec").ok_or(Error::NoApplication)?;
+    let args = split_exec(&exec).ok_or(Error::NoApplication)?;
+    let mut has_file = false;
+    let mut out = Vec::with_capacity(args.len() + 1);
+    for arg in args {
+        match arg.as_str() {
+            "%f" | "%F" => {
+                has_file = true;
+                out.push(args::path(path).into_owned());
+                continue;
+            }
+            "%u" | "%U" => {
+                has_file = true;
+                out.push(FileUri::from_path(path)?.into_string().into());
+                continue;
+            }
+            // Icons and translated names aren't needed to open a file
+            "%i" | "%c" => continue,
+            _ => {}
+        }
+        let mut expanded = String::with_capacity(arg.len());
+        let mut chars = arg.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                expanded.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('%') => expanded.push('%'),
+                Some('k') => expanded.push_str(&entry_path.to_string_lossy()),
+                // Deprecated and unknown field codes are removed
+                _ => {}
+            }
+        }
+        out.push(expanded.into());
+    }
+    if out.is_empty() {
+        return Err(Error::NoApplication);
+    }
+    if !has_file {
+        out.push(args::path(path).into_owned());
+    }
+    Ok(out)
+}
+
+/// Opens `path` with the application whose desktop file ID is `id`, as `user` if set.
+pub(crate) fn launch(id: &str, path: &Path, user: Option<&SessionUser>) -> Result<(), Error> {
+    let entry_path = find(id, user).ok_or(Error::NoApplication)?;
+    let args = exec_args(&entry_path, path)?;
+    let mut cmd = match user {
+        Some(user) => user.command(&args[0]),
+        None => process::command(&args[0]),
+    };
+    let child = match cmd.args(&args[1..]).spawn() {
+        Ok(child) => child,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(Error::NoApplication),
+        Err(e) => return Err(Error::backend(e)),
+    };
+    reap(child);
+    Ok(())
+}
+
+/// Launched apps that are waited for on later launches, since no thread could be started for them.
+static UNREAPED: Mutex<Vec<Child>> = Mutex::new(Vec::new());
+
+/// Waits for a launched app to exit in the background, so that it doesn't become a zombie.
+pub(crate) fn reap(child: Child) {
+    let mut unreaped = UNREAPED.lock().unwrap();
+    unreaped.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+    let (tx, rx) = mpsc::channel::<Child>();
+    let waiter = threads::spawn("reaper", false, move || {
+        if let Ok(mut child) = rx.recv() {
+            let _ = child.wait();
+        }
+    });
+    match waiter {
+        Ok(Some(_)) => {
+            let _ = tx.send(child);
+        }
+        _ => unreaped.push(child),
+    }
+}
@@ -0,0 +1,78 @@
+//! Reusing a Dolphin window that already shows the folders, through the D-Bus interface Dolphin
+//! exports for its windows.
+//!
+//! This is what Dolphin does itself when started with `--select` while another instance is
+//! running: each instance owns a name such as `org.kde.dolphin-1234`, and its first window can be
+//! asked whether a folder is open in one of its tabs, to select files, and to come to the front.
+
+use super::{
+    dbus::{Arg, Bus, FromReply, MethodCall},
+    parent_uris,
+};
+use crate::{Action, Error};
+
+const INSTANCE_PREFIX: &str = "org.kde.dolphin-";
+const MAIN_WINDOW: &str = "org.kde.dolphin.MainWindow";
+const MAIN_WINDOW_PATH: &str = "/dolphin/Dolphin_1";
+
+/// Calls `method` on the main window of the Dolphin instance `name`.
+fn call_window<T: FromReply>(
+    bus: &mut Bus,
+    name: &str,
+    method: &str,
+    args: &[Arg<'_>],
+) -> Result<T, Error> {
+    bus.call(&window_call(name, method, args))
+}
+
+/// Builds the call to `method` on the main window of the Dolphin instance `name`.
+fn window_call<'a>(name: &'a str, method: &'a str, args: &'a [Arg<'a>]) -> MethodCall<'a> {
+    MethodCall {
+        dest: name,
+        path: MAIN_WINDOW_PATH,
+        iface: MAIN_WINDOW,
+        method,
+        args,
+    }
+}
+
+/// Selects `uris` in a running Dolphin window that already has all of their folders open, and
+/// brings it to the front.
+///
+/// Returns the unique name of the instance that showed them with the calls made to it, or `None`
+/// if no window had the folders open.
+pub(crate) fn show_in_open_window(
+    bus: &mut Bus,
+    uris: &[&str],
+    startup_id: &str,
+) -> Option<(String, Vec<Action>)> {
+    let names = bus.list_names().ok()?;
+    let folders = parent_uris(uris);
+    for name in names
+        .iter()
+        .filter(|name| name.starts_with(INSTANCE_PREFIX))
+    {
+        let all_open = folders.iter().all(|folder| {
+            call_window(bus, name, "isUrlOpen", &[Arg::Str(folder)]).unwrap_or(false)
+        });
+        if !all_open {
+            continue;
+        }
+        // Switches to the tab showing each folder and selects the files in it
+        let args = [Arg::StrArray(uris), Arg::Bool(false)];
+        if call_window::<()>(bus, name, "openFiles", &args).is_err() {
+            continue;
+        }
+        let mut actions = vec![window_call(name, "openFiles", &args).action()];
+        // Versions before 23.08 don't take an activation token
+        let args = [Arg::Str(startup_id)];
+        if call_window::<()>(bus, name, "activateWindow", &args).is_ok() {
+            actions.push(window_call(name, "activateWindow", &args).action());
+        } else if call_window::<()>(bus, name, "activateWindow", &[]).is_ok() {
+            actions.push(window_call(name, "activateWindow", &[]).action());
+        }
+        let owner = bus.get_name_owner(name).unwrap_or_else(|_| name.clone());
+        return Some((owner, actions));
+    }
+    None
+}
@@ -0,0 +1,159 @@
+//! Detection of the desktop environment, for behavior that depends on it and for reporting it
+//! with errors.
+
+use std::{env, fmt, path::Path};
+
+use super::portal;
+use crate::{cache::Cached, Error, SandboxKind, ShowOptions};
+
+/// The desktop environments that get special handling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Desktop {
+    /// Dolphin is the file manager, which can be started directly to select files faster than
+    /// through D-Bus activation.
+    Kde,
+    /// PCManFM is the file manager, which doesn't provide FileManager1.
+    Lxde,
+    /// The Linux container on ChromeOS, whose files are browsed with the Files app on the host,
+    /// which is reached through garcon.
+    Crostini,
+    Other,
+}
+
+impl Desktop {
+    /// Returns the desktop environment, detected the first time it is needed after each
+    /// [`refresh`](crate::refresh).
+    pub(crate) fn current() -> Self {
+        static DESKTOP: Cached<Desktop> = Cached::new();
+        DESKTOP.get(Self::detect)
+    }
+
+    /// Reads the desktop environment from `XDG_CURRENT_DESKTOP`, which lists names from most to
+    /// least specific, such as `ubuntu:GNOME`.
+    fn detect() -> Self {
+        // Crostini containers run a regular desktop environment's session, so check for it first
+        if Path::new("/dev/.cros_milestone").exists() {
+            return Self::Crostini;
+        }
+        let Some(names) = env::var_os("XDG_CURRENT_DESKTOP") else {
+            return Self::Other;
+        };
+        names
+            .to_string_lossy()
+            .split(':')
+            .find_map(|name| match name.to_ascii_lowercase().as_str() {
+                "kde" => Some(Self::Kde),
+                "lxde" => Some(Self::Lxde),
+                _ => None,
+            })
+            .unwrap_or(Self::Other)
+    }
+
+    /// Returns the current desktop environment, unless the heuristics are disabled in `options`
+    /// or a bus was set on purpose.
+    pub(crate) fn for_options(options: &ShowOptions) -> Self {
+        if options.desktop_heuristics && options.dbus_address.is_none() {
+            Self::current()
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// The parts of the environment that decide how files are shown on Linux, as attached to errors
+/// and returned by [`Error::environment`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Environment {
+    /// Whether `DISPLAY` is set, so that X11 applications can be started.
+    pub display: bool,
+    /// Whether `WAYLAND_DISPLAY` is set, so that Wayland applications can be started.
+    pub wayland_display: bool,
+    /// Whether `DBUS_SESSION_BUS_ADDRESS` is set, so that the session bus can be found.
+    pub session_bus_address: bool,
+    /// The type of the graphical session from `XDG_SESSION_TYPE`, such as `wayland`, `x11` or
+    /// `tty`.
+    pub session_type: Option<String>,
+    /// The desktop environment from `XDG_CURRENT_DESKTOP`, such as `ubuntu:GNOME`.
+    pub current_desktop: Option<String>,
+    /// The sandbox the process runs in, from the markers Flatpak and Snap leave.
+    pub sandbox: Option<SandboxKind>,
+}
+
+impl Environment {
+    pub(crate) fn capture() -> Self {
+        let var = |name| env::var_os(name).map(|value| value.to_string_lossy().into_owned());
+        Self {
+            display: env::var_os("DISPLAY").is_some(),
+            wayland_display: env::var_os("WAYLAND_DISPLAY").is_some(),
+            session_bus_address: env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some(),
+            session_type: var("XDG_SESSION_TYPE"),
+            current_desktop: var("XDG_CURRENT_DESKTOP"),
+            sandbox: portal::sandbox_kind(),
+        }
+    }
+}
+
+/// Formats the environment on one line, such as `DISPLAY set, WAYLAND_DISPLAY set,
+/// DBUS_SESSION_BUS_ADDRESS set, XDG_SESSION_TYPE=wayland, XDG_CURRENT_DESKTOP=GNOME`.
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let set = |is_set| if is_set { "set" } else { "unset" };
+        write!(
+            f,
+            "DISPLAY {}, WAYLAND_DISPLAY {}, DBUS_SESSION_BUS_ADDRESS {}",
+            set(self.display),
+            set(self.wayland_display),
+            set(self.session_bus_address)
+        )?;
+        for (name, value) in [
+            ("XDG_SESSION_TYPE", &self.session_type),
+            ("XDG_CURRENT_DESKTOP", &self.current_desktop),
+        ] {
+            match value {
+                Some(value) => write!(f, ", {name}={value}")?,
+                None => write!(f, ", {name} unset")?,
+            }
+        }
+        if let Some(sandbox) = self.sandbox {
+            write!(f, ", sandbox: {sandbox:?}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A backend error, with the environment it happened in.
+///
+/// This is transparent: it is displayed as the error it wraps, and has the same source.
+#[derive(Debug)]
+pub(crate) struct EnvironmentError {
+    pub(crate) source: Box<dyn std::error::Error + Send + Sync>,
+    pub(crate) environment: Environment,
+}
+
+impl fmt::Display for EnvironmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl std::error::Error for EnvironmentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.source()
+    }
+}
+
+/// Attaches the current environment to `err`, if it is a backend error without one.
+///
+/// The other variants of [`Error`] don't carry any data, so they are left as they are.
+pub(crate) fn attach(err: Error) -> Error {
+    match err {
+        Error::Backend(source) if !source.is::<EnvironmentError>() => {
+            Error::backend(EnvironmentError {
+                source,
+                environment: Environment::capture(),
+            })
+        }
+        err => err,
+    }
+}
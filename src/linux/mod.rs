@@ -0,0 +1,610 @@
+use std::{borrow::Cow, ffi::OsStr, path::Path};
+
+use crate::{
+    args, process, selection::Item, Action, Automount, BackendInfo, Capabilities, Diagnostics,
+    Error, ErrorKind, LocationStatus, Selection, ShowOptions, ShowOutcome, Warning,
+};
+
+mod activation;
+pub(crate) mod appimage;
+mod dbus;
+mod desktop;
+mod dolphin;
+mod environment;
+mod mounts;
+mod portal;
+mod provider;
+pub(crate) mod raw;
+mod user;
+mod vfs;
+
+use activation::StartupId;
+use dbus::Bus;
+use environment::Desktop;
+pub use environment::Environment;
+pub(crate) use environment::EnvironmentError;
+pub(crate) use portal::{portals, sandbox_kind};
+use provider::Methods;
+use user::SessionUser;
+
+#[cfg(not(feature = "gio"))]
+fn path_to_uri(path: &Path, options: &ShowOptions) -> Result<String, Error> {
+    let host_path = portal::host_path(path, options);
+    let path = host_path.as_deref().unwrap_or(path);
+    options.codec().encode(path)
+}
+
+#[cfg(feature = "gio")]
+fn path_to_uri(path: &Path, options: &ShowOptions) -> Result<String, Error> {
+    let host_path = portal::host_path(path, options);
+    let path = host_path.as_deref().unwrap_or(path);
+    if options.uri_codec.is_some() {
+        return options.codec().encode(path);
+    }
+    let uri = unsafe {
+        let path = dbus::to_cstring(path.as_os_str().as_encoded_bytes());
+        let file = gio_sys::g_file_new_for_path(path.as_ptr());
+        let uri = gio_sys::g_file_get_uri(file);
+        gobject_sys::g_object_unref(file as *mut _);
+        if uri.is_null() {
+            return Err(Error::InvalidPath);
+        }
+        let s = std::ffi::CStr::from_ptr(uri).to_str().map(str::to_owned);
+        glib_sys::g_free(uri as *mut _);
+        s.map_err(|_| Error::InvalidPath)?
+    };
+    if uri.is_empty() {
+        return Err(Error::InvalidPath);
+    }
+    Ok(uri)
+}
+
+pub(crate) fn show_path(path: &Path, options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    show_uris(&[&path_to_uri(path, options)?], options).map_err(environment::attach)
+}
+
+pub(crate) fn show_uri(uri: &str, options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    show_uris(&[uri], options).map_err(environment::attach)
+}
+
+/// The state of a [`RevealSession`](crate::RevealSession), which is empty since each item is
+/// sent with `ShowItems` again and the provider picks the window.
+#[derive(Debug, Default)]
+pub(crate) struct RevealState;
+
+pub(crate) fn reveal_path(
+    _state: &mut RevealState,
+    path: &Path,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    show_path(path, options)
+}
+
+pub(crate) fn reveal_uri(
+    _state: &mut RevealState,
+    uri: &str,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    show_uri(uri, options)
+}
+
+pub(crate) fn show_selection(
+    selection: &Selection,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    let (uris, results) = selection.prepare(|item| match item {
+        Item::Path(path) => path_to_uri(path, options).map(Cow::Owned),
+        Item::Uri(uri) => Ok(Cow::Borrowed(uri.as_str())),
+    })?;
+    let uris = uris.iter().map(|(_, uri)| uri.as_ref()).collect::<Vec<_>>();
+    Ok(ShowOutcome {
+        items: results.finish()?,
+        ..show_uris(&uris, options).map_err(environment::attach)?
+    })
+}
+
+/// Shows `uris`, first mounting the volumes they are on if [`ShowOptions::automount`] is set.
+fn show_uris(uris: &[&str], options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    let mut mounts = Vec::new();
+    if options.automount != Automount::Never {
+        let user = SessionUser::detect()?;
+        for uri in uris {
+            mounts.extend(vfs::mount_enclosing_volume(uri, user.as_ref())?);
+        }
+    }
+    let mut outcome = show_mounted_uris(uris, options)?;
+    outcome.actions.splice(0..0, mounts);
+    Ok(outcome)
+}
+
+/// Shows `uris` with the FileManager1 provider, falling back to `xdg-open` if enabled.
+///
+/// Unless disabled, this is adapted to the desktop environment: on KDE, Dolphin is started
+/// directly if no provider is running yet, on LXDE, the fallback is used straight away, and in
+/// Crostini, the folders are opened in the ChromeOS Files app. In a strictly confined Snap, which
+/// can't call the provider, the fallback is used straight away as well.
+///
+/// The outcome only reports that a window appeared when the provider replied, since `xdg-open`
+/// can't tell whether anything handled the folder.
+fn show_mounted_uris(uris: &[&str], options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    if let Some(backends) = &options.backends {
+        return show_with_backends(uris, backends, options);
+    }
+    let startup_id = StartupId::new(options);
+    let desktop = Desktop::for_options(options);
+    if desktop == Desktop::Crostini {
+        if let Ok(actions) = open_parents_with_garcon(uris) {
+            return Ok(ShowOutcome {
+                actions,
+                warnings: vec![Warning::NotSelected],
+                ..ShowOutcome::default()
+            });
+        }
+    }
+    if options.dbus_address.is_none() && options.fallback && portal::snap_strict() {
+        // The bus won't let the call through, but the xdg-open shim reaches the host
+        return open_parents_with_xdg_open(uris, None, &startup_id);
+    }
+    if options.dbus_address.is_none() {
+        if let Some(user) = SessionUser::detect()? {
+            return match user.call_file_manager("ShowItems", uris, startup_id.as_str()) {
+                Ok(action) => Ok(ShowOutcome::delivered(options, vec![action])),
+                Err(Error::NoFileManager) if options.fallback => {
+                    open_parents_with_xdg_open(uris, Some(&user), &startup_id)
+                }
+                Err(e) => Err(e),
+            };
+        }
+    }
+    let mut bus = match Bus::for_options(options) {
+        Ok(bus) => bus,
+        // Built without a D-Bus backend
+        Err(Error::Unsupported) if options.fallback => {
+            return open_parents_with_xdg_open(uris, None, &startup_id);
+        }
+        // No session bus could be found to reach a file manager on
+        Err(_) if options.fallback && options.dbus_address.is_none() => {
+            return open_parents_with_xdg_open(uris, None, &startup_id);
+        }
+        Err(e) => return Err(e),
+    };
+    if options.reuse_window {
+        let shown = dolphin::show_in_open_window(&mut bus, uris, startup_id.as_str());
+        if let Some((owner, actions)) = shown {
+            return Ok(ShowOutcome {
+                handler: options
+                    .identify_handler
+                    .then(|| provider::identify(&mut bus, owner)),
+                ..ShowOutcome::delivered(options, actions)
+            });
+        }
+    }
+    if desktop == Desktop::Kde && !provider::is_running(&mut bus) {
+        if let Ok(action) = launch_dolphin(uris, &startup_id) {
+            return Ok(ShowOutcome {
+                actions: vec![action],
+                ..ShowOutcome::default()
+            });
+        }
+    }
+    let methods = if desktop == Desktop::Lxde && !provider::is_running(&mut bus) {
+        // Don't wait for a provider to be activated, there usually isn't one
+        Methods::NONE
+    } else if options.introspect {
+        provider::methods(&mut bus)
+    } else {
+        Methods::ALL
+    };
+    let mut warnings = Vec::new();
+    let res = if methods.show_items {
+        bus.call_file_manager("ShowItems", uris, startup_id.as_str())
+    } else if methods.show_folders {
+        warnings.push(Warning::NotSelected);
+        bus.call_file_manager("ShowFolders", &parent_uris(uris), startup_id.as_str())
+    } else {
+        Err(Error::NoFileManager)
+    };
+    match res {
+        Ok(action) => Ok(ShowOutcome {
+            handler: options
+                .identify_handler
+                .then(|| provider::handler(&mut bus))
+                .flatten(),
+            warnings,
+            ..ShowOutcome::delivered(options, vec![action])
+        }),
+        Err(Error::NoFileManager) if options.fallback => {
+            open_parents_with_xdg_open(uris, None, &startup_id)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Shows `uris` with the first of `backends` that works, as set with [`ShowOptions::backends`].
+fn show_with_backends(
+    uris: &[&str],
+    backends: &[String],
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    let startup_id = StartupId::new(options);
+    let user = match options.dbus_address {
+        Some(_) => None,
+        None => SessionUser::detect()?,
+    };
+    let mut last_error = Error::NoFileManager;
+    for backend in backends {
+        let res = match backend.as_str() {
+            "file-manager1" => {
+                call_file_manager1("ShowItems", uris, user.as_ref(), &startup_id, options)
+            }
+            "dolphin" => launch_dolphin(uris, &startup_id).map(|action| ShowOutcome {
+                actions: vec![action],
+                ..ShowOutcome::default()
+            }),
+            "garcon" => open_parents_with_garcon(uris).map(|actions| ShowOutcome {
+                actions,
+                warnings: vec![Warning::NotSelected],
+                ..ShowOutcome::default()
+            }),
+            "xdg-open" => open_parents_with_xdg_open(uris, user.as_ref(), &startup_id),
+            _ => continue,
+        };
+        match res {
+            Ok(outcome) => return Ok(outcome),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Calls `method` on the FileManager1 provider with `uris`, as `user` if set, without any
+/// fallback.
+fn call_file_manager1(
+    method: &str,
+    uris: &[&str],
+    user: Option<&SessionUser>,
+    startup_id: &StartupId,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    if let Some(user) = user {
+        let action = user.call_file_manager(method, uris, startup_id.as_str())?;
+        return Ok(ShowOutcome::delivered(options, vec![action]));
+    }
+    let mut bus = Bus::for_options(options)?;
+    let action = bus.call_file_manager(method, uris, startup_id.as_str())?;
+    Ok(ShowOutcome {
+        handler: options
+            .identify_handler
+            .then(|| provider::handler(&mut bus))
+            .flatten(),
+        ..ShowOutcome::delivered(options, vec![action])
+    })
+}
+
+pub(crate) fn show_properties(path: &Path, options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    let uri = path_to_uri(path, options)?;
+    let startup_id = StartupId::new(options);
+    let user = match options.dbus_address {
+        Some(_) => None,
+        None => SessionUser::detect()?,
+    };
+    call_file_manager1(
+        "ShowItemProperties",
+        &[&uri],
+        user.as_ref(),
+        &startup_id,
+        options,
+    )
+    .map_err(environment::attach)
+}
+
+pub(crate) fn backend_error_kind(err: &(dyn std::error::Error + 'static)) -> Option<ErrorKind> {
+    #[cfg(feature = "gio")]
+    if let Some(err) = err.downcast_ref::<dbus::GioError>() {
+        return err.kind();
+    }
+    let err = err.downcast_ref::<dbus::MethodError>()?;
+    let name = err.name.strip_prefix("org.freedesktop.DBus.Error.")?;
+    match name {
+        "NoReply" | "Timeout" | "TimedOut" => Some(ErrorKind::TimedOut),
+        "UnknownMethod" | "UnknownInterface" | "UnknownObject" | "NotSupported" => {
+            Some(ErrorKind::Unsupported)
+        }
+        "AccessDenied" | "AuthFailed" => Some(ErrorKind::PermissionDenied),
+        "FileNotFound" => Some(ErrorKind::NotFound),
+        _ => None,
+    }
+}
+
+pub(crate) fn file_manager_available() -> bool {
+    !portal::snap_strict()
+        && Bus::find_session().is_ok_and(|mut bus| provider::is_available(&mut bus))
+}
+
+pub(crate) fn diagnose(diagnostics: &mut Diagnostics) {
+    let environment = Environment::capture();
+    diagnostics.session_type = environment.session_type;
+    diagnostics.desktop = environment.current_desktop;
+    match Bus::find_session() {
+        Ok(mut bus) => {
+            diagnostics.session_bus = Some(true);
+            diagnostics.file_manager1_owner = provider::handler(&mut bus);
+        }
+        Err(e) => {
+            diagnostics.session_bus = Some(false);
+            diagnostics
+                .problems
+                .push(format!("session bus: {}", crate::error::report(&e)));
+        }
+    }
+    if portal::snap_strict() {
+        diagnostics.problems.push(
+            "strict Snap confinement: FileManager1 can't be called, folders are opened with \
+             xdg-open instead"
+                .to_owned(),
+        );
+    }
+}
+
+pub(crate) fn backends() -> Vec<BackendInfo> {
+    let dbus = ["file-manager1", "documents-portal"]
+        .into_iter()
+        .filter_map(dbus::backend_info);
+    let commands = ["dolphin", "garcon", "xdg-open"].map(|name| BackendInfo::new(name, None));
+    dbus.chain(commands).collect()
+}
+
+pub(crate) fn capabilities() -> Capabilities {
+    let methods = Bus::find_session()
+        .ok()
+        .filter(|_| !portal::snap_strict())
+        .and_then(|mut bus| provider::is_available(&mut bus).then(|| provider::methods(&mut bus)));
+    match methods {
+        Some(methods) => Capabilities {
+            multi_select: methods.show_items,
+            open_directory: methods.show_folders,
+            rename_mode: false,
+            properties_dialog: methods.show_item_properties,
+            uri_reveal: true,
+        },
+        // Only the xdg-open fallback, which opens folders
+        None => Capabilities {
+            open_directory: true,
+            ..Default::default()
+        },
+    }
+}
+
+pub(crate) fn supports_uri_scheme(scheme: &str) -> bool {
+    vfs::supports_scheme(scheme)
+}
+
+pub(crate) fn path_location_status(path: &Path) -> LocationStatus {
+    mounts::location_status(path)
+}
+
+pub(crate) fn uri_location_status(uri: &str) -> LocationStatus {
+    vfs::location_status(uri, SessionUser::detect().ok().flatten().as_ref())
+}
+
+/// Returns the URI of the folder containing `uri`.
+fn parent_uri(uri: &str) -> &str {
+    let uri = uri.split(['?', '#']).next().unwrap_or(uri);
+    let trimmed = uri.trim_end_matches('/');
+    let path_start = match trimmed.find("://") {
+        Some(pos) => trimmed[pos + 3..]
+            .find('/')
+            .map_or(trimmed.len(), |p| pos + 3 + p),
+        None => 0,
+    };
+    match trimmed[path_start..].rfind('/') {
+        Some(0) => &uri[..path_start + 1],
+        Some(pos) => &trimmed[..path_start + pos],
+        None => uri,
+    }
+}
+
+/// Returns the URIs of the folders containing `uris`, without duplicates.
+fn parent_uris<'a>(uris: &[&'a str]) -> Vec<&'a str> {
+    let mut parents = Vec::with_capacity(uris.len());
+    for uri in uris {
+        let parent = parent_uri(uri);
+        if !parents.contains(&parent) {
+            parents.push(parent);
+        }
+    }
+    parents
+}
+
+/// Opens `target` with `xdg-open`, as `user` if set, passing on `startup_id` so that the
+/// application it launches can take focus.
+fn xdg_open(
+    target: &OsStr,
+    user: Option<&SessionUser>,
+    startup_id: &StartupId,
+) -> Result<Action, Error> {
+    let mut cmd = match user {
+        Some(user) => user.command("xdg-open"),
+        None => process::command("xdg-open"),
+    };
+    startup_id.apply(&mut cmd);
+    let status = match cmd.arg(args::path(target)).status() {
+        Ok(status) => status,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(Error::NoApplication),
+        Err(e) => return Err(Error::backend(e)),
+    };
+    // See the exit codes in xdg-open(1)
+    match status.code() {
+        Some(0) => Ok(Action::command(&cmd)),
+        Some(2) => Err(Error::InvalidPath),
+        Some(3 | 4) => Err(Error::NoApplication),
+        _ => Err(Error::backend(format!("xdg-open failed: {status}"))),
+    }
+}
+
+/// The client ChromeOS installs in Crostini containers to make requests to the host.
+const GARCON: &str = "/opt/google/cros-containers/bin/garcon";
+
+/// Opens the folders containing `uris` with garcon, which has ChromeOS show them in the Files
+/// app. The files aren't selected.
+fn open_parents_with_garcon(uris: &[&str]) -> Result<Vec<Action>, Error> {
+    let mut actions = Vec::new();
+    for parent in parent_uris(uris) {
+        let mut cmd = process::command(GARCON);
+        cmd.args(["--client", "--url", args::uri(parent)?]);
+        let status = match cmd.status() {
+            Ok(status) => status,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(Error::NoFileManager),
+            Err(e) => return Err(Error::backend(e)),
+        };
+        if !status.success() {
+            return Err(Error::backend(format!("garcon failed: {status}")));
+        }
+        actions.push(Action::command(&cmd));
+    }
+    Ok(actions)
+}
+
+/// Starts Dolphin to select `uris`, without waiting for it to exit.
+fn launch_dolphin(uris: &[&str], startup_id: &StartupId) -> Result<Action, Error> {
+    let mut cmd = process::command("dolphin");
+    startup_id.apply(&mut cmd);
+    cmd.arg("--select");
+    for uri in uris {
+        cmd.arg(args::uri(uri)?);
+    }
+    match cmd.spawn() {
+        Ok(child) => {
+            desktop::reap(child);
+            Ok(Action::command(&cmd))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(Error::NoApplication),
+        Err(e) => Err(Error::backend(e)),
+    }
+}
+
+/// Opens the folders containing `uris` with `xdg-open`, as `user` if set.
+///
+/// The files won't be selected, but this at least shows the right folders when no FileManager1
+/// provider is available.
+fn open_parents_with_xdg_open(
+    uris: &[&str],
+    user: Option<&SessionUser>,
+    startup_id: &StartupId,
+) -> Result<ShowOutcome, Error> {
+    let mut outcome = ShowOutcome {
+        warnings: vec![Warning::Fallback, Warning::NotSelected],
+        ..ShowOutcome::default()
+    };
+    for parent in parent_uris(uris) {
+        let action = xdg_open(parent.as_ref(), user, startup_id).map_err(|e| match e {
+            Error::NoApplication => Error::NoFileManager,
+            e => e,
+        })?;
+        outcome.actions.push(action);
+    }
+    Ok(outcome)
+}
+
+#[cfg(not(feature = "gio"))]
+pub(crate) fn open_path(path: &Path) -> Result<(), Error> {
+    if !path.is_absolute() {
+        return Err(Error::InvalidPath);
+    }
+    xdg_open(
+        path.as_os_str(),
+        SessionUser::detect()?.as_ref(),
+        &StartupId::default(),
+    )
+    .map(drop)
+}
+
+#[cfg(feature = "gio")]
+pub(crate) fn open_path(path: &Path) -> Result<(), Error> {
+    if !path.is_absolute() {
+        return Err(Error::InvalidPath);
+    }
+    if let Some(user) = SessionUser::detect()? {
+        return xdg_open(path.as_os_str(), Some(&user), &StartupId::default()).map(drop);
+    }
+    unsafe {
+        let path = dbus::to_cstring(path.as_os_str().as_encoded_bytes());
+        let file = gio_sys::g_file_new_for_path(path.as_ptr());
+        let uri = gio_sys::g_file_get_uri(file);
+        gobject_sys::g_object_unref(file as *mut _);
+        if uri.is_null() {
+            return Err(Error::InvalidPath);
+        }
+        let mut err = std::ptr::null_mut();
+        let launched =
+            gio_sys::g_app_info_launch_default_for_uri(uri, std::ptr::null_mut(), &mut err);
+        glib_sys::g_free(uri as *mut _);
+        if launched != 0 {
+            return Ok(());
+        }
+        if (*err).domain == gio_sys::g_io_error_quark() {
+            let kind = match (*err).code {
+                gio_sys::G_IO_ERROR_NOT_FOUND => Some(Error::InvalidPath),
+                gio_sys::G_IO_ERROR_NOT_SUPPORTED => Some(Error::NoApplication),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                glib_sys::g_error_free(err);
+                return Err(kind);
+            }
+        }
+        Err(dbus::gerror_to_error(err))
+    }
+}
+
+#[cfg(not(feature = "gio"))]
+pub(crate) fn open_path_with(app: &str, path: &Path) -> Result<(), Error> {
+    if !path.is_absolute() {
+        return Err(Error::InvalidPath);
+    }
+    desktop::launch(&desktop_id(app), path, SessionUser::detect()?.as_ref())
+}
+
+#[cfg(feature = "gio")]
+pub(crate) fn open_path_with(app: &str, path: &Path) -> Result<(), Error> {
+    if !path.is_absolute() {
+        return Err(Error::InvalidPath);
+    }
+    let id = desktop_id(app);
+    if let Some(user) = SessionUser::detect()? {
+        return desktop::launch(&id, path, Some(&user));
+    }
+    unsafe {
+        let id = dbus::to_cstring(id.as_bytes());
+        let app = gio_sys::g_desktop_app_info_new(id.as_ptr());
+        if app.is_null() {
+            return Err(Error::NoApplication);
+        }
+        let path = dbus::to_cstring(path.as_os_str().as_encoded_bytes());
+        let file = gio_sys::g_file_new_for_path(path.as_ptr());
+        let files = glib_sys::g_list_append(std::ptr::null_mut(), file as *mut _);
+        let mut err = std::ptr::null_mut();
+        let launched = gio_sys::g_app_info_launch(
+            app as *mut gio_sys::GAppInfo,
+            files,
+            std::ptr::null_mut(),
+            &mut err,
+        );
+        glib_sys::g_list_free(files);
+        gobject_sys::g_object_unref(file as *mut _);
+        gobject_sys::g_object_unref(app as *mut _);
+        if launched == 0 {
+            return Err(dbus::gerror_to_error(err));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the desktop file ID for `app`, adding the `.desktop` suffix if it's missing.
+fn desktop_id(app: &str) -> Cow<'_, str> {
+    if app.ends_with(".desktop") {
+        Cow::Borrowed(app)
+    } else {
+        Cow::Owned(format!("{app}.desktop"))
+    }
+}
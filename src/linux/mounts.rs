@@ -0,0 +1,116 @@
+//! Finding the file system a path is on, from the mount table.
+
+use std::{
+    ffi::OsString,
+    fs,
+    os::unix::ffi::OsStringExt,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::LocationStatus;
+
+/// File system types that store files on another machine.
+const NETWORK_TYPES: &[&str] = &[
+    "9p",
+    "afs",
+    "ceph",
+    "cifs",
+    "davfs",
+    "fuse.davfs2",
+    "fuse.gvfsd-fuse",
+    "fuse.rclone",
+    "fuse.s3fs",
+    "fuse.sshfs",
+    "glusterfs",
+    "lustre",
+    "ncpfs",
+    "nfs",
+    "nfs4",
+    "smb3",
+    "smbfs",
+];
+
+/// A mounted file system.
+struct Mount {
+    point: PathBuf,
+    fs_type: String,
+}
+
+/// Decodes the octal escapes the kernel uses for spaces and other special characters in the
+/// mount table.
+fn unescape(field: &str) -> PathBuf {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let code = bytes
+            .get(i + 1..i + 4)
+            .filter(|_| bytes[i] == b'\\')
+            .and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok());
+        match code {
+            Some(b) => {
+                out.push(b);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    PathBuf::from(OsString::from_vec(out))
+}
+
+/// Reads the file systems mounted in the namespace of the process.
+fn mounts() -> Vec<Mount> {
+    let Ok(table) = fs::read_to_string("/proc/self/mounts") else {
+        return Vec::new();
+    };
+    table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ').skip(1);
+            Some(Mount {
+                point: unescape(fields.next()?),
+                fs_type: fields.next()?.to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Returns the folder a removable volume that `path` is on is mounted at by udisks, such as
+/// `/media/alice/USB` or `/run/media/alice/USB`.
+fn removable_volume(path: &Path) -> Option<PathBuf> {
+    let base = ["/run/media", "/media"]
+        .into_iter()
+        .map(Path::new)
+        .find(|base| path.starts_with(base))?;
+    let mut components = path.strip_prefix(base).ok()?.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(user)), Some(Component::Normal(name))) => {
+            Some(base.join(user).join(name))
+        }
+        _ => None,
+    }
+}
+
+/// Returns where `path` is stored, from the file system it is on.
+pub(crate) fn location_status(path: &Path) -> LocationStatus {
+    let mounts = mounts();
+    let mount = mounts
+        .iter()
+        .filter(|mount| path.starts_with(&mount.point))
+        .max_by_key(|mount| mount.point.components().count());
+    if let Some(volume) = removable_volume(path) {
+        // The volume folder is only a mount point while the drive is mounted
+        if !mount.is_some_and(|mount| mount.point.starts_with(&volume)) {
+            return LocationStatus::RemovableMissing;
+        }
+    }
+    match mount {
+        Some(mount) if NETWORK_TYPES.contains(&mount.fs_type.as_str()) => {
+            LocationStatus::NetworkMounted
+        }
+        _ => LocationStatus::Local,
+    }
+}
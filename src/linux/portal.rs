@@ -0,0 +1,140 @@
+//! Queries for the XDG desktop portals.
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    os::unix::ffi::OsStringExt,
+    path::{Component, Path, PathBuf},
+};
+
+use super::dbus::{Arg, Bus, MethodCall};
+use crate::{cache::Cached, Error, Portal, SandboxKind, ShowOptions};
+
+const DESKTOP: &str = "org.freedesktop.portal.Desktop";
+const DESKTOP_PATH: &str = "/org/freedesktop/portal/desktop";
+const DOCUMENTS: &str = "org.freedesktop.portal.Documents";
+const DOCUMENTS_PATH: &str = "/org/freedesktop/portal/documents";
+
+pub(crate) fn sandbox_kind() -> Option<SandboxKind> {
+    static SANDBOX: Cached<Option<SandboxKind>> = Cached::new();
+    SANDBOX.get(|| {
+        if Path::new("/.flatpak-info").exists() {
+            Some(SandboxKind::Flatpak)
+        } else if std::env::var_os("SNAP").is_some() {
+            Some(SandboxKind::Snap)
+        } else {
+            None
+        }
+    })
+}
+
+/// Checks whether the process runs in a Snap with strict confinement.
+///
+/// Strictly confined snaps can't call FileManager1 on the session bus, but `xdg-open` inside them
+/// is a shim that asks snapd's user session agent to open the URI on the host. The confinement is
+/// read from the snap's `meta/snap.yaml`, where it defaults to strict.
+pub(crate) fn snap_strict() -> bool {
+    static STRICT: Cached<bool> = Cached::new();
+    STRICT.get(|| {
+        if sandbox_kind() != Some(SandboxKind::Snap) {
+            return false;
+        }
+        let Some(snap) = std::env::var_os("SNAP") else {
+            return false;
+        };
+        let Ok(yaml) = fs::read_to_string(Path::new(&snap).join("meta/snap.yaml")) else {
+            return true;
+        };
+        let confinement = yaml
+            .lines()
+            .find_map(|line| line.strip_prefix("confinement:"))
+            .map(|value| value.trim().trim_matches(['"', '\'']));
+        !matches!(confinement, Some("classic" | "devmode"))
+    })
+}
+
+/// Returns the names of the interfaces in introspection XML.
+fn interface_names(xml: &str) -> impl Iterator<Item = &str> {
+    xml.split("<interface name=").skip(1).filter_map(|s| {
+        let quote = s.chars().next()?;
+        s[1..].split(quote).next()
+    })
+}
+
+pub(crate) fn portals() -> Result<Vec<Portal>, Error> {
+    let mut bus = Bus::find_session()?;
+    let xml = match bus.introspect(DESKTOP, DESKTOP_PATH) {
+        Ok(xml) => xml,
+        Err(e) if e.method_error().is_some_and(|e| e.is_service_unknown()) => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(interface_names(&xml)
+        .filter(|name| name.starts_with("org.freedesktop.portal."))
+        .filter_map(|interface| {
+            let version = bus
+                .get_property(DESKTOP, DESKTOP_PATH, interface, "version")
+                .ok()?;
+            Some(Portal {
+                interface: interface.to_owned(),
+                version,
+            })
+        })
+        .collect())
+}
+
+/// Converts a byte array returned by the Documents portal, which includes a trailing nul, into a
+/// path.
+fn bytes_to_path(mut bytes: Vec<u8>) -> PathBuf {
+    if bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+    PathBuf::from(OsString::from_vec(bytes))
+}
+
+/// Translates a path in the Documents portal's FUSE mount into the path of the file on the host.
+///
+/// Files passed into a Flatpak app are exported through the Documents portal, and show up inside
+/// the sandbox as `/run/user/$UID/doc/$DOC_ID/$NAME`, or
+/// `/run/user/$UID/doc/by-app/$APP_ID/$DOC_ID/$NAME`. The host file manager can't resolve these
+/// paths, so they are translated back with `GetHostPaths`. Returns `None` if `path` is not a
+/// document path, or if the portal is too old to support the translation.
+pub(crate) fn host_path(path: &Path, options: &ShowOptions) -> Option<PathBuf> {
+    if sandbox_kind() != Some(SandboxKind::Flatpak) {
+        return None;
+    }
+    let mut bus = Bus::for_options(options).ok()?;
+    let mount_point: Vec<u8> = bus
+        .call(&MethodCall {
+            dest: DOCUMENTS,
+            path: DOCUMENTS_PATH,
+            iface: DOCUMENTS,
+            method: "GetMountPoint",
+            args: &[],
+        })
+        .ok()?;
+    let mut components = path
+        .strip_prefix(bytes_to_path(mount_point))
+        .ok()?
+        .components();
+    let mut doc_id = components.next()?;
+    if doc_id == Component::Normal("by-app".as_ref()) {
+        components.next()?;
+        doc_id = components.next()?;
+    }
+    let doc_id = doc_id.as_os_str().to_str()?;
+    let mut host_paths: HashMap<String, Vec<u8>> = bus
+        .call(&MethodCall {
+            dest: DOCUMENTS,
+            path: DOCUMENTS_PATH,
+            iface: DOCUMENTS,
+            method: "GetHostPaths",
+            args: &[Arg::StrArray(&[doc_id])],
+        })
+        .ok()?;
+    let mut host_path = bytes_to_path(host_paths.remove(doc_id)?);
+    // The first component is the name of the exported file or folder itself
+    components.next();
+    host_path.extend(components);
+    Some(host_path)
+}
@@ -0,0 +1,116 @@
+//! Detection of what the `org.freedesktop.FileManager1` provider supports.
+
+use std::{fs, sync::Mutex};
+
+use super::{
+    dbus::{Bus, FILE_MANAGER1, FILE_MANAGER1_PATH},
+    desktop,
+};
+use crate::Handler;
+
+/// The FileManager1 methods implemented by a provider.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Methods {
+    pub show_items: bool,
+    pub show_folders: bool,
+    pub show_item_properties: bool,
+}
+
+impl Methods {
+    /// Assumed when the provider can't be introspected.
+    pub const ALL: Self = Self {
+        show_items: true,
+        show_folders: true,
+        show_item_properties: true,
+    };
+
+    /// Used when there is known to be no provider, so that the fallback is used straight away.
+    pub const NONE: Self = Self {
+        show_items: false,
+        show_folders: false,
+        show_item_properties: false,
+    };
+
+    /// Reads the implemented methods from introspection XML.
+    fn parse(xml: &str) -> Self {
+        let iface = ["\"", "'"].iter().find_map(|q| {
+            let start = xml.find(&format!("<interface name={q}{FILE_MANAGER1}{q}"))?;
+            let iface = &xml[start..];
+            Some(&iface[..iface.find("</interface>").unwrap_or(iface.len())])
+        });
+        let has = |method: &str| {
+            iface.is_some_and(|iface| {
+                iface.contains(&format!("<method name=\"{method}\""))
+                    || iface.contains(&format!("<method name='{method}'"))
+            })
+        };
+        Self {
+            show_items: has("ShowItems"),
+            show_folders: has("ShowFolders"),
+            show_item_properties: has("ShowItemProperties"),
+        }
+    }
+}
+
+/// Checks whether a provider is running, without considering ones the bus could activate.
+pub(crate) fn is_running(bus: &mut Bus) -> bool {
+    bus.name_has_owner(FILE_MANAGER1).unwrap_or(false)
+}
+
+/// Checks whether a provider is running, or can be activated by the bus.
+pub(crate) fn is_available(bus: &mut Bus) -> bool {
+    is_running(bus)
+        || bus
+            .list_activatable_names()
+            .is_ok_and(|names| names.iter().any(|name| name == FILE_MANAGER1))
+}
+
+/// Introspects the current provider to find which methods it implements.
+///
+/// The result is cached for as long as the same connection owns the FileManager1 name. If no
+/// provider is running, or it doesn't support introspection, all methods are assumed to be
+/// available so that the call itself can activate the provider or report the error.
+pub(crate) fn methods(bus: &mut Bus) -> Methods {
+    static CACHE: Mutex<Option<(String, Methods)>> = Mutex::new(None);
+
+    let Ok(owner) = bus.get_name_owner(FILE_MANAGER1) else {
+        return Methods::ALL;
+    };
+    if let Some((cached_owner, methods)) = &*CACHE.lock().unwrap() {
+        if *cached_owner == owner {
+            return *methods;
+        }
+    }
+    let Ok(xml) = bus.introspect(&owner, FILE_MANAGER1_PATH) else {
+        return Methods::ALL;
+    };
+    let methods = Methods::parse(&xml);
+    *CACHE.lock().unwrap() = Some((owner, methods));
+    methods
+}
+
+/// Identifies the process that owns the FileManager1 name.
+///
+/// Returns `None` if there is no owner, such as when calling a peer directly.
+pub(crate) fn handler(bus: &mut Bus) -> Option<Handler> {
+    let owner = bus.get_name_owner(FILE_MANAGER1).ok()?;
+    Some(identify(bus, owner))
+}
+
+/// Identifies the process behind the unique connection name `owner`.
+pub(crate) fn identify(bus: &mut Bus, owner: String) -> Handler {
+    let pid = bus.get_connection_unix_process_id(&owner).ok();
+    let exe = pid.and_then(|pid| {
+        let exe = fs::read_link(format!("/proc/{pid}/exe")).ok()?;
+        Some(exe.file_name()?.to_string_lossy().into_owned())
+    });
+    let desktop_entry = pid.and_then(|pid| desktop::find_for_process(pid, exe.as_deref()));
+    Handler {
+        id: desktop_entry
+            .clone()
+            .or(exe)
+            .unwrap_or_else(|| owner.clone()),
+        bus_name: Some(owner),
+        desktop_entry,
+    }
+}
@@ -0,0 +1,73 @@
+//! The `org.freedesktop.FileManager1` calls, for [`showfile::raw`](crate::raw).
+
+use super::dbus::{Bus, FILE_MANAGER1, FILE_MANAGER1_PATH};
+use crate::{Action, Error, ShowOptions};
+
+/// A call to one of the methods of the
+/// [`org.freedesktop.FileManager1`](https://www.freedesktop.org/wiki/Specifications/file-manager-interface/)
+/// interface, which all take the URIs of the items and a startup ID, with the signature `(as, s)`.
+///
+/// The call can be sent on the bus set in the options with [`send`](Self::send), or with the
+/// application's own D-Bus connection from its fields and constants.
+///
+/// ```no_run
+/// use showfile::{raw::FileManager1Call, FileUri, ShowOptions};
+///
+/// let uri = FileUri::from_path("/home/alice/hello.txt")?.into_string();
+/// let call = FileManager1Call::show_items(vec![uri], "");
+/// call.send(&ShowOptions::new())?;
+/// # Ok::<(), showfile::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FileManager1Call {
+    /// The method to call, such as `ShowItems`.
+    pub method: &'static str,
+    /// The URIs of the items.
+    pub uris: Vec<String>,
+    /// The startup ID the file manager uses to take focus, or an empty string.
+    pub startup_id: String,
+}
+
+impl FileManager1Call {
+    /// The well-known bus name of the file manager.
+    pub const DESTINATION: &'static str = FILE_MANAGER1;
+    /// The object path the interface is on.
+    pub const PATH: &'static str = FILE_MANAGER1_PATH;
+    /// The name of the interface.
+    pub const INTERFACE: &'static str = FILE_MANAGER1;
+
+    /// Creates a `ShowItems` call, which selects the items in their folders.
+    pub fn show_items(uris: Vec<String>, startup_id: impl Into<String>) -> Self {
+        Self::new("ShowItems", uris, startup_id.into())
+    }
+
+    /// Creates a `ShowFolders` call, which opens the folders to show what's in them.
+    pub fn show_folders(uris: Vec<String>, startup_id: impl Into<String>) -> Self {
+        Self::new("ShowFolders", uris, startup_id.into())
+    }
+
+    /// Creates a `ShowItemProperties` call, which shows the properties dialog of the items.
+    pub fn show_item_properties(uris: Vec<String>, startup_id: impl Into<String>) -> Self {
+        Self::new("ShowItemProperties", uris, startup_id.into())
+    }
+
+    fn new(method: &'static str, uris: Vec<String>, startup_id: String) -> Self {
+        Self {
+            method,
+            uris,
+            startup_id,
+        }
+    }
+
+    /// Sends the call on the bus or peer set in `options`, or the session bus by default, with the
+    /// D-Bus crate this crate was built with, and waits for the reply.
+    ///
+    /// Returns the call as it is reported in [`ShowOutcome::actions`](crate::ShowOutcome::actions),
+    /// or [`Error::NoFileManager`] if no file manager provides the interface. Nothing else is
+    /// tried, such as falling back to `xdg-open`.
+    pub fn send(&self, options: &ShowOptions) -> Result<Action, Error> {
+        let uris = self.uris.iter().map(String::as_str).collect::<Vec<_>>();
+        Bus::for_options(options)?.call_file_manager(self.method, &uris, &self.startup_id)
+    }
+}
@@ -0,0 +1,138 @@
+//! Handling for running as a different user than the desktop session, such as under `sudo` or
+//! `pkexec`.
+//!
+//! The session bus only accepts connections from the user that owns it, even from root. When
+//! running as root on behalf of another user, the file manager is called through `dbus-send`
+//! running as that user instead.
+
+use std::{
+    ffi::OsStr,
+    fs,
+    os::unix::{fs::MetadataExt, process::CommandExt},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::dbus::{MethodError, FILE_MANAGER1, FILE_MANAGER1_PATH};
+use crate::{args, cache::Cached, process, Action, Error};
+
+/// The user that owns the desktop session.
+#[derive(Clone, Debug)]
+pub(crate) struct SessionUser {
+    uid: u32,
+    gid: u32,
+    home: PathBuf,
+    runtime_dir: PathBuf,
+}
+
+impl SessionUser {
+    /// Returns the session user if the process is running as a different user, detected the
+    /// first time it is needed after each [`refresh`](crate::refresh).
+    pub(crate) fn detect() -> Result<Option<Self>, Error> {
+        static SESSION_USER: Cached<Option<Option<SessionUser>>> = Cached::new();
+        SESSION_USER
+            .get(|| Self::probe().ok())
+            .ok_or(Error::SessionUserMismatch)
+    }
+
+    /// Finds the session user.
+    ///
+    /// The session user is taken from `SUDO_UID` or `PKEXEC_UID`, or from the owner of
+    /// `XDG_RUNTIME_DIR` when it has been passed through from another user.
+    fn probe() -> Result<Option<Self>, Error> {
+        // The owner of /proc/self is the effective user
+        let Ok(euid) = fs::metadata("/proc/self").map(|m| m.uid()) else {
+            return Ok(None);
+        };
+        let uid = ["SUDO_UID", "PKEXEC_UID"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok()?.parse::<u32>().ok())
+            .or_else(|| {
+                let dir = std::env::var_os("XDG_RUNTIME_DIR")?;
+                fs::metadata(dir).ok().map(|m| m.uid())
+            });
+        let Some(uid) = uid.filter(|uid| *uid != euid) else {
+            return Ok(None);
+        };
+        // Only root can switch to the session user
+        if euid != 0 {
+            return Err(Error::SessionUserMismatch);
+        }
+        let runtime_dir = PathBuf::from(format!("/run/user/{uid}"));
+        let passwd = fs::read_to_string("/etc/passwd").unwrap_or_default();
+        let entry = passwd.lines().find_map(|line| {
+            let fields = line.split(':').collect::<Vec<_>>();
+            match fields[..] {
+                [_, _, u, gid, _, home, ..] if u.parse() == Ok(uid) => {
+                    Some((gid.parse().ok()?, PathBuf::from(home)))
+                }
+                _ => None,
+            }
+        });
+        match entry {
+            Some((gid, home)) if runtime_dir.join("bus").exists() => Ok(Some(Self {
+                uid,
+                gid,
+                home,
+                runtime_dir,
+            })),
+            _ => Err(Error::SessionUserMismatch),
+        }
+    }
+
+    /// Returns the home directory of the session user.
+    pub(crate) fn home(&self) -> &Path {
+        &self.home
+    }
+
+    /// Creates a command that runs `program` as the session user, in their session.
+    pub(crate) fn command(&self, program: impl AsRef<OsStr>) -> Command {
+        let mut cmd = process::command(program);
+        cmd.uid(self.uid)
+            .gid(self.gid)
+            .env("HOME", &self.home)
+            .env("XDG_RUNTIME_DIR", &self.runtime_dir)
+            .env(
+                "DBUS_SESSION_BUS_ADDRESS",
+                format!("unix:path={}/bus", self.runtime_dir.display()),
+            );
+        cmd
+    }
+
+    /// Calls one of the `org.freedesktop.FileManager1` methods on the session user's bus.
+    pub(crate) fn call_file_manager(
+        &self,
+        method: &str,
+        uris: &[&str],
+        startup_id: &str,
+    ) -> Result<Action, Error> {
+        let uris = args::dbus_send_array(uris);
+        let mut cmd = self.command("dbus-send");
+        cmd.arg("--session")
+            .arg("--print-reply")
+            .arg(format!("--dest={FILE_MANAGER1}"))
+            .arg(FILE_MANAGER1_PATH)
+            .arg(format!("{FILE_MANAGER1}.{method}"))
+            .arg(format!("array:string:{uris}"))
+            .arg(format!("string:{startup_id}"));
+        let output = match cmd.output() {
+            Ok(output) => output,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::SessionUserMismatch)
+            }
+            Err(e) => return Err(Error::backend(e)),
+        };
+        if output.status.success() {
+            return Ok(Action::command(&cmd));
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match MethodError::from_dbus_send(&stderr) {
+            Some(err) if err.is_service_unknown() => Err(Error::NoFileManager),
+            Some(err) => Err(Error::backend(err)),
+            None => Err(Error::backend(format!(
+                "dbus-send failed: {}",
+                stderr.trim()
+            ))),
+        }
+    }
+}
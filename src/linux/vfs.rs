@@ -0,0 +1,229 @@
+//! Detection of the URI schemes that file managers can browse, and mounting of their locations.
+//!
+//! GTK file managers browse through GIO, whose schemes are provided by GVfs. Each GVfs backend
+//! installs a mount file, which is read directly when GIO isn't linked, and locations are mounted
+//! with the `gio` tool instead.
+
+#[cfg(not(feature = "gio"))]
+use std::fs;
+use std::process::Stdio;
+
+#[cfg(not(feature = "gio"))]
+use super::desktop;
+use super::user::SessionUser;
+use crate::{process, Action, Error, LocationStatus};
+
+/// Checks whether GIO can browse URIs with `scheme`.
+#[cfg(not(feature = "gio"))]
+pub(crate) fn supports_scheme(scheme: &str) -> bool {
+    desktop::data_dirs(None).into_iter().any(|dir| {
+        let Ok(entries) = fs::read_dir(dir.join("gvfs/mounts")) else {
+            return false;
+        };
+        entries.flatten().any(|entry| {
+            if entry.path().extension() != Some("mount".as_ref()) {
+                return false;
+            }
+            let Ok(mount) = fs::read_to_string(entry.path()) else {
+                return false;
+            };
+            // The scheme defaults to the backend type
+            desktop::group_value(&mount, "Mount", "Scheme")
+                .or_else(|| desktop::group_value(&mount, "Mount", "Type"))
+                .is_some_and(|s| s.eq_ignore_ascii_case(scheme))
+        })
+    })
+}
+
+/// Checks whether GIO can browse URIs with `scheme`.
+#[cfg(feature = "gio")]
+pub(crate) fn supports_scheme(scheme: &str) -> bool {
+    unsafe {
+        let vfs = gio_sys::g_vfs_get_default();
+        if vfs.is_null() {
+            return false;
+        }
+        let mut schemes = gio_sys::g_vfs_get_supported_uri_schemes(vfs);
+        while !schemes.is_null() && !(*schemes).is_null() {
+            let s = std::ffi::CStr::from_ptr(*schemes);
+            if s.to_bytes().eq_ignore_ascii_case(scheme.as_bytes()) {
+                return true;
+            }
+            schemes = schemes.add(1);
+        }
+        false
+    }
+}
+
+/// Checks whether `uri` is on a location GVfs has to mount before it can be browsed, such as an
+/// `smb://`, `mtp://` or `davs://` share.
+fn needs_mount(uri: &str) -> bool {
+    match uri.split_once(':') {
+        Some((scheme, _)) => !scheme.eq_ignore_ascii_case("file") && supports_scheme(scheme),
+        None => false,
+    }
+}
+
+/// Runs the `gio` tool with `args` and `uri`, as `user` if set, without a terminal to ask for
+/// passwords on.
+fn run_gio_tool(args: &[&str], uri: &str, user: Option<&SessionUser>) -> Result<Action, Error> {
+    let mut cmd = match user {
+        Some(user) => user.command("gio"),
+        None => process::command("gio"),
+    };
+    cmd.args(args)
+        .arg(crate::args::uri(uri)?)
+        .stdin(Stdio::null());
+    let output = match cmd.output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(Error::Unsupported),
+        Err(e) => return Err(Error::backend(e)),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::backend(format!(
+            "gio {} failed: {}",
+            args.join(" "),
+            stderr.trim()
+        )));
+    }
+    Ok(Action::command(&cmd))
+}
+
+/// Checks whether the volume `uri` is on is mounted with the `gio` tool, as `user` if set.
+fn is_mounted_with_gio_tool(uri: &str, user: Option<&SessionUser>) -> bool {
+    // Only locations that are mounted can be queried
+    run_gio_tool(&["info", "--attributes=standard::type"], uri, user).is_ok()
+}
+
+/// Mounts the volume `uri` is on with the `gio` tool, as `user` if set, unless it is mounted.
+fn mount_with_gio_tool(uri: &str, user: Option<&SessionUser>) -> Result<Option<Action>, Error> {
+    if is_mounted_with_gio_tool(uri, user) {
+        return Ok(None);
+    }
+    run_gio_tool(&["mount"], uri, user).map(Some)
+}
+
+/// Returns where `uri` is stored: locations that GVfs has to mount are checked for a mount, and
+/// other remote locations can be reached as is.
+pub(crate) fn location_status(uri: &str, user: Option<&SessionUser>) -> LocationStatus {
+    if !needs_mount(uri) || is_mounted(uri, user) {
+        LocationStatus::NetworkMounted
+    } else {
+        LocationStatus::NetworkUnmounted
+    }
+}
+
+/// Checks whether the volume `uri` is on is mounted, in the session of `user` if set.
+#[cfg(not(feature = "gio"))]
+fn is_mounted(uri: &str, user: Option<&SessionUser>) -> bool {
+    is_mounted_with_gio_tool(uri, user)
+}
+
+/// Checks whether the volume `uri` is on is mounted, in the session of `user` if set.
+#[cfg(feature = "gio")]
+fn is_mounted(uri: &str, user: Option<&SessionUser>) -> bool {
+    if user.is_some() {
+        return is_mounted_with_gio_tool(uri, user);
+    }
+    unsafe {
+        let c_uri = super::dbus::to_cstring(uri.as_bytes());
+        let file = gio_sys::g_file_new_for_uri(c_uri.as_ptr());
+        let mut err = std::ptr::null_mut();
+        let mount = gio_sys::g_file_find_enclosing_mount(file, std::ptr::null_mut(), &mut err);
+        gobject_sys::g_object_unref(file as *mut _);
+        if mount.is_null() {
+            glib_sys::g_error_free(err);
+            return false;
+        }
+        gobject_sys::g_object_unref(mount as *mut _);
+        true
+    }
+}
+
+/// Mounts the volume `uri` is on if GVfs has to mount it first, as `user` if set.
+///
+/// Returns the call made to mount it, if the volume was mounted.
+#[cfg(not(feature = "gio"))]
+pub(crate) fn mount_enclosing_volume(
+    uri: &str,
+    user: Option<&SessionUser>,
+) -> Result<Option<Action>, Error> {
+    if !needs_mount(uri) {
+        return Ok(None);
+    }
+    mount_with_gio_tool(uri, user)
+}
+
+/// Mounts the volume `uri` is on if GVfs has to mount it first, as `user` if set.
+///
+/// Returns the call made to mount it, if the volume was mounted. The volume is mounted without a
+/// `GMountOperation`, so only volumes that don't need a password, or whose password is saved in
+/// the keyring, can be mounted.
+#[cfg(feature = "gio")]
+pub(crate) fn mount_enclosing_volume(
+    uri: &str,
+    user: Option<&SessionUser>,
+) -> Result<Option<Action>, Error> {
+    use std::ptr::null_mut;
+
+    /// Records the result of `g_file_mount_enclosing_volume` in the `Option` at `data`.
+    unsafe extern "C" fn mounted(
+        source: *mut gobject_sys::GObject,
+        res: *mut gio_sys::GAsyncResult,
+        data: glib_sys::gpointer,
+    ) {
+        let done = &mut *(data as *mut Option<*mut glib_sys::GError>);
+        let mut err = null_mut();
+        gio_sys::g_file_mount_enclosing_volume_finish(source as *mut _, res, &mut err);
+        *done = Some(err);
+    }
+
+    if !needs_mount(uri) {
+        return Ok(None);
+    }
+    // The volume has to be mounted in the user's session, not in the one of this process
+    if user.is_some() {
+        return mount_with_gio_tool(uri, user);
+    }
+    if is_mounted(uri, None) {
+        return Ok(None);
+    }
+    unsafe {
+        let c_uri = super::dbus::to_cstring(uri.as_bytes());
+        let file = gio_sys::g_file_new_for_uri(c_uri.as_ptr());
+        // Wait for the mount on a main context of our own, so that the application's main loop
+        // isn't needed
+        let context = glib_sys::g_main_context_new();
+        glib_sys::g_main_context_push_thread_default(context);
+        let mut done: Option<*mut glib_sys::GError> = None;
+        gio_sys::g_file_mount_enclosing_volume(
+            file,
+            gio_sys::G_MOUNT_MOUNT_NONE,
+            null_mut(),
+            null_mut(),
+            Some(mounted),
+            &mut done as *mut _ as glib_sys::gpointer,
+        );
+        while done.is_none() {
+            glib_sys::g_main_context_iteration(context, glib_sys::GTRUE);
+        }
+        glib_sys::g_main_context_pop_thread_default(context);
+        glib_sys::g_main_context_unref(context);
+        gobject_sys::g_object_unref(file as *mut _);
+        let err = done.unwrap_or_default();
+        if err.is_null() {
+            return Ok(Some(Action::call("g_file_mount_enclosing_volume", [uri])));
+        }
+        let nothing_to_mount = (*err).domain == gio_sys::g_io_error_quark()
+            && matches!(
+                (*err).code,
+                gio_sys::G_IO_ERROR_ALREADY_MOUNTED | gio_sys::G_IO_ERROR_NOT_SUPPORTED
+            );
+        if nothing_to_mount {
+            glib_sys::g_error_free(err);
+            return Ok(None);
+        }
+        Err(super::dbus::gerror_to_error(err))
+    }
+}
@@ -0,0 +1,127 @@
+//! Checks on an item before showing it: where it is stored, and whether it can be reached.
+
+use std::{ffi::OsStr, fs, io, path::Path};
+
+use crate::{platform, uri, Automount, Error, FileUri};
+
+/// Where an item is stored, and whether it can be reached, as returned by
+/// [`location_status`](crate::location_status).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LocationStatus {
+    /// On a local disk that is connected.
+    Local,
+    /// On a network share that is mounted, or at a remote location the file manager can reach
+    /// as is.
+    NetworkMounted,
+    /// On a network share that has to be mounted or reconnected before it can be shown, see
+    /// [`ShowOptions::automount`](crate::ShowOptions::automount).
+    NetworkUnmounted,
+    /// On a removable drive or volume that isn't connected or mounted.
+    RemovableMissing,
+    /// A file synced by a cloud storage provider whose contents haven't been downloaded, which
+    /// the file manager may download when it is shown.
+    CloudPlaceholder,
+    /// Where the item is stored can't be told, such as for URIs on targets this crate can't show
+    /// items on.
+    Unknown,
+}
+
+/// Returns the scheme of `location` if it is a URI rather than a path.
+///
+/// Single letters aren't taken as schemes, so that Windows paths such as `C:\Users` are paths.
+fn uri_scheme(location: &str) -> Option<&str> {
+    let (scheme, _) = location.split_once(':')?;
+    (scheme.len() > 1 && uri::is_scheme(scheme)).then_some(scheme)
+}
+
+pub(crate) fn status(location: &OsStr) -> Result<LocationStatus, Error> {
+    let Some((location, scheme)) = location.to_str().and_then(|s| Some((s, uri_scheme(s)?))) else {
+        return Ok(platform::path_location_status(Path::new(location)));
+    };
+    if !scheme.eq_ignore_ascii_case("file") {
+        return Ok(platform::uri_location_status(location));
+    }
+    let uri = FileUri::parse(location)?;
+    if let Some(path) = uri.to_path() {
+        return Ok(platform::path_location_status(&path));
+    }
+    // A URI with a host is on another machine, unless the platform can't have paths for it
+    let host = location[5..]
+        .strip_prefix("//")
+        .and_then(|rest| rest.split('/').next())
+        .filter(|host| !host.is_empty() && !host.eq_ignore_ascii_case("localhost"));
+    match host {
+        Some(_) => Ok(platform::uri_location_status(location)),
+        None => Err(Error::InvalidPath),
+    }
+}
+
+/// Returns the archive `path` is inside of, if it goes through a file such as a ZIP archive
+/// rather than only through folders.
+pub(crate) fn archive_ancestor(path: &Path) -> Option<&Path> {
+    // Only look further when the path can't be found, which is always the case inside archives
+    if path.exists() {
+        return None;
+    }
+    path.ancestors().skip(1).find(|ancestor| ancestor.is_file())
+}
+
+/// Returns whether `path` is inside an archive that the file manager browses like a folder, so
+/// that it can be shown even though it has no file of its own. Only Explorer does this.
+pub(crate) fn is_archive_entry(path: &Path) -> bool {
+    cfg!(windows) && archive_ancestor(path).is_some()
+}
+
+pub(crate) fn check_access(path: &Path) -> Result<(), Error> {
+    // Getting the metadata needs permission to search every folder on the way, but doesn't open
+    // the item itself
+    match fs::symlink_metadata(path) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(match e.kind() {
+            io::ErrorKind::NotFound => Error::NotFound,
+            io::ErrorKind::PermissionDenied => Error::PermissionDenied,
+            io::ErrorKind::InvalidInput => Error::InvalidPath,
+            _ => Error::backend(e),
+        }),
+    }
+}
+
+/// Checks that the file manager can show `path` without an error of its own, for
+/// [`ShowOptions::quiet`](crate::ShowOptions::quiet).
+pub(crate) fn check_path_quietly(path: &Path, automount: Automount) -> Result<(), Error> {
+    match check_access(path) {
+        // The volume is mounted before showing the item
+        Err(Error::NotFound)
+            if automount != Automount::Never
+                && matches!(
+                    platform::path_location_status(path),
+                    LocationStatus::NetworkUnmounted | LocationStatus::RemovableMissing
+                ) =>
+        {
+            Ok(())
+        }
+        Err(Error::NotFound) if is_archive_entry(path) => Ok(()),
+        res => res,
+    }
+}
+
+/// Checks that the file manager can show `uri` without an error of its own, for
+/// [`ShowOptions::quiet`](crate::ShowOptions::quiet).
+pub(crate) fn check_uri_quietly(uri: &str, automount: Automount) -> Result<(), Error> {
+    let scheme = uri_scheme(uri).ok_or(Error::InvalidPath)?;
+    if scheme.eq_ignore_ascii_case("file") {
+        let path = FileUri::parse(uri)?.to_path().ok_or(Error::InvalidPath)?;
+        return check_path_quietly(&path, automount);
+    }
+    if !crate::supports_uri_scheme(scheme) {
+        return Err(Error::Unsupported);
+    }
+    // The file manager would ask for a password, or fail to mount it
+    if automount == Automount::Never
+        && platform::uri_location_status(uri) == LocationStatus::NetworkUnmounted
+    {
+        return Err(Error::NotFound);
+    }
+    Ok(())
+}
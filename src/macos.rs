@@ -0,0 +1,1207 @@
+use objc::{
+    class, msg_send,
+    rc::autoreleasepool,
+    runtime::{Class, BOOL, NO},
+    sel, sel_impl,
+};
+use std::{
+    borrow::Cow,
+    ffi::{c_char, c_void, CStr, CString, OsStr, OsString},
+    fmt, fs, io,
+    os::unix::ffi::OsStrExt,
+    panic::AssertUnwindSafe,
+    path::{Component, Path, PathBuf},
+    sync::{mpsc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    args::{self, applescript_string},
+    process,
+    selection::Item,
+    Action, Automount, BackendInfo, Capabilities, Diagnostics, Error, ErrorKind, Handler,
+    LocationStatus, Portal, SandboxKind, Selection, ShowOptions, ShowOutcome, Warning,
+};
+
+#[cfg(target_os = "macos")]
+#[link(name = "AppKit", kind = "framework")]
+extern "C" {}
+
+#[allow(non_camel_case_types)]
+type id = *mut objc::runtime::Object;
+#[allow(non_upper_case_globals)]
+const nil: id = std::ptr::null_mut();
+
+/// Looks up the `NSWorkspace` class.
+///
+/// Mac Catalyst apps don't link against AppKit, but it can be loaded from its bundle.
+unsafe fn workspace_class() -> Option<&'static Class> {
+    if let Some(class) = Class::get("NSWorkspace") {
+        return Some(class);
+    }
+    #[cfg(target_os = "ios")]
+    {
+        let path = nsstring(b"/System/Library/Frameworks/AppKit.framework");
+        let bundle: id = msg_send![class!(NSBundle), bundleWithPath:path];
+        if bundle != nil {
+            let _: BOOL = msg_send![bundle, load];
+        }
+    }
+    Class::get("NSWorkspace")
+}
+
+/// Shows `nsurl` in Finder, returning the call that was made, or `None` if AppKit is
+/// unavailable.
+unsafe fn show_nsurl_in_file_manager(nsurl: id, activate: bool) -> Result<Option<Action>, Error> {
+    let Some(workspace) = workspace_class() else {
+        return Ok(None);
+    };
+    let ws: id = msg_send![workspace, sharedWorkspace];
+    let path: id = if activate {
+        nil
+    } else {
+        let is_file: BOOL = msg_send![nsurl, isFileURL];
+        if is_file != NO {
+            msg_send![nsurl, path]
+        } else {
+            nil
+        }
+    };
+    if path != nil {
+        // selectFile:inFileViewerRootedAtPath: still brings Finder forward, so give focus back to
+        // whichever app was in front before
+        const NS_APPLICATION_ACTIVATE_IGNORING_OTHER_APPS: usize = 1 << 1;
+        const SELECT_FILE: &str = "NSWorkspace selectFile:inFileViewerRootedAtPath:";
+
+        let front: id = msg_send![ws, frontmostApplication];
+        let root = nsstring(b"");
+        let selected: BOOL = msg_send![ws, selectFile:path inFileViewerRootedAtPath:root];
+        if front != nil {
+            let _: BOOL =
+                msg_send![front, activateWithOptions:NS_APPLICATION_ACTIVATE_IGNORING_OTHER_APPS];
+        }
+        if selected == NO {
+            return Err(AppKitError::new(SELECT_FILE).into());
+        }
+        let path = nsstring_to_string(path).unwrap_or_default();
+        Ok(Some(Action::call(SELECT_FILE, [path])))
+    } else {
+        Ok(Some(select_nsurls(ws, &[nsurl])))
+    }
+}
+
+/// Activates Finder and selects `nsurls`, returning the call that was made.
+unsafe fn select_nsurls(ws: id, nsurls: &[id]) -> Action {
+    let urls: id = msg_send![
+        class!(NSArray),
+        arrayWithObjects:nsurls.as_ptr()
+        count:nsurls.len()
+    ];
+    let _: () = msg_send![ws, activateFileViewerSelectingURLs:urls];
+    Action::call(
+        "NSWorkspace activateFileViewerSelectingURLs:",
+        nsurls.iter().map(|&nsurl| nsurl_string(nsurl)),
+    )
+}
+
+/// Shows `nsurl` in the file manager.
+///
+/// Finder is used unless another file viewer is configured, falling back to `open -R` for file
+/// URLs if AppKit is unavailable. If neither works, such as in agent processes where AppKit calls
+/// throw or fail, Finder is asked to reveal the file through `osascript` as a last resort.
+unsafe fn show_nsurl_or_open(nsurl: id, options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    if let (Some(bundle_id), Some(path)) = (alternate_file_viewer(options), file_url_path(nsurl)) {
+        if let Some(outcome) = reveal_with_app(&bundle_id, &path, options.activate) {
+            return Ok(ShowOutcome {
+                handler: options.identify_handler.then(|| Handler::new(bundle_id)),
+                ..outcome
+            });
+        }
+    }
+    let res = catch_exception(|| show_nsurl_in_file_manager(nsurl, options.activate));
+    let action = match (res, file_url_path(nsurl)) {
+        (Ok(Some(action)), _) => action,
+        (Ok(None), Some(path)) => reveal_with_open(&path, options.activate)
+            .or_else(|e| reveal_with_osascript(&path, options.activate).map_err(|_| e))?,
+        (Err(e), Some(path)) => reveal_with_osascript(&path, options.activate).map_err(|_| e)?,
+        (Ok(None), None) => {
+            return Err(AppKitError {
+                call: "NSWorkspace",
+                detail: Some("AppKit is unavailable".to_owned()),
+            }
+            .into())
+        }
+        (Err(e), None) => return Err(e),
+    };
+    Ok(finder_outcome(&[nsurl], options, vec![action]))
+}
+
+/// Returns the outcome of showing `nsurls` in Finder with `actions`, with the details requested
+/// in `options`.
+unsafe fn finder_outcome(
+    nsurls: &[id],
+    options: &ShowOptions,
+    actions: Vec<Action>,
+) -> ShowOutcome {
+    let folders = nsurls
+        .iter()
+        .filter_map(|&nsurl| Some(file_url_path(nsurl)?.parent()?.to_owned()))
+        .collect::<Vec<_>>();
+    ShowOutcome {
+        window_appeared: (options.verify && !folders.is_empty())
+            .then(|| wait_for_finder_window(&folders))
+            .flatten(),
+        handler: options
+            .identify_handler
+            .then(|| Handler::new("com.apple.finder")),
+        items: Vec::new(),
+        actions,
+        warnings: Vec::new(),
+    }
+}
+
+/// Polls Finder until its front window shows one of `folders`.
+///
+/// Returns `None` if Finder can't be asked, usually because the app hasn't been granted the
+/// automation permission for it.
+fn wait_for_finder_window(folders: &[PathBuf]) -> Option<bool> {
+    const TIMEOUT: Duration = Duration::from_secs(2);
+    const SCRIPT: &str = "tell application id \"com.apple.finder\"\n\
+        get POSIX path of (target of front Finder window as alias)\n\
+        end tell";
+    // errAEEventNotPermitted
+    const NOT_PERMITTED: i64 = -1743;
+
+    let start = Instant::now();
+    loop {
+        match unsafe { run_applescript(SCRIPT) } {
+            Ok(Some(folder)) => {
+                let folder = Path::new(folder.trim_end_matches('/'));
+                if folders.iter().any(|f| f == folder) {
+                    return Some(true);
+                }
+            }
+            Err(ScriptError {
+                number: Some(NOT_PERMITTED),
+                ..
+            }) => return None,
+            Ok(None) | Err(_) => {}
+        }
+        if start.elapsed() >= TIMEOUT {
+            return Some(false);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// An AppleScript that failed to run.
+#[derive(Debug, Default)]
+struct ScriptError {
+    number: Option<i64>,
+    message: Option<String>,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message.as_deref().unwrap_or("AppleScript failed"))?;
+        if let Some(number) = self.number {
+            write!(f, " ({number})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// Runs an AppleScript, returning its result as a string.
+unsafe fn run_applescript(source: &str) -> Result<Option<String>, ScriptError> {
+    let Some(class) = Class::get("NSAppleScript") else {
+        return Err(ScriptError::default());
+    };
+    let script: id = msg_send![class, alloc];
+    let script: id = msg_send![script, initWithSource:nsstring(source.as_bytes())];
+    let script: id = msg_send![script, autorelease];
+    let mut err: id = nil;
+    let res: id = msg_send![script, executeAndReturnError:&mut err as *mut id];
+    if res != nil {
+        let value: id = msg_send![res, stringValue];
+        return Ok(nsstring_to_string(value));
+    }
+    if err == nil {
+        return Err(ScriptError::default());
+    }
+    let number: id = msg_send![err, objectForKey:nsstring(b"NSAppleScriptErrorNumber")];
+    let message: id = msg_send![err, objectForKey:nsstring(b"NSAppleScriptErrorMessage")];
+    Err(ScriptError {
+        number: (number != nil).then(|| msg_send![number, longLongValue]),
+        message: nsstring_to_string(message),
+    })
+}
+
+fn reveal_with_open(path: &Path, activate: bool) -> Result<Action, Error> {
+    let mut cmd = process::command("/usr/bin/open");
+    if !activate {
+        cmd.arg("-g");
+    }
+    let output = cmd
+        .arg("-R")
+        .arg(args::path(path))
+        .output()
+        .map_err(Error::backend)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppKitError {
+            call: "open -R",
+            detail: Some(format!("{}: {}", output.status, stderr.trim())),
+        }
+        .into());
+    }
+    Ok(Action::command(&cmd))
+}
+
+/// Has Finder reveal `path` by running an AppleScript with `osascript`.
+///
+/// This runs out of process, so it works when AppKit can't be used from this one. It needs the
+/// automation permission for Finder, which the user is asked for on first use.
+fn reveal_with_osascript(path: &Path, activate: bool) -> Result<Action, Error> {
+    let path = path.to_str().ok_or(Error::InvalidPath)?;
+    let mut cmd = process::command("/usr/bin/osascript");
+    cmd.args(["-e", "tell application id \"com.apple.finder\""])
+        .arg("-e")
+        .arg(format!("reveal POSIX file {}", applescript_string(path)));
+    if activate {
+        cmd.args(["-e", "activate"]);
+    }
+    cmd.args(["-e", "end tell"]);
+    let output = cmd.output().map_err(Error::backend)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppKitError {
+            call: "osascript",
+            detail: Some(format!("{}: {}", output.status, stderr.trim())),
+        }
+        .into());
+    }
+    Ok(Action::command(&cmd))
+}
+
+unsafe fn nsstring(bytes: &[u8]) -> id {
+    let s: id = msg_send![class!(NSString), alloc];
+    let s: id = msg_send![
+        s,
+        initWithBytes:bytes.as_ptr()
+        length:bytes.len()
+        encoding:4 as id
+    ];
+    msg_send![s, autorelease]
+}
+
+unsafe fn nsstring_to_string(s: id) -> Option<String> {
+    if s == nil {
+        return None;
+    }
+    let s: *const std::ffi::c_char = msg_send![s, UTF8String];
+    if s.is_null() {
+        return None;
+    }
+    Some(CStr::from_ptr(s).to_string_lossy().into_owned())
+}
+
+/// Returns `nsurl` as a string, for [`ShowOutcome::actions`].
+unsafe fn nsurl_string(nsurl: id) -> String {
+    let s: id = msg_send![nsurl, absoluteString];
+    nsstring_to_string(s).unwrap_or_default()
+}
+
+/// An AppKit call or command that reported failure, with whatever details it gave.
+#[derive(Debug)]
+struct AppKitError {
+    call: &'static str,
+    detail: Option<String>,
+}
+
+impl AppKitError {
+    fn new(call: &'static str) -> Self {
+        Self { call, detail: None }
+    }
+}
+
+impl fmt::Display for AppKitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed", self.call)?;
+        if let Some(detail) = &self.detail {
+            write!(f, ": {detail}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AppKitError {}
+
+impl From<AppKitError> for Error {
+    fn from(err: AppKitError) -> Self {
+        Error::backend(err)
+    }
+}
+
+/// The details of an `NSError`.
+#[derive(Debug)]
+struct NSErrorInfo {
+    domain: String,
+    code: isize,
+    description: String,
+}
+
+impl fmt::Display for NSErrorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({} {})", self.description, self.domain, self.code)
+    }
+}
+
+impl std::error::Error for NSErrorInfo {}
+
+unsafe fn nserror_to_error(err: id) -> Error {
+    if err == nil {
+        return Error::InvalidPath;
+    }
+    let domain: id = msg_send![err, domain];
+    let code: isize = msg_send![err, code];
+    let desc: id = msg_send![err, localizedDescription];
+    Error::backend(NSErrorInfo {
+        domain: nsstring_to_string(domain).unwrap_or_default(),
+        code,
+        description: nsstring_to_string(desc).unwrap_or_default(),
+    })
+}
+
+/// Runs `f`, catching any Objective-C exception thrown by AppKit and returning it as an error.
+///
+/// Exceptions unwinding into Rust frames are undefined behavior, and AppKit can throw them on bad
+/// arguments or from misbehaving Finder extensions.
+unsafe fn catch_exception<R>(f: impl FnOnce() -> Result<R, Error>) -> Result<R, Error> {
+    match objc_exception::r#try(f) {
+        Ok(res) => res,
+        Err(exception) => {
+            let exception = exception as id;
+            let detail = if exception == nil {
+                "uncaught exception".to_owned()
+            } else {
+                let name: id = msg_send![exception, name];
+                let reason: id = msg_send![exception, reason];
+                let detail = format!(
+                    "uncaught exception {}: {}",
+                    nsstring_to_string(name).unwrap_or_default(),
+                    nsstring_to_string(reason).unwrap_or_default()
+                );
+                // The exception is retained when it is caught
+                let _: () = msg_send![exception, release];
+                detail
+            };
+            Err(AppKitError {
+                call: "AppKit",
+                detail: Some(detail),
+            }
+            .into())
+        }
+    }
+}
+
+/// A libdispatch queue.
+#[repr(C)]
+struct DispatchQueue {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    fn dispatch_queue_create(label: *const c_char, attr: *const c_void) -> *mut DispatchQueue;
+    fn dispatch_get_global_queue(identifier: isize, flags: usize) -> *mut DispatchQueue;
+    fn dispatch_sync_f(
+        queue: *mut DispatchQueue,
+        context: *mut c_void,
+        work: extern "C" fn(*mut c_void),
+    );
+}
+
+/// The serial queue that all AppKit calls are made on.
+struct AppKitQueue(*mut DispatchQueue);
+
+// libdispatch queues can be used from any thread
+unsafe impl Send for AppKitQueue {}
+unsafe impl Sync for AppKitQueue {}
+
+/// Runs `f` on the AppKit queue and waits for it to return, catching any exception it throws.
+///
+/// All NSWorkspace calls are funneled through one serial queue, so that they never run
+/// concurrently and callers don't have to care which thread they're on. NSWorkspace is
+/// thread-safe, so the main queue isn't needed, which would deadlock when called from the main
+/// thread or in apps that don't run a main loop. Each call gets its own autorelease pool, and
+/// panics in `f` are resumed on the calling thread.
+fn appkit<R: Send>(f: impl FnOnce() -> Result<R, Error> + Send) -> Result<R, Error> {
+    extern "C" fn run<F: FnOnce()>(context: *mut c_void) {
+        let f = unsafe { &mut *(context as *mut Option<F>) };
+        if let Some(f) = f.take() {
+            f();
+        }
+    }
+
+    fn run_job<F: FnOnce()>(_job: &Option<F>) -> extern "C" fn(*mut c_void) {
+        run::<F>
+    }
+
+    static QUEUE: OnceLock<AppKitQueue> = OnceLock::new();
+    let queue = QUEUE.get_or_init(|| {
+        let label = b"io.github.jf2048.showfile.appkit\0";
+        AppKitQueue(unsafe { dispatch_queue_create(label.as_ptr().cast(), std::ptr::null()) })
+    });
+    let mut res = None;
+    let mut job = Some(|| {
+        res = Some(std::panic::catch_unwind(AssertUnwindSafe(|| {
+            autoreleasepool(|| unsafe { catch_exception(f) })
+        })));
+    });
+    let run = run_job(&job);
+    unsafe { dispatch_sync_f(queue.0, &mut job as *mut _ as *mut c_void, run) };
+    match res {
+        Some(Ok(res)) => res,
+        Some(Err(panic)) => std::panic::resume_unwind(panic),
+        None => unreachable!("dispatch_sync_f returned without running the job"),
+    }
+}
+
+unsafe fn file_url_path(nsurl: id) -> Option<PathBuf> {
+    let is_file: BOOL = msg_send![nsurl, isFileURL];
+    if is_file == NO {
+        return None;
+    }
+    let path: *const std::ffi::c_char = msg_send![nsurl, fileSystemRepresentation];
+    if path.is_null() {
+        return None;
+    }
+    Some(PathBuf::from(OsStr::from_bytes(
+        CStr::from_ptr(path).to_bytes(),
+    )))
+}
+
+/// Returns the bundle identifier of the file manager to use instead of Finder, if any.
+///
+/// Finder alternatives such as Path Finder set the `NSFileViewer` default when they are made the
+/// default file viewer.
+unsafe fn alternate_file_viewer(options: &ShowOptions) -> Option<String> {
+    let bundle_id = match &options.file_manager {
+        Some(bundle_id) => bundle_id.clone(),
+        None => {
+            let defaults: id = msg_send![class!(NSUserDefaults), standardUserDefaults];
+            let value: id = msg_send![defaults, stringForKey:nsstring(b"NSFileViewer")];
+            nsstring_to_string(value)?
+        }
+    };
+    (!bundle_id.eq_ignore_ascii_case("com.apple.finder")).then_some(bundle_id)
+}
+
+/// Reveals `path` using the scripting interface of another file manager.
+///
+/// Falls back to opening the parent folder in that app if it doesn't support `reveal`. Returns
+/// `None` if neither worked.
+fn reveal_with_app(bundle_id: &str, path: &Path, activate: bool) -> Option<ShowOutcome> {
+    if let Some(path) = path.to_str() {
+        let mut source = format!(
+            "tell application id {}\nreveal POSIX file {}\n",
+            applescript_string(bundle_id),
+            applescript_string(path)
+        );
+        if activate {
+            source.push_str("activate\n");
+        }
+        source.push_str("end tell");
+        if unsafe { run_applescript(&source) }.is_ok() {
+            return Some(ShowOutcome {
+                actions: vec![Action::call(
+                    "NSAppleScript executeAndReturnError:",
+                    [source],
+                )],
+                ..Default::default()
+            });
+        }
+    }
+    let mut cmd = process::command("/usr/bin/open");
+    if !activate {
+        cmd.arg("-g");
+    }
+    cmd.arg("-b")
+        .arg(bundle_id)
+        .arg(args::path(path.parent().unwrap_or(path)));
+    cmd.status()
+        .is_ok_and(|status| status.success())
+        .then(|| ShowOutcome {
+            actions: vec![Action::command(&cmd)],
+            warnings: vec![Warning::NotSelected],
+            ..Default::default()
+        })
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFRelease(cf: *const c_void);
+}
+
+#[link(name = "DiskArbitration", kind = "framework")]
+extern "C" {
+    static kDADiskDescriptionVolumeNameKey: id;
+    static kDADiskDescriptionVolumePathKey: id;
+    static kDADiskDescriptionVolumeMountableKey: id;
+    fn DASessionCreate(allocator: *const c_void) -> id;
+    fn DASessionSetDispatchQueue(session: id, queue: *mut DispatchQueue);
+    fn DADiskCreateFromBSDName(allocator: *const c_void, session: id, name: *const c_char) -> id;
+    fn DADiskCopyDescription(disk: id) -> id;
+    fn DADiskMount(
+        disk: id,
+        path: *const c_void,
+        options: u32,
+        callback: extern "C" fn(disk: id, dissenter: id, context: *mut c_void),
+        context: *mut c_void,
+    );
+    fn DADissenterGetStatus(dissenter: id) -> i32;
+    fn DADissenterGetStatusString(dissenter: id) -> id;
+}
+
+/// An owned Core Foundation object, released when dropped.
+struct CfObject(id);
+
+impl CfObject {
+    fn new(object: id) -> Option<Self> {
+        (object != nil).then_some(Self(object))
+    }
+}
+
+impl Drop for CfObject {
+    fn drop(&mut self) {
+        unsafe { CFRelease(self.0 as *const c_void) };
+    }
+}
+
+/// How long to wait for a volume to be mounted, unless [`ShowOptions::timeout`] is set.
+const MOUNT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returns the name of the volume `path` is on, if it is under `/Volumes` and the volume isn't
+/// mounted there.
+fn unmounted_volume(path: &Path) -> Option<&str> {
+    let Some(Component::Normal(name)) = path.strip_prefix("/Volumes").ok()?.components().next()
+    else {
+        return None;
+    };
+    let name = name.to_str()?;
+    (!Path::new("/Volumes").join(name).exists()).then_some(name)
+}
+
+/// Finds the disk with the unmounted volume `name` among the disks in `/dev`, returning it with
+/// its BSD name.
+unsafe fn find_unmounted_disk(session: id, name: &str) -> Option<(CfObject, String)> {
+    fs::read_dir("/dev").ok()?.find_map(|entry| {
+        let bsd_name = entry.ok()?.file_name().into_string().ok()?;
+        if !bsd_name.starts_with("disk") {
+            return None;
+        }
+        let c_name = CString::new(bsd_name.as_str()).ok()?;
+        let disk = CfObject::new(DADiskCreateFromBSDName(
+            std::ptr::null(),
+            session,
+            c_name.as_ptr(),
+        ))?;
+        let description = CfObject::new(DADiskCopyDescription(disk.0))?;
+        let volume_name: id =
+            msg_send![description.0, objectForKey:kDADiskDescriptionVolumeNameKey];
+        let mount_point: id =
+            msg_send![description.0, objectForKey:kDADiskDescriptionVolumePathKey];
+        let mountable: id =
+            msg_send![description.0, objectForKey:kDADiskDescriptionVolumeMountableKey];
+        let mountable: BOOL = if mountable == nil {
+            NO
+        } else {
+            msg_send![mountable, boolValue]
+        };
+        (mount_point == nil
+            && mountable != NO
+            && nsstring_to_string(volume_name).as_deref() == Some(name))
+        .then_some((disk, bsd_name))
+    })
+}
+
+/// Called by Disk Arbitration once a disk is mounted, with the sender to report the result to.
+extern "C" fn disk_mounted(_disk: id, dissenter: id, context: *mut c_void) {
+    let sender = unsafe { Box::from_raw(context as *mut mpsc::Sender<Result<(), Error>>) };
+    let res = if dissenter == nil {
+        Ok(())
+    } else {
+        autoreleasepool(|| unsafe {
+            let status = DADissenterGetStatus(dissenter);
+            let message = nsstring_to_string(DADissenterGetStatusString(dissenter));
+            Err(AppKitError {
+                call: "DADiskMount",
+                detail: Some(message.unwrap_or_else(|| format!("status {status:#x}"))),
+            }
+            .into())
+        })
+    };
+    let _ = sender.send(res);
+}
+
+/// Asks the user whether to mount the volume `name`, with a dialog shown by `osascript`.
+fn confirm_mount(name: &str) -> bool {
+    let message =
+        format!("The volume \u{201c}{name}\u{201d} isn't mounted. Mount it to show the file?");
+    process::command("/usr/bin/osascript")
+        .arg("-e")
+        .arg(format!(
+            "display dialog {} buttons {{\"Cancel\", \"Mount\"}} default button \"Mount\" \
+             with icon caution",
+            applescript_string(&message)
+        ))
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Mounts the volume `path` is on if it isn't mounted, as set with [`ShowOptions::automount`].
+///
+/// Returns the call made to mount it, if the volume was mounted.
+fn mount_volume(path: &Path, options: &ShowOptions) -> Result<Option<Action>, Error> {
+    if options.automount == Automount::Never {
+        return Ok(None);
+    }
+    let Some(name) = unmounted_volume(path) else {
+        return Ok(None);
+    };
+    autoreleasepool(|| unsafe {
+        let session = CfObject::new(DASessionCreate(std::ptr::null()))
+            .ok_or_else(|| AppKitError::new("DASessionCreate"))?;
+        let Some((disk, bsd_name)) = find_unmounted_disk(session.0, name) else {
+            return Ok(None);
+        };
+        if options.automount == Automount::Ask && !confirm_mount(name) {
+            return Ok(None);
+        }
+        let (sender, receiver) = mpsc::channel::<Result<(), Error>>();
+        DASessionSetDispatchQueue(session.0, dispatch_get_global_queue(0, 0));
+        DADiskMount(
+            disk.0,
+            std::ptr::null(),
+            0,
+            disk_mounted,
+            Box::into_raw(Box::new(sender)).cast(),
+        );
+        let res = receiver.recv_timeout(options.timeout.unwrap_or(MOUNT_TIMEOUT));
+        // No more callbacks once the session is unscheduled, so it can be released
+        DASessionSetDispatchQueue(session.0, std::ptr::null_mut());
+        res.map_err(|_| Error::TimedOut)??;
+        Ok(Some(Action::call("DADiskMount", [bsd_name])))
+    })
+}
+
+#[link(name = "Foundation", kind = "framework")]
+extern "C" {
+    static NSURLIsUbiquitousItemKey: id;
+    static NSURLUbiquitousItemDownloadingStatusKey: id;
+    static NSURLUbiquitousItemDownloadingStatusNotDownloaded: id;
+    static NSURLVolumeIsLocalKey: id;
+    static NSURLVolumeURLForRemountingKey: id;
+}
+
+/// Returns the value of the resource `key` of `nsurl`, or `nil` if it has none.
+unsafe fn resource_value(nsurl: id, key: id) -> id {
+    let mut value: id = nil;
+    let found: BOOL = msg_send![
+        nsurl,
+        getResourceValue:&mut value as *mut id
+        forKey:key
+        error:nil
+    ];
+    if found == NO {
+        nil
+    } else {
+        value
+    }
+}
+
+/// Returns the boolean resource `key` of `nsurl`, if it has it.
+unsafe fn resource_flag(nsurl: id, key: id) -> Option<bool> {
+    let value = resource_value(nsurl, key);
+    if value == nil {
+        return None;
+    }
+    let flag: BOOL = msg_send![value, boolValue];
+    Some(flag != NO)
+}
+
+/// Checks whether `path` is a file that older versions of iCloud Drive replaced with a hidden
+/// `.name.icloud` file because it isn't downloaded.
+fn is_icloud_stub(path: &Path) -> bool {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return false;
+    };
+    let mut stub = OsString::from(".");
+    stub.push(name);
+    stub.push(".icloud");
+    parent.join(stub).exists()
+}
+
+pub(crate) fn path_location_status(path: &Path) -> LocationStatus {
+    if unmounted_volume(path).is_some() {
+        return LocationStatus::RemovableMissing;
+    }
+    if !path.exists() && is_icloud_stub(path) {
+        return LocationStatus::CloudPlaceholder;
+    }
+    // The volume can only be looked up through an item that exists
+    let Some(existing) = path.ancestors().find(|ancestor| ancestor.exists()) else {
+        return LocationStatus::Local;
+    };
+    autoreleasepool(|| unsafe {
+        let Ok(nsurl) = path_to_nsurl(existing) else {
+            return LocationStatus::Local;
+        };
+        if existing == path && resource_flag(nsurl, NSURLIsUbiquitousItemKey) == Some(true) {
+            let status = resource_value(nsurl, NSURLUbiquitousItemDownloadingStatusKey);
+            let not_downloaded: BOOL =
+                msg_send![status, isEqual:NSURLUbiquitousItemDownloadingStatusNotDownloaded];
+            if status != nil && not_downloaded != NO {
+                return LocationStatus::CloudPlaceholder;
+            }
+        }
+        match resource_flag(nsurl, NSURLVolumeIsLocalKey) {
+            Some(false) => LocationStatus::NetworkMounted,
+            _ => LocationStatus::Local,
+        }
+    })
+}
+
+/// Returns `uri` without the user name and password, and with the scheme and host in lowercase,
+/// so that URIs of the same share can be compared.
+fn normalize_remote_uri(uri: &str) -> String {
+    let Some((scheme, rest)) = uri.split_once("://") else {
+        return uri.to_owned();
+    };
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    format!(
+        "{}://{}{}",
+        scheme.to_ascii_lowercase(),
+        host.to_ascii_lowercase(),
+        path.trim_end_matches('/')
+    )
+}
+
+/// Returns where `uri` is stored, by looking for a mounted volume that `uri` is on among the ones
+/// mounted from the network.
+pub(crate) fn uri_location_status(uri: &str) -> LocationStatus {
+    let uri = normalize_remote_uri(uri);
+    autoreleasepool(|| unsafe {
+        let manager: id = msg_send![class!(NSFileManager), defaultManager];
+        let keys: id = msg_send![class!(NSArray), arrayWithObject:NSURLVolumeURLForRemountingKey];
+        let volumes: id = msg_send![
+            manager,
+            mountedVolumeURLsIncludingResourceValuesForKeys:keys
+            options:0usize
+        ];
+        let count: usize = if volumes == nil {
+            0
+        } else {
+            msg_send![volumes, count]
+        };
+        for i in 0..count {
+            let volume: id = msg_send![volumes, objectAtIndex:i];
+            let remount = resource_value(volume, NSURLVolumeURLForRemountingKey);
+            if remount == nil {
+                continue;
+            }
+            let volume = normalize_remote_uri(&nsurl_string(remount));
+            if uri == volume || uri.starts_with(&format!("{volume}/")) {
+                return LocationStatus::NetworkMounted;
+            }
+        }
+        LocationStatus::NetworkUnmounted
+    })
+}
+
+/// Returns the local path of an item, if it has one, decoding URIs with the codec of `options`.
+fn item_path<'a>(item: &'a Item, options: &ShowOptions) -> Option<Cow<'a, Path>> {
+    match item {
+        Item::Path(path) => Some(Cow::Borrowed(path)),
+        Item::Uri(uri) => options.codec().decode(uri).map(Cow::Owned),
+    }
+}
+
+/// Adds the calls made to mount volumes before showing the items to `outcome`.
+fn with_mounts(mounts: Vec<Action>, mut outcome: ShowOutcome) -> ShowOutcome {
+    outcome.actions.splice(0..0, mounts);
+    outcome
+}
+
+/// Checks whether the process is running inside the App Sandbox.
+fn is_sandboxed() -> bool {
+    std::env::var_os("APP_SANDBOX_CONTAINER_ID").is_some()
+}
+
+unsafe fn path_to_nsurl(path: &Path) -> Result<id, Error> {
+    // Finder silently ignores requests for paths the sandbox doesn't grant access to
+    if is_sandboxed() {
+        if let Err(e) = path.symlink_metadata() {
+            if e.kind() == io::ErrorKind::PermissionDenied {
+                return Err(Error::SandboxAccessDenied);
+            }
+        }
+    }
+    let s = nsstring(path.as_os_str().as_encoded_bytes());
+    if s == nil {
+        return Err(Error::InvalidPath);
+    }
+    let url: id = msg_send![class!(NSURL), fileURLWithPath:s];
+    if url == nil {
+        return Err(Error::InvalidPath);
+    }
+    Ok(url)
+}
+
+pub(crate) fn show_path(path: &Path, options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    let mount = mount_volume(path, options)?;
+    let outcome = appkit(|| unsafe { show_nsurl_or_open(path_to_nsurl(path)?, options) })?;
+    Ok(with_mounts(mount.into_iter().collect(), outcome))
+}
+
+unsafe fn uri_to_nsurl(uri: &str, options: &ShowOptions) -> Result<id, Error> {
+    // A custom codec can turn URIs that NSURL doesn't know into local paths
+    if options.uri_codec.is_some() {
+        if let Some(path) = options.codec().decode(uri) {
+            return path_to_nsurl(&path);
+        }
+    }
+    let s = nsstring(uri.as_bytes());
+    if s == nil {
+        return Err(Error::InvalidPath);
+    }
+    let url: id = msg_send![class!(NSURL), URLWithString:s];
+    if url == nil {
+        return Err(Error::InvalidPath);
+    }
+    Ok(url)
+}
+
+pub(crate) fn show_uri(uri: &str, options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    let mount = match item_path(&Item::Uri(uri.to_owned()), options) {
+        Some(path) => mount_volume(&path, options)?,
+        None => None,
+    };
+    let outcome = appkit(|| unsafe { show_nsurl_or_open(uri_to_nsurl(uri, options)?, options) })?;
+    Ok(with_mounts(mount.into_iter().collect(), outcome))
+}
+
+/// The state of a [`RevealSession`](crate::RevealSession). Finder reuses its windows on its own,
+/// so nothing needs to be kept.
+#[derive(Debug, Default)]
+pub(crate) struct RevealState;
+
+pub(crate) fn reveal_path(
+    _state: &mut RevealState,
+    path: &Path,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    show_path(path, options)
+}
+
+pub(crate) fn reveal_uri(
+    _state: &mut RevealState,
+    uri: &str,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    show_uri(uri, options)
+}
+
+pub(crate) fn show_selection(
+    selection: &Selection,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    let mut mounts = Vec::new();
+    for path in selection
+        .items
+        .iter()
+        .filter_map(|item| item_path(item, options))
+    {
+        mounts.extend(mount_volume(&path, options)?);
+    }
+    let outcome = appkit(|| unsafe { show_nsurls(selection, options) })?;
+    Ok(with_mounts(mounts, outcome))
+}
+
+unsafe fn show_nsurls(selection: &Selection, options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    let (urls, mut results) = selection.prepare(|item| match item {
+        Item::Path(path) => path_to_nsurl(path),
+        Item::Uri(uri) => uri_to_nsurl(uri, options),
+    })?;
+    // Only activateFileViewerSelectingURLs: can select several files at once, everything else
+    // has to show them one at a time
+    if options.activate && alternate_file_viewer(options).is_none() {
+        if let Some(workspace) = workspace_class() {
+            let ws: id = msg_send![workspace, sharedWorkspace];
+            let urls = urls.into_iter().map(|(_, url)| url).collect::<Vec<_>>();
+            let action = select_nsurls(ws, &urls);
+            return Ok(ShowOutcome {
+                items: results.finish()?,
+                ..finder_outcome(&urls, options, vec![action])
+            });
+        }
+    }
+    let mut outcome = ShowOutcome::default();
+    let (mut actions, mut warnings) = (Vec::new(), Vec::new());
+    let mut shown_count = 0;
+    for (i, url) in urls {
+        match show_nsurl_or_open(url, options) {
+            Ok(mut shown) => {
+                actions.append(&mut shown.actions);
+                for warning in shown.warnings.drain(..) {
+                    if !warnings.contains(&warning) {
+                        warnings.push(warning);
+                    }
+                }
+                shown_count += 1;
+                outcome = shown;
+            }
+            Err(e) => results.fail(i, e),
+        }
+    }
+    if shown_count > 1 {
+        warnings.push(Warning::ShownSeparately);
+    }
+    outcome.items = results.finish()?;
+    outcome.actions = actions;
+    outcome.warnings = warnings;
+    Ok(outcome)
+}
+
+pub(crate) fn open_path(path: &Path) -> Result<(), Error> {
+    // openURL: only reports failure, so check for the common cause first
+    if !path.exists() {
+        return Err(Error::InvalidPath);
+    }
+    let opened = appkit(|| unsafe {
+        let url = path_to_nsurl(path)?;
+        let Some(workspace) = workspace_class() else {
+            return Ok(None);
+        };
+        let ws: id = msg_send![workspace, sharedWorkspace];
+        let opened: BOOL = msg_send![ws, openURL:url];
+        Ok(Some(opened != NO))
+    })?;
+    match opened {
+        Some(true) => return Ok(()),
+        Some(false) => return Err(Error::NoApplication),
+        None => {}
+    }
+    let status = process::command("/usr/bin/open")
+        .arg(args::path(path))
+        .status()
+        .map_err(Error::backend)?;
+    if !status.success() {
+        return Err(Error::NoApplication);
+    }
+    Ok(())
+}
+
+pub(crate) fn open_path_with(bundle_id: &str, path: &Path) -> Result<(), Error> {
+    if !path.exists() {
+        return Err(Error::InvalidPath);
+    }
+    let opened = appkit(|| unsafe {
+        const NS_WORKSPACE_LAUNCH_DEFAULT: usize = 0x0001_0000;
+
+        let url = path_to_nsurl(path)?;
+        let Some(workspace) = workspace_class() else {
+            return Ok(None);
+        };
+        let ws: id = msg_send![workspace, sharedWorkspace];
+        let bundle_id = nsstring(bundle_id.as_bytes());
+        let app: id = msg_send![ws, URLForApplicationWithBundleIdentifier:bundle_id];
+        if app == nil {
+            return Err(Error::NoApplication);
+        }
+        let urls: id = msg_send![class!(NSArray), arrayWithObject:url];
+        let opened: BOOL = msg_send![
+            ws,
+            openURLs:urls
+            withAppBundleIdentifier:bundle_id
+            options:NS_WORKSPACE_LAUNCH_DEFAULT
+            additionalEventParamDescriptor:nil
+            launchIdentifiers:nil
+        ];
+        Ok(Some(opened != NO))
+    })?;
+    match opened {
+        Some(true) => return Ok(()),
+        Some(false) => return Err(Error::NoApplication),
+        None => {}
+    }
+    let status = process::command("/usr/bin/open")
+        .arg("-b")
+        .arg(bundle_id)
+        .arg(args::path(path))
+        .status()
+        .map_err(Error::backend)?;
+    if !status.success() {
+        return Err(Error::NoApplication);
+    }
+    Ok(())
+}
+
+pub(crate) fn show_properties(_path: &Path, _options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    Err(Error::Unsupported)
+}
+
+pub(crate) fn backend_error_kind(_err: &(dyn std::error::Error + 'static)) -> Option<ErrorKind> {
+    None
+}
+
+pub(crate) fn diagnose(diagnostics: &mut Diagnostics) {
+    let running = appkit(|| unsafe {
+        // Loads AppKit on Mac Catalyst
+        if workspace_class().is_none() {
+            return Ok(false);
+        }
+        let Some(class) = Class::get("NSRunningApplication") else {
+            return Ok(false);
+        };
+        let apps: id = msg_send![
+            class,
+            runningApplicationsWithBundleIdentifier:nsstring(b"com.apple.finder")
+        ];
+        let count: usize = msg_send![apps, count];
+        Ok(count > 0)
+    });
+    match running {
+        Ok(running) => diagnostics.finder_running = Some(running),
+        Err(e) => diagnostics
+            .problems
+            .push(format!("Finder: {}", crate::error::report(&e))),
+    }
+}
+
+pub(crate) fn backends() -> Vec<BackendInfo> {
+    vec![
+        // The version is the one in Cargo.toml
+        BackendInfo::new("NSWorkspace", Some(("objc", "0.2"))),
+        BackendInfo::new("open", None),
+        BackendInfo::new("osascript", None),
+    ]
+}
+
+pub(crate) fn capabilities() -> Capabilities {
+    let finder = unsafe { alternate_file_viewer(&ShowOptions::new()) }.is_none();
+    Capabilities {
+        multi_select: finder,
+        open_directory: true,
+        rename_mode: false,
+        properties_dialog: false,
+        uri_reveal: false,
+    }
+}
+
+pub(crate) fn supports_uri_scheme(scheme: &str) -> bool {
+    let handler = appkit(|| unsafe {
+        let Some(workspace) = workspace_class() else {
+            return Ok(None);
+        };
+        let ws: id = msg_send![workspace, sharedWorkspace];
+        let url: id = msg_send![
+            class!(NSURL),
+            URLWithString:nsstring(format!("{scheme}://").as_bytes())
+        ];
+        if url == nil {
+            return Ok(None);
+        }
+        let app: id = msg_send![ws, URLForApplicationToOpenURL:url];
+        if app == nil {
+            return Ok(None);
+        }
+        let bundle: id = msg_send![class!(NSBundle), bundleWithURL:app];
+        if bundle == nil {
+            return Ok(None);
+        }
+        let bundle_id: id = msg_send![bundle, bundleIdentifier];
+        Ok(nsstring_to_string(bundle_id))
+    });
+    let viewer = unsafe { alternate_file_viewer(&ShowOptions::new()) };
+    let viewer = viewer.as_deref().unwrap_or("com.apple.finder");
+    handler.is_ok_and(|handler| handler.is_some_and(|handler| handler.eq_ignore_ascii_case(viewer)))
+}
+
+pub(crate) fn sandbox_kind() -> Option<SandboxKind> {
+    is_sandboxed().then_some(SandboxKind::AppSandbox)
+}
+
+pub(crate) fn portals() -> Result<Vec<Portal>, Error> {
+    Ok(Vec::new())
+}
+
+pub(crate) fn file_manager_available() -> bool {
+    let workspace = unsafe { workspace_class() };
+    workspace.is_some() || Path::new("/usr/bin/open").exists()
+}
+
+pub(crate) fn show_bookmark(bookmark: &[u8], options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    const NSURL_BOOKMARK_RESOLUTION_WITH_SECURITY_SCOPE: usize = 1 << 10;
+
+    appkit(|| unsafe {
+        let data: id = msg_send![
+            class!(NSData),
+            dataWithBytes:bookmark.as_ptr()
+            length:bookmark.len()
+        ];
+        let mut stale: BOOL = NO;
+        let mut err: id = nil;
+        let url: id = msg_send![
+            class!(NSURL),
+            URLByResolvingBookmarkData:data
+            options:NSURL_BOOKMARK_RESOLUTION_WITH_SECURITY_SCOPE
+            relativeToURL:nil
+            bookmarkDataIsStale:&mut stale as *mut BOOL
+            error:&mut err as *mut id
+        ];
+        if url == nil {
+            return Err(nserror_to_error(err));
+        }
+        let accessing: BOOL = msg_send![url, startAccessingSecurityScopedResource];
+        // Always give up access, even if showing the file throws
+        let res = catch_exception(|| show_nsurl_or_open(url, options));
+        if accessing != NO {
+            let _: () = msg_send![url, stopAccessingSecurityScopedResource];
+        }
+        res
+    })
+}
+
+/// The NSWorkspace calls, for [`showfile::raw`](crate::raw).
+pub(crate) mod raw {
+    use std::path::Path;
+
+    use objc::{msg_send, sel, sel_impl};
+
+    use super::{appkit, id, path_to_nsurl, select_nsurls, workspace_class};
+    use crate::{Action, Error};
+
+    /// Shows `paths` selected in Finder, or the file viewer set as the default, with
+    /// `NSWorkspace activateFileViewerSelectingURLs:`.
+    ///
+    /// The call is made on the serial queue that this crate makes all its AppKit calls on, with
+    /// any exception it throws turned into an error. Returns the call as it is reported in
+    /// [`ShowOutcome::actions`](crate::ShowOutcome::actions), or [`Error::Unsupported`] if AppKit
+    /// can't be loaded, as in some Mac Catalyst apps. Nothing else is tried, such as `open -R`.
+    pub fn activate_file_viewer_selecting(paths: &[&Path]) -> Result<Action, Error> {
+        appkit(|| unsafe {
+            let workspace = workspace_class().ok_or(Error::Unsupported)?;
+            let urls = paths
+                .iter()
+                .map(|path| path_to_nsurl(path))
+                .collect::<Result<Vec<_>, _>>()?;
+            let ws: id = msg_send![workspace, sharedWorkspace];
+            Ok(select_nsurls(ws, &urls))
+        })
+    }
+}
@@ -0,0 +1,423 @@
+//! The `showfile` command, which shows files in the file manager from scripts and the terminal.
+
+use std::{
+    ffi::{OsStr, OsString},
+    fmt::Write,
+    fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use showfile::{Error, Selection, ShowOptions, ShowOutcome, Warning};
+
+const USAGE: &str = "\
+Usage: showfile [OPTIONS] [PATH|URI]...
+
+Shows files in the file manager, selected in their folders.
+
+Options:
+      --open-dir           Open the given folders to show what's in them, instead of selecting
+                           them in their parents; only takes local folders, with the default
+                           application for folders
+      --properties         Show the file manager's properties dialog for each item
+      --rename             Start editing the name of the item once it is shown
+      --no-activate        Leave the file manager window behind the current one
+      --backend <NAME>     Show the items with this backend, such as `file-manager1`, `dolphin`
+                           or `xdg-open` on Linux; can be repeated to try several in order
+      --files-from <FILE>  Also show the items listed in FILE, one per line, or read them from
+                           standard input if FILE is -
+  -0, --null               Items in the list are separated by NUL instead of newlines, as
+                           printed by `find -print0` or `fd -0`; reads the list from standard
+                           input if --files-from isn't given
+      --json               Print the outcome as JSON: which backend showed the items, whether
+                           they are selected, the application that handled them, and any
+                           warnings or errors
+  -h, --help               Print this help
+  -V, --version            Print the version, and the features and backends it was built with
+";
+
+/// What to do with the items.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum Mode {
+    #[default]
+    Select,
+    OpenDir,
+    Properties,
+}
+
+/// The parsed command line.
+#[derive(Default)]
+struct Args {
+    items: Vec<OsString>,
+    files_from: Option<OsString>,
+    null: bool,
+    json: bool,
+    mode: Mode,
+    rename: bool,
+    no_activate: bool,
+    backends: Vec<String>,
+}
+
+/// What to do, as asked on the command line.
+enum Command {
+    Show(Args),
+    Help,
+    Version,
+}
+
+/// Returns `mode`, unless another mode than selecting the items was already set.
+fn set_mode(current: Mode, mode: Mode) -> Result<Mode, String> {
+    match current {
+        Mode::Select => Ok(mode),
+        _ => Err("--open-dir and --properties can't be combined".to_owned()),
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = OsString>) -> Result<Command, String> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        let Some(flag) = arg
+            .to_str()
+            .filter(|arg| arg.starts_with('-') && *arg != "-")
+        else {
+            parsed.items.push(arg);
+            continue;
+        };
+        match flag {
+            "--" => {
+                parsed.items.extend(args);
+                break;
+            }
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-0" | "--null" => parsed.null = true,
+            "--json" => parsed.json = true,
+            "--open-dir" => parsed.mode = set_mode(parsed.mode, Mode::OpenDir)?,
+            "--properties" => parsed.mode = set_mode(parsed.mode, Mode::Properties)?,
+            "--rename" => parsed.rename = true,
+            "--no-activate" => parsed.no_activate = true,
+            "--files-from" => {
+                let file = args
+                    .next()
+                    .ok_or("--files-from needs a file, or - for standard input")?;
+                parsed.files_from = Some(file);
+            }
+            "--backend" => {
+                let backend = args.next().ok_or("--backend needs a name")?;
+                let backend = backend.into_string().map_err(|_| "unknown backend")?;
+                parsed.backends.push(backend);
+            }
+            _ => {
+                if let Some(file) = flag.strip_prefix("--files-from=") {
+                    parsed.files_from = Some(file.into());
+                } else if let Some(backend) = flag.strip_prefix("--backend=") {
+                    parsed.backends.push(backend.to_owned());
+                } else {
+                    return Err(format!("unknown option {flag}"));
+                }
+            }
+        }
+    }
+    if parsed.rename && parsed.mode != Mode::Select {
+        return Err("--rename only applies when selecting items".to_owned());
+    }
+    if parsed.mode == Mode::OpenDir && (parsed.no_activate || !parsed.backends.is_empty()) {
+        return Err("--backend and --no-activate can't be combined with --open-dir".to_owned());
+    }
+    if parsed.null && parsed.files_from.is_none() {
+        parsed.files_from = Some("-".into());
+    }
+    Ok(Command::Show(parsed))
+}
+
+/// Reads the list of items in `file`, or standard input if it is `-`, separated by NUL if `null`
+/// is set, or by newlines otherwise.
+fn read_items(file: &OsStr, null: bool) -> io::Result<Vec<OsString>> {
+    let data = if file == "-" {
+        let mut data = Vec::new();
+        io::stdin().lock().read_to_end(&mut data)?;
+        data
+    } else {
+        fs::read(file)?
+    };
+    let separator = if null { b'\0' } else { b'\n' };
+    Ok(data
+        .split(|&b| b == separator)
+        .map(|item| {
+            if null {
+                item
+            } else {
+                item.strip_suffix(b"\r").unwrap_or(item)
+            }
+        })
+        .filter(|item| !item.is_empty())
+        .map(os_string)
+        .collect())
+}
+
+#[cfg(unix)]
+fn os_string(bytes: &[u8]) -> OsString {
+    use std::os::unix::ffi::OsStrExt;
+
+    OsStr::from_bytes(bytes).to_owned()
+}
+
+#[cfg(not(unix))]
+fn os_string(bytes: &[u8]) -> OsString {
+    String::from_utf8_lossy(bytes).into_owned().into()
+}
+
+/// Checks whether `item` is taken for a URI, which it is if it has an authority such as `smb://`
+/// or is a `file:` URI.
+fn is_uri(item: &str) -> bool {
+    item.contains("://") || item.starts_with("file:")
+}
+
+/// Adds `item` to `selection`, as a URI if [`is_uri`] says so, or as a path otherwise.
+fn push_item(selection: &mut Selection, item: OsString) {
+    match item.into_string() {
+        Ok(item) if is_uri(&item) => {
+            selection.uri(item);
+        }
+        Ok(item) => {
+            selection.path(item);
+        }
+        Err(item) => {
+            selection.path(item);
+        }
+    }
+}
+
+/// Quotes `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Formats `value` as JSON, or `null` if it is `None`.
+fn json_or_null(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "null".to_owned())
+}
+
+/// Formats a warning as a JSON object with its `kind` and `message`.
+fn json_problem(kind: &str, message: &str) -> String {
+    format!(
+        "{{\"kind\":{},\"message\":{}}}",
+        json_string(kind),
+        json_string(message)
+    )
+}
+
+/// Formats `err` followed by the errors that caused it, such as the message of the platform API
+/// that failed.
+fn report(err: &Error) -> String {
+    let mut report = err.to_string();
+    let mut source = std::error::Error::source(err);
+    while let Some(err) = source {
+        let _ = write!(report, ": {err}");
+        source = err.source();
+    }
+    report
+}
+
+/// Formats an error as a JSON object with its `kind`, `message`, and the label of its `hint`.
+fn json_error(err: &Error) -> String {
+    format!(
+        "{{\"kind\":{},\"message\":{},\"hint\":{}}}",
+        json_string(err.kind().label()),
+        json_string(&report(err)),
+        json_or_null(err.hint().map(|hint| json_string(hint.label())))
+    )
+}
+
+/// Formats the result of showing `items` as a JSON object on one line.
+fn json_result(items: &[OsString], res: &Result<ShowOutcome, Error>) -> String {
+    let outcome = match res {
+        Ok(outcome) => outcome,
+        Err(e) => return format!("{{\"ok\":false,\"error\":{}}}", json_error(e)),
+    };
+    let selected = !outcome
+        .warnings
+        .iter()
+        .any(|warning| matches!(warning, Warning::NotSelected | Warning::Fallback));
+    let actions: Vec<_> = outcome
+        .actions
+        .iter()
+        .map(|action| json_string(&action.to_string()))
+        .collect();
+    let warnings: Vec<_> = outcome
+        .warnings
+        .iter()
+        .map(|warning| json_problem(warning.label(), &warning.to_string()))
+        .collect();
+    let results: Vec<_> = items
+        .iter()
+        .zip(&outcome.items)
+        .map(|(item, res)| {
+            format!(
+                "{{\"item\":{},\"error\":{}}}",
+                json_string(&item.to_string_lossy()),
+                json_or_null(res.as_ref().err().map(json_error))
+            )
+        })
+        .collect();
+    format!(
+        "{{\"ok\":true,\"backend\":{},\"selected\":{selected},\"window_appeared\":{},\
+         \"handler\":{},\"actions\":[{}],\"warnings\":[{}],\"items\":[{}]}}",
+        json_or_null(outcome.backend().map(json_string)),
+        json_or_null(outcome.window_appeared.map(|appeared| appeared.to_string())),
+        json_or_null(
+            outcome
+                .handler
+                .as_ref()
+                .map(|handler| json_string(&handler.id))
+        ),
+        actions.join(","),
+        warnings.join(","),
+        results.join(","),
+    )
+}
+
+/// Opens the folder `item` with the default application for folders.
+///
+/// Only local folders are taken, since the default application of a file or a URI may not be a
+/// file manager at all.
+fn open_dir(item: &OsStr) -> Result<ShowOutcome, Error> {
+    if item.to_str().is_some_and(is_uri) {
+        return Err(Error::InvalidPath);
+    }
+    match fs::metadata(item) {
+        Ok(metadata) if metadata.is_dir() => {}
+        Ok(_) => return Err(Error::InvalidPath),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(Error::NotFound),
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            return Err(Error::PermissionDenied)
+        }
+        Err(_) => return Err(Error::InvalidPath),
+    }
+    showfile::open_path_with_default_app(item)?;
+    Ok(ShowOutcome::default())
+}
+
+/// Calls `f` for each of `items` in turn, combining the outcomes the way showing a selection does:
+/// the errors of the items that failed are reported in the outcome of the others, unless they all
+/// failed.
+fn each_item(
+    items: &[OsString],
+    mut f: impl FnMut(&OsStr) -> Result<ShowOutcome, Error>,
+) -> Result<ShowOutcome, Error> {
+    let mut combined = ShowOutcome::default();
+    for item in items {
+        match f(item) {
+            Ok(outcome) => {
+                combined.handler = combined.handler.or(outcome.handler);
+                combined.actions.extend(outcome.actions);
+                combined.warnings.extend(outcome.warnings);
+                combined.items.push(Ok(()));
+            }
+            Err(e) => combined.items.push(Err(e)),
+        }
+    }
+    if combined.items.iter().all(Result::is_err) {
+        if let Some(Err(e)) = combined.items.pop() {
+            return Err(e);
+        }
+    }
+    Ok(combined)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args_os().skip(1)) {
+        Ok(Command::Show(args)) => args,
+        Ok(Command::Help) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            print!("{}", showfile::build_info());
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("showfile: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let mut selection = Selection::new();
+    let listed = match &args.files_from {
+        Some(file) => match read_items(file, args.null) {
+            Ok(items) => items,
+            Err(e) => {
+                eprintln!("showfile: can't read {}: {e}", file.to_string_lossy());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Vec::new(),
+    };
+    let items: Vec<_> = args.items.into_iter().chain(listed).collect();
+    for item in &items {
+        push_item(&mut selection, item.clone());
+    }
+    if selection.is_empty() && args.files_from.is_none() {
+        eprintln!("showfile: no items to show\n\n{USAGE}");
+        return ExitCode::from(2);
+    }
+    let mut options = ShowOptions::new();
+    options.identify_handler(args.json).rename(args.rename);
+    if args.no_activate {
+        options.activate(false);
+    }
+    if !args.backends.is_empty() {
+        options.backends(args.backends);
+    }
+    let res = match args.mode {
+        Mode::Select => options.show_selection(&selection),
+        Mode::OpenDir => each_item(&items, open_dir),
+        Mode::Properties => each_item(&items, |item| options.show_properties(item)),
+    };
+    if args.json {
+        println!("{}", json_result(&items, &res));
+        return match res {
+            Ok(_) => ExitCode::SUCCESS,
+            Err(_) => ExitCode::FAILURE,
+        };
+    }
+    match res {
+        Ok(outcome) => {
+            for (item, res) in items.iter().zip(&outcome.items) {
+                if let Err(e) = res {
+                    eprintln!(
+                        "showfile: skipped {}: {}",
+                        item.to_string_lossy(),
+                        report(e)
+                    );
+                }
+            }
+            for warning in &outcome.warnings {
+                eprintln!("showfile: {warning}");
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("showfile: {}", report(&e));
+            if let Some(hint) = e.hint() {
+                eprintln!("showfile: hint: {hint}");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
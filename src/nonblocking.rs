@@ -0,0 +1,256 @@
+//! Functions that return immediately and run in the background.
+//!
+//! These mirror the functions in [`blocking`](crate::blocking), but hand the call off to a worker
+//! thread owned by the crate, see [`ThreadOptions`](crate::ThreadOptions). Each returns a
+//! [`Pending`] call, which can be awaited from any async runtime, or waited for from a thread that
+//! is allowed to block.
+//!
+//! ```no_run
+//! # async fn reveal() -> Result<(), showfile::Error> {
+//! showfile::nonblocking::show_path_in_file_manager("/home/alice/hello.txt").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    ffi::OsStr,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+use crate::{blocking, threads, Backend, Diagnostics, Error, LocationStatus, Selection};
+
+/// A call running in the background.
+///
+/// Await it, or call [`wait`](Self::wait) to block until it returns. Dropping it doesn't cancel
+/// the call. If the call panics, the panic is resumed when the result is taken.
+#[derive(Debug)]
+#[must_use = "the result of the call is lost unless it is awaited or waited for"]
+pub struct Pending<T> {
+    shared: Arc<Shared<T>>,
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    done: Condvar,
+}
+
+#[derive(Debug)]
+struct State<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+impl<T: Send + 'static> Pending<T> {
+    /// Runs `f` on a worker thread.
+    fn spawn(f: impl FnOnce() -> T + Send + 'static) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                result: None,
+                waker: None,
+            }),
+            done: Condvar::new(),
+        });
+        let worker = shared.clone();
+        threads::execute(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f));
+            let mut state = worker.state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+            worker.done.notify_all();
+        });
+        Self { shared }
+    }
+}
+
+impl<T> Pending<T> {
+    /// Blocks until the call returns, and returns its result.
+    pub fn wait(self) -> T {
+        let mut state = self.shared.state.lock().unwrap();
+        loop {
+            if let Some(result) = state.result.take() {
+                return unwrap_result(result);
+            }
+            state = self.shared.done.wait(state).unwrap();
+        }
+    }
+
+    /// Checks whether the call has returned, so that the result can be taken without blocking.
+    pub fn is_finished(&self) -> bool {
+        self.shared.state.lock().unwrap().result.is_some()
+    }
+}
+
+impl<T> Future for Pending<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(unwrap_result(result)),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+fn unwrap_result<T>(result: thread::Result<T>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+/// Shows `path` in a file manager in the background.
+///
+/// See [`blocking::show_path_in_file_manager`] for details.
+pub fn show_path_in_file_manager(path: impl AsRef<Path>) -> Pending<Result<(), Error>> {
+    let path = path.as_ref().to_owned();
+    Pending::spawn(move || blocking::show_path_in_file_manager(path))
+}
+
+/// Shows `uri` in a file manager in the background.
+///
+/// See [`blocking::show_uri_in_file_manager`] for details.
+pub fn show_uri_in_file_manager(uri: impl AsRef<str>) -> Pending<Result<(), Error>> {
+    let uri = uri.as_ref().to_owned();
+    Pending::spawn(move || blocking::show_uri_in_file_manager(uri))
+}
+
+/// Shows the file manager's properties dialog for `path` in the background.
+///
+/// See [`blocking::show_properties`] for details.
+pub fn show_properties(path: impl AsRef<Path>) -> Pending<Result<(), Error>> {
+    let path = path.as_ref().to_owned();
+    Pending::spawn(move || blocking::show_properties(path))
+}
+
+/// Shows several URIs in a file manager at once in the background.
+///
+/// See [`blocking::show_uris_in_file_manager`] for details.
+pub fn show_uris_in_file_manager<I>(uris: I) -> Pending<Result<(), Error>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let uris = uris
+        .into_iter()
+        .map(|uri| uri.as_ref().to_owned())
+        .collect::<Vec<_>>();
+    Pending::spawn(move || blocking::show_uris_in_file_manager(uris))
+}
+
+/// Shows a file picked in an `rfd` dialog in a file manager in the background.
+///
+/// See [`blocking::show_file_handle_in_file_manager`] for details.
+#[cfg(feature = "rfd")]
+pub fn show_file_handle_in_file_manager(handle: &rfd::FileHandle) -> Pending<Result<(), Error>> {
+    let handle = handle.clone();
+    Pending::spawn(move || blocking::show_file_handle_in_file_manager(&handle))
+}
+
+/// Shows the running executable in a file manager in the background.
+///
+/// See [`blocking::show_current_exe`] for details.
+pub fn show_current_exe() -> Pending<Result<(), Error>> {
+    Pending::spawn(blocking::show_current_exe)
+}
+
+/// Shows the working directory of the process in a file manager in the background.
+///
+/// See [`blocking::show_current_dir`] for details.
+pub fn show_current_dir() -> Pending<Result<(), Error>> {
+    Pending::spawn(blocking::show_current_dir)
+}
+
+/// Shows the most recently modified file in `dir` in a file manager in the background.
+///
+/// See [`blocking::show_latest_in_dir`] for details.
+pub fn show_latest_in_dir(dir: impl AsRef<Path>) -> Pending<Result<(), Error>> {
+    let dir = dir.as_ref().to_owned();
+    Pending::spawn(move || blocking::show_latest_in_dir(dir))
+}
+
+/// Shows the items in `dir` whose names match the glob `pattern` in a file manager in the
+/// background.
+///
+/// See [`blocking::show_matching`] for details.
+pub fn show_matching(dir: impl AsRef<Path>, pattern: &str) -> Pending<Result<(), Error>> {
+    let (dir, pattern) = (dir.as_ref().to_owned(), pattern.to_owned());
+    Pending::spawn(move || blocking::show_matching(dir, &pattern))
+}
+
+/// Shows a [`Selection`] of paths and URIs in a file manager at once in the background.
+///
+/// See [`blocking::show_selection_in_file_manager`] for details.
+pub fn show_selection_in_file_manager(selection: &Selection) -> Pending<Result<(), Error>> {
+    let selection = selection.clone();
+    Pending::spawn(move || blocking::show_selection_in_file_manager(&selection))
+}
+
+/// Shows a [`Selection`] of paths and URIs with `backend` alone in the background.
+///
+/// See [`blocking::show_with_backend`] for details.
+pub fn show_with_backend<B: Backend + 'static>(
+    backend: B,
+    selection: &Selection,
+) -> Pending<Result<(), Error>> {
+    let selection = selection.clone();
+    Pending::spawn(move || blocking::show_with_backend(backend, &selection))
+}
+
+/// Opens `path` with the default application for its type in the background.
+///
+/// See [`blocking::open_path_with_default_app`] for details.
+pub fn open_path_with_default_app(path: impl AsRef<Path>) -> Pending<Result<(), Error>> {
+    let path = path.as_ref().to_owned();
+    Pending::spawn(move || blocking::open_path_with_default_app(path))
+}
+
+/// Opens `path` with a specific application in the background.
+///
+/// See [`blocking::open_path_with`] for details.
+pub fn open_path_with(app: &str, path: impl AsRef<Path>) -> Pending<Result<(), Error>> {
+    let (app, path) = (app.to_owned(), path.as_ref().to_owned());
+    Pending::spawn(move || blocking::open_path_with(&app, path))
+}
+
+/// Checks whether a file manager is available in the background.
+///
+/// See [`blocking::file_manager_available`] for details.
+pub fn file_manager_available() -> Pending<bool> {
+    Pending::spawn(blocking::file_manager_available)
+}
+
+/// Checks the environment the file manager is called in the background.
+///
+/// See [`blocking::doctor`] for details.
+pub fn doctor() -> Pending<Diagnostics> {
+    Pending::spawn(blocking::doctor)
+}
+
+/// Finds out where a path or URI is stored in the background.
+///
+/// See [`blocking::location_status`] for details.
+pub fn location_status(location: impl AsRef<OsStr>) -> Pending<Result<LocationStatus, Error>> {
+    let location = location.as_ref().to_owned();
+    Pending::spawn(move || blocking::location_status(location))
+}
+
+/// Checks whether the current user can reach `path` in the background.
+///
+/// See [`blocking::is_accessible`] for details.
+pub fn is_accessible(path: impl AsRef<Path>) -> Pending<Result<(), Error>> {
+    let path = path.as_ref().to_owned();
+    Pending::spawn(move || blocking::is_accessible(path))
+}
@@ -0,0 +1,722 @@
+use std::{path::Path, sync::Arc, time::Duration};
+
+use crate::{
+    backend,
+    config::UserConfig,
+    dir, location, platform,
+    selection::Item,
+    telemetry, threads,
+    uri::{DefaultCodec, SharedCodec},
+    Backend, Error, RevealSession, Selection, ShowOutcome, ShowPolicy, UriCodec,
+};
+
+/// Options for showing files in the file manager.
+///
+/// This is a builder similar to [`std::fs::OpenOptions`]. Create it with [`ShowOptions::new`],
+/// chain calls to set each option, then call [`show_path`](Self::show_path) or
+/// [`show_uri`](Self::show_uri).
+///
+/// ```no_run
+/// showfile::ShowOptions::new()
+///     .activate(false)
+///     .show_path("/Users/Bob/hello.txt")?;
+/// # Ok::<(), showfile::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShowOptions {
+    pub(crate) activate: bool,
+    pub(crate) fallback: bool,
+    pub(crate) file_manager: Option<String>,
+    pub(crate) backends: Option<Vec<String>>,
+    pub(crate) introspect: bool,
+    pub(crate) verify: bool,
+    pub(crate) identify_handler: bool,
+    pub(crate) dbus_address: Option<DBusAddress>,
+    pub(crate) activation_token: Option<String>,
+    pub(crate) desktop_heuristics: bool,
+    pub(crate) reuse_window: bool,
+    pub(crate) library_view: bool,
+    pub(crate) automount: Automount,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) match_limit: usize,
+    pub(crate) item_limit: usize,
+    pub(crate) window_limit: usize,
+    pub(crate) policy: ShowPolicy,
+    pub(crate) quiet: bool,
+    pub(crate) rename: bool,
+    pub(crate) uri_codec: Option<SharedCodec>,
+    #[cfg(feature = "raw-window-handle")]
+    pub(crate) parent: ParentWindow,
+}
+
+/// Whether to mount the volume of an item that is on an unmounted volume before showing it, as
+/// set with [`ShowOptions::automount`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Automount {
+    /// Show the item as is, which usually fails because the file manager can't find it.
+    #[default]
+    Never,
+    /// Ask the user whether to mount the volume first, and only mount it if they agree.
+    Ask,
+    /// Mount the volume without asking.
+    Always,
+}
+
+/// Where to connect to D-Bus instead of the session bus.
+#[derive(Clone, Debug)]
+#[cfg_attr(not(freedesktop), allow(dead_code))]
+pub(crate) enum DBusAddress {
+    Bus(String),
+    Peer(String),
+}
+
+/// The application window set with [`ShowOptions::parent_window`], and the display it is on.
+#[cfg(feature = "raw-window-handle")]
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(
+    any(target_os = "macos", target_abi = "macabi", target_os = "redox"),
+    allow(dead_code)
+)]
+pub(crate) struct ParentWindow {
+    pub(crate) window: Option<raw_window_handle::RawWindowHandle>,
+    pub(crate) display: Option<raw_window_handle::RawDisplayHandle>,
+    pub(crate) same_monitor: bool,
+}
+
+// The handles are only passed on to the system, which can be called from any thread with them
+#[cfg(feature = "raw-window-handle")]
+unsafe impl Send for ParentWindow {}
+#[cfg(feature = "raw-window-handle")]
+unsafe impl Sync for ParentWindow {}
+
+impl Default for ShowOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShowOptions {
+    /// Creates a new set of options with default values.
+    ///
+    /// The defaults of [`activate`](Self::activate), [`file_manager`](Self::file_manager) and
+    /// [`backends`](Self::backends) can be set by the user for every application in
+    /// `showfile.toml`, in their configuration folder: `$XDG_CONFIG_HOME`, or `~/.config`, on
+    /// Linux and Redox OS, `~/Library/Application Support` on macOS, and `%APPDATA%` on Windows.
+    /// The file is read the first time options are created, and again after
+    /// [`refresh`](crate::refresh), and it takes top-level keys with the names of these options:
+    ///
+    /// ```toml
+    /// file_manager = "com.binarynights.ForkLift"
+    /// activate = false
+    /// backends = ["dolphin", "file-manager1", "xdg-open"]
+    /// ```
+    ///
+    /// Options set by the application override the user's settings.
+    pub fn new() -> Self {
+        let config = UserConfig::get();
+        Self {
+            activate: config.activate.unwrap_or(true),
+            fallback: true,
+            file_manager: config.file_manager,
+            backends: config.backends,
+            introspect: false,
+            verify: false,
+            identify_handler: false,
+            dbus_address: None,
+            activation_token: None,
+            desktop_heuristics: true,
+            reuse_window: false,
+            library_view: false,
+            automount: Automount::Never,
+            timeout: None,
+            match_limit: 100,
+            item_limit: 50,
+            window_limit: 10,
+            policy: ShowPolicy::default(),
+            quiet: false,
+            rename: false,
+            uri_codec: None,
+            #[cfg(feature = "raw-window-handle")]
+            parent: ParentWindow::default(),
+        }
+    }
+
+    /// Sets whether the file manager should be brought to the front.
+    ///
+    /// When `false`, the file is selected in a file manager window while the current application
+    /// stays in front. This is best-effort. On Linux, this controls whether an activation token is
+    /// requested for the window set with [`parent_window`](Self::parent_window), without which
+    /// most file managers aren't allowed to take focus. Defaults to `true`, unless set in the
+    /// user's configuration file, see [`new`](Self::new).
+    pub fn activate(&mut self, activate: bool) -> &mut Self {
+        self.activate = activate;
+        self
+    }
+
+    /// Sets whether to fall back to a degraded way of showing the file if the file manager is
+    /// unavailable.
+    ///
+    /// On Linux, if nothing provides the `org.freedesktop.FileManager1` D-Bus interface, the
+    /// containing folder is opened with `xdg-open` instead, without selecting the file. When
+    /// `false`, [`Error::NoFileManager`] is returned instead. Defaults to `true`.
+    pub fn fallback(&mut self, fallback: bool) -> &mut Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Sets the file manager to use instead of the system default.
+    ///
+    /// On macOS, this is the bundle identifier of a Finder alternative such as
+    /// `com.cocoatech.PathFinder` or `com.binarynights.ForkLift`. If unset, the app set as the
+    /// `NSFileViewer` default is used, falling back to Finder. The user can set it in their
+    /// configuration file, see [`new`](Self::new). Ignored on other platforms.
+    pub fn file_manager(&mut self, file_manager: impl Into<String>) -> &mut Self {
+        self.file_manager = Some(file_manager.into());
+        self
+    }
+
+    /// Sets the ways to show items to try, in order, instead of choosing one for the desktop
+    /// environment.
+    ///
+    /// On Linux, these are `file-manager1`, to call the `org.freedesktop.FileManager1` provider,
+    /// `dolphin`, to start Dolphin with `--select`, and `garcon` and `xdg-open`, which open the
+    /// containing folders in the ChromeOS Files app or the default file manager without selecting
+    /// the items. The first one that doesn't fail shows the items, and the error of the last one
+    /// is returned if they all fail. Unknown names are skipped. This replaces the
+    /// [`desktop_heuristics`](Self::desktop_heuristics) and the [`fallback`](Self::fallback).
+    /// Only used on Linux. Defaults to choosing automatically, unless set in the user's
+    /// configuration file, see [`new`](Self::new).
+    pub fn backends<I>(&mut self, backends: I) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.backends = Some(backends.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets whether to check which methods the file manager implements before calling it.
+    ///
+    /// On Linux, some minimal `org.freedesktop.FileManager1` providers only implement part of the
+    /// interface. When enabled, the provider is introspected and the result cached, so that if it
+    /// can't select items, the containing folder is opened with `ShowFolders` instead. Ignored on
+    /// other platforms. Defaults to `false`.
+    pub fn introspect(&mut self, introspect: bool) -> &mut Self {
+        self.introspect = introspect;
+        self
+    }
+
+    /// Sets whether to check that a file manager window likely appeared after showing the file.
+    ///
+    /// The result is reported in [`ShowOutcome::window_appeared`], so that the application can
+    /// offer another way to get to the file if nothing showed up. On Windows, the open Explorer
+    /// windows are polled for one showing the containing folder. On macOS, Finder is asked for its
+    /// front window with AppleScript, which requires the automation permission. On Linux, the
+    /// file manager is considered to have shown the file once it replies to the D-Bus call. This
+    /// can add up to a couple of seconds of blocking. Defaults to `false`.
+    pub fn verify(&mut self, verify: bool) -> &mut Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Sets whether to find out which application showed the file.
+    ///
+    /// The result is reported in [`ShowOutcome::handler`], for diagnostics. On Windows, this is
+    /// the application registered to open folders. On macOS, this is Finder or the configured
+    /// alternative. On Linux, the process that owns the FileManager1 name is looked up, and its
+    /// desktop entry is searched for, which takes a few more D-Bus calls. Defaults to `false`.
+    pub fn identify_handler(&mut self, identify_handler: bool) -> &mut Self {
+        self.identify_handler = identify_handler;
+        self
+    }
+
+    /// Sets the address of the D-Bus message bus to use instead of the session bus.
+    ///
+    /// This is a [D-Bus address](https://dbus.freedesktop.org/doc/dbus-specification.html#addresses)
+    /// such as `unix:path=/run/user/1000/bus`, for example a proxied bus inside a container.
+    /// Replaces any address set with [`dbus_peer_address`](Self::dbus_peer_address). Only used on
+    /// Linux.
+    pub fn dbus_address(&mut self, address: impl Into<String>) -> &mut Self {
+        self.dbus_address = Some(DBusAddress::Bus(address.into()));
+        self
+    }
+
+    /// Sets the address of a peer to call the file manager methods on directly, instead of going
+    /// through a message bus.
+    ///
+    /// This is for applications that broker D-Bus access themselves. The peer must implement the
+    /// `org.freedesktop.FileManager1` interface at `/org/freedesktop/FileManager1`. Replaces any
+    /// address set with [`dbus_address`](Self::dbus_address). Only used on Linux.
+    pub fn dbus_peer_address(&mut self, address: impl Into<String>) -> &mut Self {
+        self.dbus_address = Some(DBusAddress::Peer(address.into()));
+        self
+    }
+
+    /// Sets whether to adapt to the desktop environment.
+    ///
+    /// The desktop environment is named in `XDG_CURRENT_DESKTOP`. On KDE, Dolphin is started
+    /// directly with `--select` if no FileManager1 provider is running, which is faster than
+    /// having it activated by D-Bus. On LXDE, whose file manager doesn't provide FileManager1, the
+    /// fallback is used straight away unless a provider is running. In ChromeOS's Linux container
+    /// (Crostini), the folders are opened in the Files app on the host through `garcon`, without
+    /// selecting the files. These guesses are skipped when a D-Bus address is set. Only used on
+    /// Linux. Defaults to `true`.
+    pub fn desktop_heuristics(&mut self, desktop_heuristics: bool) -> &mut Self {
+        self.desktop_heuristics = desktop_heuristics;
+        self
+    }
+
+    /// Sets whether to show the items in a file manager window that already shows their folder,
+    /// instead of letting the file manager decide whether to open another one.
+    ///
+    /// On Linux, this is done through Dolphin's D-Bus interface: if a running Dolphin window has
+    /// the folders open, the items are selected in it and it is brought to the front. Otherwise,
+    /// or with other file managers, the file manager is called as usual. Explorer and Finder
+    /// already reuse windows. Only used on Linux. Defaults to `false`.
+    pub fn reuse_window(&mut self, reuse_window: bool) -> &mut Self {
+        self.reuse_window = reuse_window;
+        self
+    }
+
+    /// Sets whether to show items in the shell library that includes their folder, such as
+    /// Documents or Pictures, instead of in the folder itself.
+    ///
+    /// On Windows, libraries aggregate several folders, so a file in one of them can be shown
+    /// with the library in the address bar and navigation pane, the way the user usually browses
+    /// to it. Items outside every library are shown in their folder as usual. Only applies to
+    /// single items, and only used on Windows. Defaults to `false`.
+    pub fn library_view(&mut self, library_view: bool) -> &mut Self {
+        self.library_view = library_view;
+        self
+    }
+
+    /// Sets whether to mount the volume an item is on first, if it isn't mounted.
+    ///
+    /// On macOS, paths under `/Volumes` whose volume isn't mounted, such as a disk that was
+    /// unmounted but is still connected or a disk image that is attached but not mounted, are
+    /// looked up with Disk Arbitration, and the volume with that name is mounted before the item is
+    /// shown. Disk images that aren't attached at all can't be found. With [`Automount::Ask`], a
+    /// dialog asks the user first.
+    ///
+    /// On Linux, URIs of locations that GVfs mounts, such as `smb://`, `mtp://` or `davs://`, have
+    /// their volume mounted with `g_file_mount_enclosing_volume`, or with the `gio` tool when GIO
+    /// isn't linked or the file manager runs as another user. There is no way to ask for
+    /// passwords, so only volumes that don't need one or whose password is saved in the keyring
+    /// can be mounted, and [`Automount::Ask`] mounts them without asking.
+    ///
+    /// Not used on other platforms. Defaults to [`Automount::Never`].
+    pub fn automount(&mut self, automount: Automount) -> &mut Self {
+        self.automount = automount;
+        self
+    }
+
+    /// Sets how long to wait for the file manager before giving up with [`Error::TimedOut`].
+    ///
+    /// A file manager that hangs, or a broken D-Bus connection, can otherwise block the calling
+    /// thread indefinitely. With a timeout, the call is made on a thread of its own, see
+    /// [`ThreadOptions`](crate::ThreadOptions), and abandoned after the deadline. On Linux, the
+    /// D-Bus calls also time out, so that the thread closes its connection and exits. Calls made
+    /// through a [`RevealSession`] only use the D-Bus timeouts. Defaults to no timeout.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the most items [`show_matching`](Self::show_matching) shows at once.
+    ///
+    /// When more items match, only the first ones in order of their names are shown, so that a
+    /// broad pattern doesn't select thousands of files. The [`item_limit`](Self::item_limit)
+    /// applies as well. Defaults to `100`.
+    pub fn match_limit(&mut self, limit: usize) -> &mut Self {
+        self.match_limit = limit;
+        self
+    }
+
+    /// Sets which items may be shown.
+    ///
+    /// Every path and URI is checked against `policy` before anything is passed to the system,
+    /// including by a [`RevealSession`] created from these options, and the call fails with
+    /// [`Error::PolicyViolation`] if any is outside of it. Defaults to allowing everything.
+    pub fn policy(&mut self, policy: ShowPolicy) -> &mut Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets whether to refuse showing items that would likely have the file manager show an error
+    /// dialog.
+    ///
+    /// Before anything is passed to the system, each path is checked to exist and be accessible,
+    /// each URI to have a scheme the file manager supports and, unless
+    /// [`automount`](Self::automount) is set, to be on a mounted volume, and a file manager to be
+    /// available, see [`file_manager_available`](crate::file_manager_available). The call fails
+    /// with the error found instead, such as [`Error::NotFound`], [`Error::PermissionDenied`] or
+    /// [`Error::Unsupported`]. Items that only the file manager can find, such as entries inside
+    /// ZIP archives on Windows, are refused as well. Defaults to `false`.
+    pub fn quiet(&mut self, quiet: bool) -> &mut Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Sets whether to show an item with its name ready to be edited, as if the user had started
+    /// renaming it.
+    ///
+    /// On Windows, Explorer selects the item and starts editing its name, which only applies when
+    /// showing a single item. Other file managers can't be asked to do this, see
+    /// [`Capabilities::rename_mode`](crate::Capabilities::rename_mode), so the call fails with
+    /// [`Error::Unsupported`] on other platforms. Defaults to `false`.
+    pub fn rename(&mut self, rename: bool) -> &mut Self {
+        self.rename = rename;
+        self
+    }
+
+    /// Sets the most items that can be shown at once.
+    ///
+    /// Showing a [`Selection`] or several URIs with more items than this fails with
+    /// [`Error::TooManyItems`] before anything is shown, so that passing an unbounded list by
+    /// mistake doesn't flood the desktop. Defaults to `50`.
+    pub fn item_limit(&mut self, limit: usize) -> &mut Self {
+        self.item_limit = limit;
+        self
+    }
+
+    /// Sets the most folder windows that can be opened at once.
+    ///
+    /// Items in different folders are shown in a window for each folder, so showing a
+    /// [`Selection`] whose items are in more folders than this fails with
+    /// [`Error::TooManyItems`] before anything is shown. Defaults to `10`.
+    pub fn window_limit(&mut self, limit: usize) -> &mut Self {
+        self.window_limit = limit;
+        self
+    }
+
+    /// Sets how paths are converted into the URIs passed to the file manager, and URIs back into
+    /// paths.
+    ///
+    /// On Linux, `codec` encodes the paths passed to the FileManager1 interface, the XDG desktop
+    /// portal and `xdg-open`. On all platforms, it decodes the URIs that are shown as local paths,
+    /// such as on Windows and macOS, where the system only takes paths, and the URIs checked
+    /// against the [`policy`](Self::policy). Everything else, such as the fallbacks and the
+    /// checks, is the same as with the default codec, which uses standard `file:` URIs, see
+    /// [`FileUri`](crate::FileUri).
+    pub fn uri_codec(&mut self, codec: impl UriCodec + 'static) -> &mut Self {
+        self.uri_codec = Some(SharedCodec(Arc::new(codec)));
+        self
+    }
+
+    /// Returns the codec set with [`uri_codec`](Self::uri_codec), or the default one.
+    pub(crate) fn codec(&self) -> &dyn UriCodec {
+        match &self.uri_codec {
+            Some(SharedCodec(codec)) => &**codec,
+            None => &DefaultCodec,
+        }
+    }
+
+    /// Sets the activation token to pass to the file manager so that it is allowed to take focus.
+    ///
+    /// This is for applications that already got a token from their toolkit, such as an
+    /// `xdg_activation_v1` token on Wayland or a startup notification id on X11, and takes
+    /// precedence over the one requested for [`parent_window`](Self::parent_window). It is passed
+    /// as the startup id of the FileManager1 call, and as `XDG_ACTIVATION_TOKEN` and
+    /// `DESKTOP_STARTUP_ID` to `xdg-open` when falling back to it. Ignored if
+    /// [`activate`](Self::activate) is `false`. Only used on Linux.
+    pub fn activation_token(&mut self, token: impl Into<String>) -> &mut Self {
+        self.activation_token = Some(token.into());
+        self
+    }
+
+    /// Sets the application window the file is shown on behalf of.
+    ///
+    /// On Windows, any dialogs Explorer shows while finding the file, such as a prompt for network
+    /// credentials, are owned by this window so that they stay in front of it. On Linux, an
+    /// activation token is requested for this window and passed to the file manager, so that it
+    /// is allowed to take focus: on X11, this carries the time of the last user interaction with
+    /// the window, and on Wayland, it is requested with `xdg_activation_v1`, which also needs the
+    /// display set with [`parent_display`](Self::parent_display). Other handles are ignored.
+    /// Requires the `raw-window-handle` feature.
+    #[cfg(feature = "raw-window-handle")]
+    pub fn parent_window(&mut self, handle: raw_window_handle::RawWindowHandle) -> &mut Self {
+        self.parent.window = Some(handle);
+        self
+    }
+
+    /// Sets the display connection the window set with [`parent_window`](Self::parent_window) is
+    /// on.
+    ///
+    /// This is only needed on Wayland, where the activation token has to be requested on the
+    /// application's own connection. Requires the `raw-window-handle` feature.
+    ///
+    /// # Safety
+    ///
+    /// The display, and the surface set with [`parent_window`](Self::parent_window), must stay
+    /// valid for as long as files are shown with these options.
+    #[cfg(feature = "raw-window-handle")]
+    pub unsafe fn parent_display(
+        &mut self,
+        handle: raw_window_handle::RawDisplayHandle,
+    ) -> &mut Self {
+        self.parent.display = Some(handle);
+        self
+    }
+
+    /// Sets whether the file manager window should appear on the same monitor as the window set
+    /// with [`parent_window`](Self::parent_window).
+    ///
+    /// This is a hint. On Windows, the Explorer window is looked for after showing the items, for
+    /// up to two seconds, and moved to the monitor of the parent window if it is on another one,
+    /// keeping its size and its position on the monitor. On Wayland, compositors place windows
+    /// near the surface the activation token was requested for, which is already done unless
+    /// [`activate`](Self::activate) is `false`, so this changes nothing. Not used on other
+    /// platforms. Requires the `raw-window-handle` feature. Defaults to `false`.
+    #[cfg(feature = "raw-window-handle")]
+    pub fn same_monitor(&mut self, same_monitor: bool) -> &mut Self {
+        self.parent.same_monitor = same_monitor;
+        self
+    }
+
+    /// Tries to show `path` in a file manager.
+    ///
+    /// See [`show_path_in_file_manager`](crate::show_path_in_file_manager) for details.
+    pub fn show_path(&self, path: impl AsRef<Path>) -> Result<ShowOutcome, Error> {
+        let path = path.as_ref().to_owned();
+        self.call(move |options| {
+            options.check_path(&path)?;
+            backend::show(
+                || Selection::from_iter([&path]),
+                options,
+                || platform::show_path(&path, options),
+            )
+        })
+    }
+
+    /// Tries to show `uri` in a file manager.
+    ///
+    /// See [`show_uri_in_file_manager`](crate::show_uri_in_file_manager) for details.
+    pub fn show_uri(&self, uri: impl AsRef<str>) -> Result<ShowOutcome, Error> {
+        let uri = uri.as_ref().to_owned();
+        self.call(move |options| {
+            options.check_uri(&uri)?;
+            backend::show(
+                || {
+                    let mut selection = Selection::new();
+                    selection.uri(&uri);
+                    selection
+                },
+                options,
+                || platform::show_uri(&uri, options),
+            )
+        })
+    }
+
+    /// Tries to show a file picked in an `rfd` dialog in a file manager.
+    ///
+    /// See [`show_file_handle_in_file_manager`](crate::show_file_handle_in_file_manager) for
+    /// details.
+    #[cfg(feature = "rfd")]
+    pub fn show_file_handle(&self, handle: &rfd::FileHandle) -> Result<ShowOutcome, Error> {
+        self.show_path(handle.path())
+    }
+
+    /// Tries to show the running executable in a file manager.
+    ///
+    /// See [`show_current_exe`](crate::show_current_exe) for details.
+    pub fn show_current_exe(&self) -> Result<ShowOutcome, Error> {
+        self.show_path(dir::current_exe()?)
+    }
+
+    /// Tries to show the working directory of the process in a file manager.
+    ///
+    /// See [`show_current_dir`](crate::show_current_dir) for details.
+    pub fn show_current_dir(&self) -> Result<ShowOutcome, Error> {
+        self.show_path(dir::current_dir()?)
+    }
+
+    /// Tries to show the most recently modified file in `dir` in a file manager.
+    ///
+    /// See [`show_latest_in_dir`](crate::show_latest_in_dir) for details.
+    pub fn show_latest_in_dir(&self, dir: impl AsRef<Path>) -> Result<ShowOutcome, Error> {
+        let path = threads::blocking(|| dir::latest_file(dir.as_ref()))?;
+        self.show_path(path)
+    }
+
+    /// Tries to show the items in `dir` whose names match the glob `pattern` in a file manager.
+    ///
+    /// See [`show_matching`](crate::show_matching) for details.
+    pub fn show_matching(
+        &self,
+        dir: impl AsRef<Path>,
+        pattern: &str,
+    ) -> Result<ShowOutcome, Error> {
+        let limit = self.match_limit.min(self.item_limit);
+        let paths = threads::blocking(|| dir::matching(dir.as_ref(), pattern, limit))?;
+        let mut selection = Selection::new();
+        for path in paths {
+            selection.path(path);
+        }
+        self.show_selection(&selection)
+    }
+
+    /// Tries to show the file manager's properties dialog for `path`.
+    ///
+    /// See [`show_properties`](crate::show_properties) for details.
+    pub fn show_properties(&self, path: impl AsRef<Path>) -> Result<ShowOutcome, Error> {
+        let path = path.as_ref().to_owned();
+        // Not recorded with the calls that show items, since it doesn't show them in a folder
+        threads::blocking(|| {
+            self.call_with_timeout(move |options| {
+                // The dialog has nothing to rename, so `rename` doesn't apply
+                options.check_path_location(&path)?;
+                platform::show_properties(&path, options)
+            })
+        })
+    }
+
+    /// Creates a [`RevealSession`] that shows items one after another in the same window, using
+    /// these options.
+    pub fn reveal_session(&self) -> RevealSession {
+        RevealSession::with_options(self)
+    }
+
+    /// Tries to show several URIs in a file manager.
+    ///
+    /// See [`show_uris_in_file_manager`](crate::show_uris_in_file_manager) for details.
+    pub fn show_uris<I>(&self, uris: I) -> Result<ShowOutcome, Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut selection = Selection::new();
+        for uri in uris {
+            selection.uri(uri.as_ref());
+        }
+        self.show_selection(&selection)
+    }
+
+    /// Tries to show a selection of paths and URIs in a file manager.
+    ///
+    /// See [`show_selection_in_file_manager`](crate::show_selection_in_file_manager) for details.
+    pub fn show_selection(&self, selection: &Selection) -> Result<ShowOutcome, Error> {
+        if selection.is_empty() {
+            return Ok(ShowOutcome::default());
+        }
+        let selection = selection.clone();
+        self.call(move |options| {
+            options.check_selection(&selection)?;
+            backend::show(
+                || selection.clone(),
+                options,
+                || platform::show_selection(&selection, options),
+            )
+        })
+    }
+
+    /// Tries to show a selection of paths and URIs with `backend` alone.
+    ///
+    /// See [`show_with_backend`](crate::show_with_backend) for details.
+    pub fn show_with_backend<B: Backend + 'static>(
+        &self,
+        backend: B,
+        selection: &Selection,
+    ) -> Result<ShowOutcome, Error> {
+        if selection.is_empty() {
+            return Ok(ShowOutcome::default());
+        }
+        let selection = selection.clone();
+        self.call(move |options| {
+            options.check_selection(&selection)?;
+            backend.show(&selection, options)
+        })
+    }
+
+    /// Checks `path` against the policy, and in quiet mode, that it can be shown without an error.
+    /// Also checks that it can be renamed once selected, if that was asked for.
+    pub(crate) fn check_path(&self, path: &Path) -> Result<(), Error> {
+        self.check_rename()?;
+        self.check_path_location(path)
+    }
+
+    /// Checks `path` against the policy, and in quiet mode, that it can be shown without an error,
+    /// leaving out [`rename`](Self::rename), which only applies to selecting it.
+    fn check_path_location(&self, path: &Path) -> Result<(), Error> {
+        self.policy.check_path(path)?;
+        if self.quiet {
+            location::check_path_quietly(path, self.automount)?;
+            self.check_file_manager()?;
+        }
+        Ok(())
+    }
+
+    /// Checks `uri` against the policy, and in quiet mode, that it can be shown without an error.
+    pub(crate) fn check_uri(&self, uri: &str) -> Result<(), Error> {
+        self.check_rename()?;
+        self.policy.check_uri(uri, self.codec())?;
+        if self.quiet {
+            location::check_uri_quietly(uri, self.automount)?;
+            self.check_file_manager()?;
+        }
+        Ok(())
+    }
+
+    /// Checks that `selection` is within the limits, every item against the policy, and in quiet
+    /// mode, that they can be shown without an error.
+    fn check_selection(&self, selection: &Selection) -> Result<(), Error> {
+        if selection.len() > self.item_limit || selection.folder_count() > self.window_limit {
+            return Err(Error::TooManyItems);
+        }
+        self.check_rename()?;
+        self.policy.check_selection(selection, self.codec())?;
+        if self.quiet {
+            for item in &selection.items {
+                match item {
+                    Item::Path(path) => location::check_path_quietly(path, self.automount)?,
+                    Item::Uri(uri) => location::check_uri_quietly(uri, self.automount)?,
+                }
+            }
+            self.check_file_manager()?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the file manager can be asked to rename the item, if that was asked for.
+    fn check_rename(&self) -> Result<(), Error> {
+        // Only Explorer takes a flag to start editing the name of the item it selects
+        if self.rename && !cfg!(windows) {
+            Err(Error::Unsupported)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks that a file manager is available, for quiet mode, where falling back to opening
+    /// the folder with another application isn't safe.
+    fn check_file_manager(&self) -> Result<(), Error> {
+        if platform::file_manager_available() {
+            Ok(())
+        } else {
+            Err(Error::NoFileManager)
+        }
+    }
+
+    /// Makes the blocking call `f` that shows items with these options, and records it.
+    pub(crate) fn call(
+        &self,
+        f: impl FnOnce(&Self) -> Result<ShowOutcome, Error> + Send + 'static,
+    ) -> Result<ShowOutcome, Error> {
+        threads::blocking(|| telemetry::record(|| self.call_with_timeout(f)))
+    }
+
+    /// Makes the call `f` with these options, on a thread of its own that is given up on after the
+    /// timeout if one is set.
+    fn call_with_timeout(
+        &self,
+        f: impl FnOnce(&Self) -> Result<ShowOutcome, Error> + Send + 'static,
+    ) -> Result<ShowOutcome, Error> {
+        match self.timeout {
+            Some(timeout) => {
+                let options = self.clone();
+                threads::with_deadline(timeout, move || f(&options))
+            }
+            None => f(self),
+        }
+    }
+}
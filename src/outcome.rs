@@ -0,0 +1,258 @@
+use std::{borrow::Cow, fmt, process::Command};
+
+use crate::Error;
+
+/// Details about a file that was successfully shown in the file manager.
+///
+/// Returned by the methods on [`ShowOptions`](crate::ShowOptions). Most fields are best-effort and
+/// only filled in when requested, so check for `None` before relying on them.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct ShowOutcome {
+    /// Whether a file manager window likely appeared.
+    ///
+    /// Only set if [`ShowOptions::verify`](crate::ShowOptions::verify) was enabled and the backend
+    /// that was used can tell, otherwise `None`.
+    pub window_appeared: Option<bool>,
+    /// The application that showed the file.
+    ///
+    /// Only set if [`ShowOptions::identify_handler`](crate::ShowOptions::identify_handler) was
+    /// enabled and the application could be determined, otherwise `None`.
+    pub handler: Option<Handler>,
+    /// The result for each item, in order, when showing a [`Selection`](crate::Selection) or
+    /// several URIs.
+    ///
+    /// Items that don't exist or couldn't be converted are skipped while the rest are still shown,
+    /// and their error is reported here. If no item could be shown, the whole call fails instead.
+    /// Empty when showing a single path or URI.
+    pub items: Vec<Result<(), Error>>,
+    /// The calls that were made to show the items, in order.
+    ///
+    /// This is the D-Bus method call, command line or platform API call that was issued, meant to
+    /// be included in bug reports. Calls that only gathered information aren't listed, and neither
+    /// are ones that failed before a fallback was used.
+    pub actions: Vec<Action>,
+    /// Ways in which the items were shown differently than asked for, such as in a folder without
+    /// being selected.
+    ///
+    /// Applications can use this to tell the user why a file isn't highlighted. Empty when the
+    /// items were shown as requested, as far as the crate can tell.
+    pub warnings: Vec<Warning>,
+}
+
+/// A way in which items were shown differently than asked for, as reported in
+/// [`ShowOutcome::warnings`].
+///
+/// The [`Display`](fmt::Display) implementation gives a short explanation for the user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Warning {
+    /// The folders containing the items were opened, but the items aren't selected in them.
+    ///
+    /// This happens when the file manager can only open folders, such as a Linux file manager that
+    /// only implements `ShowFolders`, the ChromeOS Files app, the Orbital file manager on Redox OS,
+    /// or a macOS file viewer that doesn't support AppleScript. On Windows, this happens when a
+    /// shell extension makes `SHOpenFolderAndSelectItems` fail, and the folders are opened with
+    /// `ShellExecuteEx` instead.
+    NotSelected,
+    /// No file manager could be reached, so the folders were opened with `xdg-open` instead,
+    /// which doesn't select the items. Only on Linux.
+    Fallback,
+    /// The items were shown one at a time, so only the last one is selected. Happens on macOS when
+    /// another file viewer than Finder is set, or when showing items without activating Finder.
+    ShownSeparately,
+}
+
+impl Warning {
+    /// Returns the name of this warning in snake case, such as `not_selected`, for
+    /// machine-readable output.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::NotSelected => "not_selected",
+            Self::Fallback => "fallback",
+            Self::ShownSeparately => "shown_separately",
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::NotSelected => "the file manager opened the folder but can't select files",
+            Self::Fallback => "no file manager was found, so the folder was opened instead",
+            Self::ShownSeparately => "the file manager can only select one file at a time",
+        })
+    }
+}
+
+/// A call made to show files, as reported in [`ShowOutcome::actions`].
+///
+/// The [`Display`](fmt::Display) implementation formats it on one line, similar to how it would be
+/// written for `busctl`, a shell, or in C.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Action {
+    /// A D-Bus method call, on Linux.
+    MethodCall {
+        /// The bus name the call was sent to.
+        destination: String,
+        /// The object path.
+        path: String,
+        /// The interface the method belongs to.
+        interface: String,
+        /// The method name.
+        method: String,
+        /// The arguments, each formatted as a quoted string, an array of them, or a boolean.
+        args: Vec<String>,
+    },
+    /// A program that was started.
+    Command {
+        /// The program, as it was looked up.
+        program: String,
+        /// The arguments passed to the program.
+        args: Vec<String>,
+    },
+    /// A call to a platform API, such as `SHOpenFolderAndSelectItems` on Windows.
+    Call {
+        /// The name of the function or method.
+        function: String,
+        /// The items passed to it, as paths or URIs.
+        args: Vec<String>,
+    },
+}
+
+impl Action {
+    /// Describes running `cmd`, replacing anything that isn't valid UTF-8.
+    #[cfg_attr(any(unsupported, windows), allow(dead_code))]
+    pub(crate) fn command(cmd: &Command) -> Self {
+        Self::Command {
+            program: cmd.get_program().to_string_lossy().into_owned(),
+            args: cmd
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+        }
+    }
+
+    /// Describes calling `function` with `args`.
+    #[cfg_attr(
+        not(any(target_os = "macos", target_abi = "macabi", windows)),
+        allow(dead_code)
+    )]
+    pub(crate) fn call<I>(function: &str, args: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self::Call {
+            function: function.to_owned(),
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Quotes `arg` for a POSIX shell if it contains anything special.
+fn shell_quote(arg: &str) -> Cow<'_, str> {
+    let plain = !arg.is_empty()
+        && arg.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || matches!(c, '-' | '_' | '.' | '/' | ':' | '=' | ',' | '+' | '@' | '%')
+        });
+    if plain {
+        arg.into()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''")).into()
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MethodCall {
+                destination,
+                path,
+                interface,
+                method,
+                args,
+            } => {
+                write!(f, "{destination} {path} {interface}.{method}")?;
+                for arg in args {
+                    write!(f, " {arg}")?;
+                }
+                Ok(())
+            }
+            Self::Command { program, args } => {
+                f.write_str(&shell_quote(program))?;
+                for arg in args {
+                    write!(f, " {}", shell_quote(arg))?;
+                }
+                Ok(())
+            }
+            Self::Call { function, args } => write!(f, "{function}({})", args.join(", ")),
+        }
+    }
+}
+
+/// The application that handled a request to show files.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Handler {
+    /// Identifies the application.
+    ///
+    /// This is the executable name on Windows, such as `explorer.exe`, and the bundle identifier
+    /// on macOS, such as `com.apple.finder`. On Linux, this is the desktop file ID if one was
+    /// found, otherwise the executable name or D-Bus name of the process.
+    pub id: String,
+    /// The unique name of the D-Bus connection that handled the call, such as `:1.42`. Only set on
+    /// Linux.
+    pub bus_name: Option<String>,
+    /// The desktop file ID of the application, such as `org.gnome.Nautilus.desktop`. Only set on
+    /// Linux, when a matching desktop entry was found.
+    pub desktop_entry: Option<String>,
+}
+
+impl Handler {
+    /// Creates a handler that is only known by its identifier.
+    #[cfg_attr(
+        not(any(target_os = "macos", target_abi = "macabi", windows)),
+        allow(dead_code)
+    )]
+    pub(crate) fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            bus_name: None,
+            desktop_entry: None,
+        }
+    }
+}
+
+impl ShowOutcome {
+    /// Returns the name of the file manager interface, program or function that showed the items,
+    /// such as `org.freedesktop.FileManager1`, `xdg-open` or `SHOpenFolderAndSelectItems`.
+    ///
+    /// This is taken from the last of the [`actions`](Self::actions), since earlier ones such as
+    /// mounting a volume only prepare for it. Returns `None` if there are no actions.
+    pub fn backend(&self) -> Option<&str> {
+        Some(match self.actions.last()? {
+            Action::MethodCall { destination, .. } => destination,
+            Action::Command { program, .. } => std::path::Path::new(program)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(program),
+            Action::Call { function, .. } => function,
+        })
+    }
+
+    /// Creates an outcome for a backend that can only tell whether the request was delivered,
+    /// with `actions`.
+    #[cfg(freedesktop)]
+    pub(crate) fn delivered(options: &crate::ShowOptions, actions: Vec<Action>) -> Self {
+        Self {
+            window_appeared: options.verify.then_some(true),
+            handler: None,
+            items: Vec::new(),
+            actions,
+            warnings: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Restricting which items can be shown, for applications that show paths they don't control.
+
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{selection::Item, Error, Selection, UriCodec};
+
+/// Which items may be shown, as set with [`ShowOptions::policy`](crate::ShowOptions::policy).
+///
+/// The default policy allows everything. With a policy made by [`allow_under`](Self::allow_under),
+/// every path and URI is checked before anything is passed to the system, and the call fails with
+/// [`Error::PolicyViolation`] if any of them is outside of the allowed folders.
+///
+/// ```no_run
+/// use showfile::{Error, ShowOptions, ShowPolicy};
+///
+/// let result = ShowOptions::new()
+///     .policy(ShowPolicy::allow_under(&["/home/alice/project"]))
+///     .show_path("/home/alice/project/../.ssh/id_ed25519");
+/// assert!(matches!(result, Err(Error::PolicyViolation)));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ShowPolicy {
+    roots: Option<Vec<PathBuf>>,
+}
+
+impl ShowPolicy {
+    /// Creates a policy that only allows the folders in `roots` and the items inside of them.
+    ///
+    /// Paths are compared after resolving symbolic links and `..` components, on both sides, so
+    /// that neither can lead outside of a root. Roots that don't exist allow nothing. URIs are only
+    /// allowed if they are `file:` URIs of allowed paths, or URIs that the
+    /// [`UriCodec`] set with [`ShowOptions::uri_codec`](crate::ShowOptions::uri_codec) turns into
+    /// allowed paths, since other locations can't be inside of a local folder.
+    pub fn allow_under<P: AsRef<Path>>(roots: &[P]) -> Self {
+        Self {
+            roots: Some(roots.iter().map(|root| root.as_ref().to_owned()).collect()),
+        }
+    }
+
+    pub(crate) fn check_path(&self, path: &Path) -> Result<(), Error> {
+        let Some(roots) = &self.roots else {
+            return Ok(());
+        };
+        let allowed = resolve(path).is_some_and(|path| {
+            roots
+                .iter()
+                .filter_map(|root| fs::canonicalize(root).ok())
+                .any(|root| path.starts_with(root))
+        });
+        if allowed {
+            Ok(())
+        } else {
+            Err(Error::PolicyViolation)
+        }
+    }
+
+    /// Checks `uri` against the policy, with `codec` finding the local path it refers to.
+    pub(crate) fn check_uri(&self, uri: &str, codec: &dyn UriCodec) -> Result<(), Error> {
+        if self.roots.is_none() {
+            return Ok(());
+        }
+        match codec.decode(uri) {
+            Some(path) => self.check_path(&path),
+            None => Err(Error::PolicyViolation),
+        }
+    }
+
+    pub(crate) fn check_selection(
+        &self,
+        selection: &Selection,
+        codec: &dyn UriCodec,
+    ) -> Result<(), Error> {
+        selection.items.iter().try_for_each(|item| match item {
+            Item::Path(path) => self.check_path(path),
+            Item::Uri(uri) => self.check_uri(uri, codec),
+        })
+    }
+}
+
+/// Resolves symbolic links and `..` components in `path`, as far as it exists.
+///
+/// The part that doesn't exist is appended as is, unless it goes up with `..`, since where that
+/// leads depends on what gets created later.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    let mut resolved = loop {
+        if let Ok(resolved) = fs::canonicalize(existing) {
+            break resolved;
+        }
+        let mut components = existing.components();
+        match components.next_back()? {
+            Component::Normal(name) => missing.push(name),
+            Component::CurDir => {}
+            _ => return None,
+        }
+        // What's left of a relative path is in the working directory
+        existing = match components.as_path() {
+            rest if !rest.as_os_str().is_empty() => rest,
+            _ if existing != Path::new(".") => Path::new("."),
+            _ => return None,
+        };
+    };
+    resolved.extend(missing.into_iter().rev());
+    Some(resolved)
+}
@@ -0,0 +1,92 @@
+use std::{
+    ffi::{OsStr, OsString},
+    process::Command,
+    sync::Mutex,
+};
+
+/// Changes to the environment of the programs this crate runs.
+///
+/// When no file manager can be called directly, the crate runs programs such as `xdg-open`,
+/// `dolphin` or `open`, which inherit the environment of the application. Applications that run
+/// with a modified `PATH`, locale or library path can add, remove or clear variables for these
+/// programs. The changes apply to programs started after [`apply`](Self::apply) is called.
+///
+/// When the application runs from an AppImage on Linux, the entries that point into the image are
+/// already removed from variables such as `LD_LIBRARY_PATH`, `LD_PRELOAD` and `XDG_DATA_DIRS`,
+/// which would have the programs load the bundled libraries. Variables set here are set after
+/// that, so they can put entries back.
+///
+/// ```no_run
+/// showfile::ChildEnv::new()
+///     .set("LANG", "C.UTF-8")
+///     .remove("PYTHONPATH")
+///     .apply();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ChildEnv {
+    clear: bool,
+    vars: Vec<(OsString, Option<OsString>)>,
+}
+
+/// The changes set with [`ChildEnv::apply`], if any.
+static ENV: Mutex<Option<ChildEnv>> = Mutex::new(None);
+
+impl ChildEnv {
+    /// Creates a set of changes that leaves the environment as it is.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the variable `key` to `value`.
+    pub fn set(&mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> &mut Self {
+        self.vars
+            .push((key.as_ref().to_owned(), Some(value.as_ref().to_owned())));
+        self
+    }
+
+    /// Removes the variable `key`.
+    pub fn remove(&mut self, key: impl AsRef<OsStr>) -> &mut Self {
+        self.vars.push((key.as_ref().to_owned(), None));
+        self
+    }
+
+    /// Starts from an empty environment instead of the application's, so that the programs only
+    /// see the variables that are [`set`](Self::set) afterwards and those the crate sets itself,
+    /// such as `DBUS_SESSION_BUS_ADDRESS` when showing items as another user.
+    ///
+    /// Most programs need at least `PATH`, and on Linux `DISPLAY` or `WAYLAND_DISPLAY` to open a
+    /// window.
+    pub fn clear(&mut self) -> &mut Self {
+        self.clear = true;
+        self.vars.clear();
+        self
+    }
+
+    /// Uses these changes for programs started from now on.
+    pub fn apply(&self) {
+        *ENV.lock().unwrap() = Some(self.clone());
+    }
+}
+
+/// Creates a command that runs `program` with the environment changes that were applied.
+///
+/// On Linux, the variables an AppImage points into itself are restored first. Variables set on the
+/// command afterwards take precedence.
+#[cfg_attr(any(unsupported, windows), allow(dead_code))]
+pub(crate) fn command(program: impl AsRef<OsStr>) -> Command {
+    let mut command = Command::new(program);
+    #[cfg(freedesktop)]
+    crate::linux::appimage::sanitize(&mut command);
+    if let Some(env) = &*ENV.lock().unwrap() {
+        if env.clear {
+            command.env_clear();
+        }
+        for (key, value) in &env.vars {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
+    }
+    command
+}
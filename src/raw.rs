@@ -0,0 +1,23 @@
+//! The platform calls that the rest of the crate is built on, for applications that need to put
+//! them together differently.
+//!
+//! Each platform has its own items, and nothing here falls back to another way of showing items,
+//! checks them against [`ShowOptions`](crate::ShowOptions), or reports them to telemetry:
+//!
+//! - Linux, the BSDs, illumos and Solaris: `FileManager1Call`, which builds the calls of the
+//!   `org.freedesktop.FileManager1` interface, such as `ShowItems`, and sends them with the
+//!   D-Bus crate this crate was built with
+//! - Windows: `ItemIdList`, which parses paths into item ID lists the way this crate does, and
+//!   `open_folder_and_select_items`, which wraps `SHOpenFolderAndSelectItems`
+//! - macOS: `activate_file_viewer_selecting`, which calls
+//!   `NSWorkspace activateFileViewerSelectingURLs:` on the queue this crate makes its AppKit calls
+//!   on
+//!
+//! There is nothing here on other platforms.
+
+#[cfg(freedesktop)]
+pub use crate::linux::raw::*;
+#[cfg(any(target_os = "macos", target_abi = "macabi"))]
+pub use crate::macos::raw::*;
+#[cfg(windows)]
+pub use crate::win32::raw::*;
@@ -0,0 +1,191 @@
+//! Redox OS, where the Orbital file manager and launcher from orbutils are started directly.
+//!
+//! The file manager takes the folder to open as its only argument and can't select files, so the
+//! containing folders are opened instead.
+
+use std::{
+    io, iter,
+    path::{Path, PathBuf},
+    process::Child,
+};
+
+use crate::{
+    args, process, selection::Item, threads, Action, BackendInfo, Capabilities, Diagnostics, Error,
+    ErrorKind, LocationStatus, Portal, SandboxKind, Selection, ShowOptions, ShowOutcome, Warning,
+};
+
+const FILE_MANAGER: &str = "file_manager";
+const LAUNCHER: &str = "launcher";
+
+/// Starts `program` with `arg`, waiting for it to exit in the background.
+fn spawn(program: &str, arg: &Path) -> Result<Action, Error> {
+    let mut cmd = process::command(program);
+    let child = cmd.arg(args::path(arg)).spawn().map_err(|e| {
+        if e.kind() == io::ErrorKind::NotFound {
+            Error::NoApplication
+        } else {
+            Error::backend(e)
+        }
+    })?;
+    reap(child);
+    Ok(Action::command(&cmd))
+}
+
+/// Waits for `child` on another thread if one can be started, so that it doesn't become a zombie.
+fn reap(mut child: Child) {
+    let _ = threads::spawn("reaper", false, move || child.wait());
+}
+
+/// Returns the folder containing `path`, or `path` itself for the root.
+fn parent_folder(path: &Path) -> Result<PathBuf, Error> {
+    if !path.is_absolute() {
+        return Err(Error::InvalidPath);
+    }
+    Ok(path.parent().unwrap_or(path).to_owned())
+}
+
+fn uri_to_path(uri: &str, options: &ShowOptions) -> Result<PathBuf, Error> {
+    options.codec().decode(uri).ok_or(Error::InvalidPath)
+}
+
+/// Opens `folder` in the file manager.
+fn open_folder(folder: &Path) -> Result<Action, Error> {
+    spawn(FILE_MANAGER, folder).map_err(|e| match e {
+        Error::NoApplication => Error::NoFileManager,
+        e => e,
+    })
+}
+
+pub(crate) fn show_path(path: &Path, _options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    Ok(ShowOutcome {
+        actions: vec![open_folder(&parent_folder(path)?)?],
+        warnings: vec![Warning::NotSelected],
+        ..ShowOutcome::default()
+    })
+}
+
+pub(crate) fn show_uri(uri: &str, options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    show_path(&uri_to_path(uri, options)?, options)
+}
+
+/// The state of a [`RevealSession`](crate::RevealSession), which is empty since the file manager
+/// is started again for each item.
+#[derive(Debug, Default)]
+pub(crate) struct RevealState;
+
+pub(crate) fn reveal_path(
+    _state: &mut RevealState,
+    path: &Path,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    show_path(path, options)
+}
+
+pub(crate) fn reveal_uri(
+    _state: &mut RevealState,
+    uri: &str,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    show_uri(uri, options)
+}
+
+pub(crate) fn show_selection(
+    selection: &Selection,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    let (folders, mut results) = selection.prepare(|item| match item {
+        Item::Path(path) => parent_folder(path),
+        Item::Uri(uri) => parent_folder(&uri_to_path(uri, options)?),
+    })?;
+    // Open each folder once, however many of the items are in it
+    let mut grouped: Vec<(PathBuf, Vec<usize>)> = Vec::new();
+    for (i, folder) in folders {
+        match grouped.iter_mut().find(|(grouped, _)| *grouped == folder) {
+            Some((_, indices)) => indices.push(i),
+            None => grouped.push((folder, vec![i])),
+        }
+    }
+    let mut actions = Vec::with_capacity(grouped.len());
+    for (folder, indices) in grouped {
+        match open_folder(&folder) {
+            Ok(action) => actions.push(action),
+            Err(e) => {
+                // Errors can't be cloned, so the other items get a copy of the message
+                let message = crate::error::report(&e);
+                let mut errors =
+                    iter::once(e).chain(iter::repeat_with(|| Error::backend(message.clone())));
+                for i in indices {
+                    results.fail(i, errors.next().unwrap());
+                }
+            }
+        }
+    }
+    Ok(ShowOutcome {
+        items: results.finish()?,
+        actions,
+        warnings: vec![Warning::NotSelected],
+        ..ShowOutcome::default()
+    })
+}
+
+pub(crate) fn open_path(path: &Path) -> Result<(), Error> {
+    if !path.is_absolute() {
+        return Err(Error::InvalidPath);
+    }
+    spawn(LAUNCHER, path).map(drop)
+}
+
+pub(crate) fn open_path_with(app: &str, path: &Path) -> Result<(), Error> {
+    if !path.is_absolute() {
+        return Err(Error::InvalidPath);
+    }
+    spawn(app, path).map(drop)
+}
+
+pub(crate) fn show_properties(_path: &Path, _options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    Err(Error::Unsupported)
+}
+
+pub(crate) fn backend_error_kind(_err: &(dyn std::error::Error + 'static)) -> Option<ErrorKind> {
+    None
+}
+
+pub(crate) fn diagnose(_diagnostics: &mut Diagnostics) {}
+
+pub(crate) fn backends() -> Vec<BackendInfo> {
+    vec![BackendInfo::new(FILE_MANAGER, None)]
+}
+
+pub(crate) fn capabilities() -> Capabilities {
+    Capabilities {
+        open_directory: true,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn supports_uri_scheme(_scheme: &str) -> bool {
+    false
+}
+
+pub(crate) fn path_location_status(_path: &Path) -> LocationStatus {
+    LocationStatus::Local
+}
+
+pub(crate) fn uri_location_status(_uri: &str) -> LocationStatus {
+    // Nothing mounts remote locations for the file manager to browse
+    LocationStatus::NetworkUnmounted
+}
+
+pub(crate) fn sandbox_kind() -> Option<SandboxKind> {
+    None
+}
+
+pub(crate) fn portals() -> Result<Vec<Portal>, Error> {
+    Ok(Vec::new())
+}
+
+pub(crate) fn file_manager_available() -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| dir.join(FILE_MANAGER).is_file())
+    })
+}
@@ -0,0 +1,22 @@
+/// A kind of application sandbox.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SandboxKind {
+    /// A [Flatpak](https://flatpak.org/) sandbox on Linux.
+    Flatpak,
+    /// A [Snap](https://snapcraft.io/) confinement on Linux.
+    Snap,
+    /// The macOS App Sandbox.
+    AppSandbox,
+}
+
+/// An [XDG desktop portal](https://flatpak.github.io/xdg-desktop-portal/) interface available on
+/// the session bus.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Portal {
+    /// The D-Bus interface name, such as `org.freedesktop.portal.OpenURI`.
+    pub interface: String,
+    /// The version of the interface, from its `version` property.
+    pub version: u32,
+}
@@ -0,0 +1,272 @@
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{location, Error, FileUri};
+
+/// An item in a [`Selection`].
+#[derive(Clone, Debug)]
+pub(crate) enum Item {
+    Path(PathBuf),
+    Uri(String),
+}
+
+impl Item {
+    /// Returns the folder containing the item, as a path or the start of a URI.
+    fn parent(&self) -> &OsStr {
+        match self {
+            Self::Path(path) => path.parent().unwrap_or(path).as_os_str(),
+            Self::Uri(uri) => {
+                let uri = uri.trim_end_matches('/');
+                OsStr::new(uri.rsplit_once('/').map_or(uri, |(parent, _)| parent))
+            }
+        }
+    }
+
+    /// Checks that the item exists, if it is a local file that isn't inside an archive the file
+    /// manager can browse.
+    #[cfg_attr(unsupported, allow(dead_code))]
+    fn check_exists(&self) -> Result<(), Error> {
+        let uri_path;
+        let path = match self {
+            Self::Path(path) => path,
+            Self::Uri(uri) => match FileUri::parse(uri).ok().and_then(|uri| uri.to_path()) {
+                Some(path) => {
+                    uri_path = path;
+                    &uri_path
+                }
+                None => return Ok(()),
+            },
+        };
+        match path.symlink_metadata() {
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !location::is_archive_entry(path) => {
+                Err(Error::NotFound)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// An item of a [`Selection`], as returned by [`Selection::iter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SelectedItem<'a> {
+    /// A native path.
+    Path(&'a Path),
+    /// A URI.
+    Uri(&'a str),
+}
+
+/// A set of files to show together, given as native paths, URIs, or a mix of both.
+///
+/// Each item is converted to the form the platform's file manager understands before showing
+/// them, so local files and network URIs can be shown in one call.
+///
+/// ```no_run
+/// let mut selection = showfile::Selection::new();
+/// selection
+///     .path("/home/alice/hello.txt")
+///     .uri("sftp://example.com/home/alice/world.txt");
+/// showfile::show_selection_in_file_manager(&selection)?;
+/// # Ok::<(), showfile::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Selection {
+    pub(crate) items: Vec<Item>,
+}
+
+impl Selection {
+    /// Creates an empty selection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a path to the selection.
+    pub fn path(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.items.push(Item::Path(path.into()));
+        self
+    }
+
+    /// Adds the file or folder a directory entry refers to, such as one from
+    /// [`std::fs::read_dir`], to the selection.
+    pub fn entry(&mut self, entry: &fs::DirEntry) -> &mut Self {
+        self.path(entry.path())
+    }
+
+    /// Adds a file picked in an `rfd` dialog to the selection.
+    ///
+    /// Requires the `rfd` feature. The files picked in one dialog can be added at once with
+    /// `selection.extend(files.iter().map(rfd::FileHandle::path))`.
+    #[cfg(feature = "rfd")]
+    pub fn file_handle(&mut self, handle: &rfd::FileHandle) -> &mut Self {
+        self.path(handle.path())
+    }
+
+    /// Adds a URI to the selection.
+    pub fn uri(&mut self, uri: impl Into<String>) -> &mut Self {
+        self.items.push(Item::Uri(uri.into()));
+        self
+    }
+
+    /// Returns the number of items in the selection.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Checks whether the selection has no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the items of the selection, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = SelectedItem<'_>> {
+        self.items.iter().map(|item| match item {
+            Item::Path(path) => SelectedItem::Path(path),
+            Item::Uri(uri) => SelectedItem::Uri(uri),
+        })
+    }
+
+    /// Splits the selection into one selection for each folder that contains its items, in the
+    /// order the folders first appear.
+    ///
+    /// This is for showing the results of a recursive walk, such as from the `walkdir` crate, one
+    /// folder at a time instead of opening a window for every folder at once. URIs are grouped by
+    /// the part before their last `/`.
+    ///
+    /// ```no_run
+    /// let mut selection = showfile::Selection::new();
+    /// for dir in ["target/debug", "target/release"] {
+    ///     for entry in std::fs::read_dir(dir)? {
+    ///         selection.entry(&entry?);
+    ///     }
+    /// }
+    /// for batch in selection.by_parent() {
+    ///     showfile::show_selection_in_file_manager(&batch)?;
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn by_parent(&self) -> Vec<Selection> {
+        let mut batches: Vec<(&OsStr, Selection)> = Vec::new();
+        for item in &self.items {
+            let parent = item.parent();
+            match batches.iter_mut().find(|(p, _)| *p == parent) {
+                Some((_, batch)) => batch.items.push(item.clone()),
+                None => batches.push((
+                    parent,
+                    Selection {
+                        items: vec![item.clone()],
+                    },
+                )),
+            }
+        }
+        batches.into_iter().map(|(_, batch)| batch).collect()
+    }
+
+    /// Returns the number of folders that contain the items, which is how many windows showing
+    /// them opens.
+    pub(crate) fn folder_count(&self) -> usize {
+        self.items
+            .iter()
+            .map(Item::parent)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Prepares each item to be shown with `prepare`, skipping items that are missing or fail.
+    ///
+    /// Returns the prepared items with their index in the selection, along with a result for each
+    /// item. If no item could be prepared, the error for the first one is returned instead.
+    #[cfg_attr(unsupported, allow(dead_code))]
+    pub(crate) fn prepare<'a, T>(
+        &'a self,
+        mut prepare: impl FnMut(&'a Item) -> Result<T, Error>,
+    ) -> Result<(Vec<(usize, T)>, ItemResults), Error> {
+        let mut prepared = Vec::with_capacity(self.items.len());
+        let results = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let value = item.check_exists().and_then(|()| prepare(item))?;
+                prepared.push((i, value));
+                Ok(())
+            })
+            .collect::<Vec<_>>();
+        let results = ItemResults(results);
+        if prepared.is_empty() {
+            return Err(results.finish().err().unwrap_or(Error::InvalidPath));
+        }
+        Ok((prepared, results))
+    }
+}
+
+/// The result for each item of a selection being shown.
+#[derive(Debug)]
+#[cfg_attr(unsupported, allow(dead_code))]
+pub(crate) struct ItemResults(Vec<Result<(), Error>>);
+
+#[cfg_attr(unsupported, allow(dead_code))]
+impl ItemResults {
+    /// Records that the item at `index` could not be shown.
+    #[cfg_attr(freedesktop, allow(dead_code))]
+    pub(crate) fn fail(&mut self, index: usize, err: Error) {
+        self.0[index] = Err(err);
+    }
+
+    /// Returns the results, or the first error if every item failed.
+    pub(crate) fn finish(self) -> Result<Vec<Result<(), Error>>, Error> {
+        let mut results = self.0;
+        if results.iter().all(Result::is_err) {
+            if let Some(Err(err)) = results.drain(..).next() {
+                return Err(err);
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// Adds paths to the selection.
+impl<P: AsRef<Path>> Extend<P> for Selection {
+    fn extend<T: IntoIterator<Item = P>>(&mut self, iter: T) {
+        self.items
+            .extend(iter.into_iter().map(|p| Item::Path(p.as_ref().to_owned())));
+    }
+}
+
+/// Collects paths into a selection, such as `walkdir` entries mapped with `DirEntry::into_path`.
+impl<P: AsRef<Path>> FromIterator<P> for Selection {
+    fn from_iter<T: IntoIterator<Item = P>>(iter: T) -> Self {
+        let mut selection = Self::new();
+        selection.extend(iter);
+        selection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_entry() {
+        let dir = std::env::temp_dir().join(format!("showfile-archive-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.zip");
+        // An empty ZIP archive, which is only its end of central directory record
+        let mut empty = b"PK\x05\x06".to_vec();
+        empty.resize(22, 0);
+        fs::write(&archive, empty).unwrap();
+        let mut selection = Selection::new();
+        selection
+            .path(archive.join("inner").join("file.txt"))
+            .path(dir.join("missing").join("file.txt"));
+        let res = selection.prepare(|_| Ok(()));
+        let _ = fs::remove_dir_all(&dir);
+        let (prepared, results) = res.unwrap();
+        assert_eq!(prepared.iter().map(|(i, ())| *i).collect::<Vec<_>>(), [0]);
+        let results = results.finish().unwrap();
+        assert!(matches!(results[1], Err(Error::NotFound)));
+    }
+}
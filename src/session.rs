@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use crate::{backend, platform, telemetry, threads, Error, Selection, ShowOptions, ShowOutcome};
+
+/// A file manager window that items are shown in one after another.
+///
+/// The first call shows the item like [`ShowOptions::show_path`]. Later calls change the selection
+/// in that same window instead of opening new ones, which suits stepping through search results.
+/// On Windows, the Explorer window is found again with `IShellWindows` and navigated to the
+/// item's folder if needed, and a new window is only opened if it was closed. On Linux and macOS,
+/// each item is shown again with the same call, and whether the window is reused is up to the file
+/// manager.
+///
+/// ```no_run
+/// let mut session = showfile::RevealSession::new();
+/// for result in ["/home/alice/a.txt", "/home/alice/b.txt"] {
+///     session.reveal_path(result)?;
+/// }
+/// # Ok::<(), showfile::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct RevealSession {
+    options: ShowOptions,
+    state: platform::RevealState,
+}
+
+impl Default for RevealSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RevealSession {
+    /// Creates a session with the default options.
+    pub fn new() -> Self {
+        ShowOptions::new().reveal_session()
+    }
+
+    pub(crate) fn with_options(options: &ShowOptions) -> Self {
+        Self {
+            options: options.clone(),
+            state: Default::default(),
+        }
+    }
+
+    /// Shows `path` in the session's window.
+    ///
+    /// See [`show_path_in_file_manager`](crate::show_path_in_file_manager) for details.
+    pub fn reveal_path(&mut self, path: impl AsRef<Path>) -> Result<ShowOutcome, Error> {
+        threads::blocking(|| {
+            telemetry::record(|| {
+                let path = path.as_ref();
+                self.options.check_path(path)?;
+                backend::show(
+                    || Selection::from_iter([path]),
+                    &self.options,
+                    || platform::reveal_path(&mut self.state, path, &self.options),
+                )
+            })
+        })
+    }
+
+    /// Shows `uri` in the session's window.
+    ///
+    /// See [`show_uri_in_file_manager`](crate::show_uri_in_file_manager) for details.
+    pub fn reveal_uri(&mut self, uri: impl AsRef<str>) -> Result<ShowOutcome, Error> {
+        threads::blocking(|| {
+            telemetry::record(|| {
+                let uri = uri.as_ref();
+                self.options.check_uri(uri)?;
+                backend::show(
+                    || {
+                        let mut selection = Selection::new();
+                        selection.uri(uri);
+                        selection
+                    },
+                    &self.options,
+                    || platform::reveal_uri(&mut self.state, uri, &self.options),
+                )
+            })
+        })
+    }
+}
@@ -0,0 +1,93 @@
+//! Counting the calls that show items, for [`stats`](crate::stats).
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use crate::{Error, ShowOutcome, Warning};
+
+static REVEALS: AtomicU64 = AtomicU64::new(0);
+static FAILED: AtomicU64 = AtomicU64::new(0);
+static FALLBACKS: AtomicU64 = AtomicU64::new(0);
+static NOT_SELECTED: AtomicU64 = AtomicU64::new(0);
+/// The number of calls each backend succeeded for, in the order they were first used.
+static BACKENDS: Mutex<Vec<(String, u64)>> = Mutex::new(Vec::new());
+
+/// How often items were shown since the process started, as returned by
+/// [`stats`](crate::stats).
+///
+/// Every call that shows items is counted, whether through the functions at the crate root,
+/// [`ShowOptions`](crate::ShowOptions) or a [`RevealSession`](crate::RevealSession). The
+/// [`Display`](fmt::Display) implementation formats the counters on one line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// The number of calls that showed items.
+    pub reveals: u64,
+    /// The number of those calls that failed.
+    pub failed: u64,
+    /// The number of calls that succeeded with [`Warning::Fallback`], because no file manager
+    /// could be reached.
+    pub fallbacks: u64,
+    /// The number of calls that succeeded with [`Warning::NotSelected`], so that the folders were
+    /// opened without the items selected in them.
+    pub not_selected: u64,
+    /// The number of calls that each backend succeeded for, named as in
+    /// [`ShowOutcome::backend`], such as `org.freedesktop.FileManager1` or `xdg-open`, in the
+    /// order they were first used.
+    pub backends: Vec<(String, u64)>,
+}
+
+impl Stats {
+    pub(crate) fn get() -> Self {
+        Self {
+            reveals: REVEALS.load(Ordering::Relaxed),
+            failed: FAILED.load(Ordering::Relaxed),
+            fallbacks: FALLBACKS.load(Ordering::Relaxed),
+            not_selected: NOT_SELECTED.load(Ordering::Relaxed),
+            backends: BACKENDS.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// Counts a call that showed items, which returned `res`.
+pub(crate) fn record(res: &Result<ShowOutcome, Error>) {
+    REVEALS.fetch_add(1, Ordering::Relaxed);
+    let outcome = match res {
+        Ok(outcome) => outcome,
+        Err(_) => {
+            FAILED.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+    };
+    if outcome.warnings.contains(&Warning::Fallback) {
+        FALLBACKS.fetch_add(1, Ordering::Relaxed);
+    }
+    if outcome.warnings.contains(&Warning::NotSelected) {
+        NOT_SELECTED.fetch_add(1, Ordering::Relaxed);
+    }
+    let backend = outcome.backend().unwrap_or("unknown");
+    let mut backends = BACKENDS.lock().unwrap();
+    match backends.iter_mut().find(|(name, _)| name == backend) {
+        Some((_, count)) => *count += 1,
+        None => backends.push((backend.to_owned(), 1)),
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} reveals, {} failed, {} fallbacks, {} not selected",
+            self.reveals, self.failed, self.fallbacks, self.not_selected
+        )?;
+        for (backend, count) in &self.backends {
+            write!(f, ", {backend}: {count}")?;
+        }
+        Ok(())
+    }
+}
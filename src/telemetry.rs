@@ -0,0 +1,110 @@
+//! Reporting calls that show items to the application's telemetry, with the `metrics` and
+//! `opentelemetry` features, and to [`stats`](crate::stats).
+
+use crate::{stats, Error, ShowOutcome};
+
+/// Makes the call `f` that shows items, and records it.
+pub(crate) fn record(f: impl FnOnce() -> Result<ShowOutcome, Error>) -> Result<ShowOutcome, Error> {
+    #[cfg(feature = "metrics")]
+    let start = std::time::Instant::now();
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!("showfile_reveals_total").increment(1);
+    #[cfg(feature = "opentelemetry")]
+    let span = spans::start();
+    let res = f();
+    stats::record(&res);
+    #[cfg(feature = "metrics")]
+    recorder::record(&res, start.elapsed());
+    #[cfg(feature = "opentelemetry")]
+    spans::end(span, &res);
+    res
+}
+
+#[cfg(feature = "metrics")]
+mod recorder {
+    use std::{sync::Once, time::Duration};
+
+    use crate::{Error, ShowOutcome};
+
+    /// Describes the metrics to the recorder, the first time one is recorded.
+    fn describe() {
+        static DESCRIBED: Once = Once::new();
+        DESCRIBED.call_once(|| {
+            ::metrics::describe_counter!("showfile_reveals_total", "Calls to show items");
+            ::metrics::describe_counter!(
+                "showfile_reveals_succeeded_total",
+                "Calls to show items that succeeded, by backend"
+            );
+            ::metrics::describe_counter!(
+                "showfile_reveals_failed_total",
+                "Calls to show items that failed, by error kind"
+            );
+            ::metrics::describe_histogram!(
+                "showfile_reveal_duration_seconds",
+                ::metrics::Unit::Seconds,
+                "How long calls to show items took"
+            );
+        });
+    }
+
+    pub(super) fn record(res: &Result<ShowOutcome, Error>, elapsed: Duration) {
+        describe();
+        let result = match res {
+            Ok(outcome) => {
+                let backend = outcome.backend().unwrap_or("unknown").to_owned();
+                ::metrics::counter!("showfile_reveals_succeeded_total", "backend" => backend)
+                    .increment(1);
+                "ok"
+            }
+            Err(err) => {
+                let error_kind = err.kind().label();
+                ::metrics::counter!("showfile_reveals_failed_total", "error_kind" => error_kind)
+                    .increment(1);
+                "error"
+            }
+        };
+        ::metrics::histogram!("showfile_reveal_duration_seconds", "result" => result)
+            .record(elapsed);
+    }
+}
+
+#[cfg(feature = "opentelemetry")]
+mod spans {
+    use opentelemetry::{
+        global::{self, BoxedSpan},
+        trace::{Span, Status, Tracer},
+        InstrumentationScope, KeyValue,
+    };
+
+    use crate::{Error, ShowOutcome};
+
+    /// Starts the span for a call, as a child of the current context's span.
+    pub(super) fn start() -> BoxedSpan {
+        let scope = InstrumentationScope::builder("showfile")
+            .with_version(env!("CARGO_PKG_VERSION"))
+            .build();
+        global::tracer_with_scope(scope).start("showfile.reveal")
+    }
+
+    pub(super) fn end(mut span: BoxedSpan, res: &Result<ShowOutcome, Error>) {
+        span.set_attribute(KeyValue::new("showfile.platform", std::env::consts::OS));
+        match res {
+            Ok(outcome) => {
+                let backend = outcome.backend().unwrap_or("unknown").to_owned();
+                span.set_attribute(KeyValue::new("showfile.backend", backend));
+                let shown = if outcome.warnings.is_empty() {
+                    "shown"
+                } else {
+                    "degraded"
+                };
+                span.set_attribute(KeyValue::new("showfile.outcome", shown));
+            }
+            Err(err) => {
+                span.set_attribute(KeyValue::new("showfile.outcome", "error"));
+                span.set_attribute(KeyValue::new("showfile.error_kind", err.kind().label()));
+                span.set_status(Status::error(crate::error::report(err)));
+            }
+        }
+        span.end();
+    }
+}
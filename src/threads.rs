@@ -0,0 +1,307 @@
+use std::{
+    collections::VecDeque,
+    io,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::Error;
+
+/// Limits for the threads this crate starts internally.
+///
+/// Some calls are handed off to threads owned by the crate, such as the workers that run the
+/// functions in [`nonblocking`](crate::nonblocking), the threads that calls with a
+/// [`timeout`](crate::ShowOptions::timeout) are made on, the thread all shell calls are made on on
+/// Windows, and the threads that wait for applications launched by
+/// [`open_path_with`](crate::open_path_with) on Linux to exit. Applications with a strict thread
+/// budget can bound how many of these run at once and how they are named. Workers exit after
+/// they have been idle for a few seconds. On macOS, AppKit calls are queued with libdispatch,
+/// which manages its own threads. The limits apply to
+/// threads started after [`apply`](Self::apply) is called, so it should be called early.
+///
+/// ```no_run
+/// showfile::ThreadOptions::new()
+///     .max_threads(2)
+///     .name("my-plugin-showfile")
+///     .apply();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ThreadOptions {
+    max_threads: usize,
+    name: String,
+    async_calls: AsyncCallPolicy,
+}
+
+/// What to do when a blocking function is called from a thread that runs async tasks.
+///
+/// Calls such as [`show_path_in_file_manager`](crate::show_path_in_file_manager) block until the
+/// file manager has been called, which stalls every other task on the thread if it belongs to an
+/// async runtime. Only Tokio runtimes can be detected, with the `tokio` feature.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AsyncCallPolicy {
+    /// Tell the runtime that the thread is about to block, so that it moves its other tasks to
+    /// another thread, with
+    /// [`block_in_place`](https://docs.rs/tokio/latest/tokio/task/fn.block_in_place.html). This is
+    /// only possible on the multi-threaded runtime, and warns as [`Warn`](Self::Warn) otherwise.
+    #[default]
+    Offload,
+    /// Log a warning with the [`log`](https://docs.rs/log) crate the first time it happens. The
+    /// current thread runtime doesn't tell its own thread apart from the threads of
+    /// `spawn_blocking`, so the warning may also be logged for calls that are fine.
+    Warn,
+    /// Block without doing anything.
+    Ignore,
+}
+
+/// The options set with [`ThreadOptions::apply`], or the defaults.
+static OPTIONS: Mutex<Option<ThreadOptions>> = Mutex::new(None);
+/// The number of threads started by the crate that are still running.
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+impl Default for ThreadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ThreadOptions {
+    /// Creates a new set of options with default values.
+    pub fn new() -> Self {
+        Self {
+            max_threads: 4,
+            name: "showfile".to_owned(),
+            async_calls: AsyncCallPolicy::Offload,
+        }
+    }
+
+    /// Sets the maximum number of threads the crate runs at once.
+    ///
+    /// When the limit is reached, work that would have been handed off to a new thread is done
+    /// later or on the calling thread instead. The thread that shell calls are made on on Windows,
+    /// and the threads that calls with a [`timeout`](crate::ShowOptions::timeout) are made on, are
+    /// always started, and count towards the limit. Defaults to 4.
+    pub fn max_threads(&mut self, max_threads: usize) -> &mut Self {
+        self.max_threads = max_threads;
+        self
+    }
+
+    /// Sets the prefix of the names of the threads, which are followed by what the thread is
+    /// for, such as `showfile-shell`. Defaults to `showfile`.
+    pub fn name(&mut self, name: impl Into<String>) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets what to do when a blocking function is called from an async runtime. Defaults to
+    /// [`AsyncCallPolicy::Offload`].
+    pub fn async_calls(&mut self, policy: AsyncCallPolicy) -> &mut Self {
+        self.async_calls = policy;
+        self
+    }
+
+    /// Uses these options for threads started from now on.
+    pub fn apply(&self) {
+        *OPTIONS.lock().unwrap() = Some(self.clone());
+    }
+}
+
+/// Starts a thread for `role`, whose name is appended to the configured prefix.
+///
+/// Returns `None` if the maximum number of threads is already running, unless `required` is set.
+pub(crate) fn spawn<T: Send + 'static>(
+    role: &str,
+    required: bool,
+    f: impl FnOnce() -> T + Send + 'static,
+) -> io::Result<Option<JoinHandle<T>>> {
+    let options = OPTIONS.lock().unwrap().clone().unwrap_or_default();
+    let running = RUNNING.fetch_add(1, Ordering::SeqCst);
+    if running >= options.max_threads && !required {
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+        return Ok(None);
+    }
+    /// Counts the thread as stopped when it returns or panics.
+    struct Running;
+    impl Drop for Running {
+        fn drop(&mut self) {
+            RUNNING.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+    let running = Running;
+    let res = thread::Builder::new()
+        .name(format!("{}-{role}", options.name))
+        .spawn(move || {
+            let _running = running;
+            f()
+        });
+    // If the thread couldn't be started, the closure was dropped and the count already restored
+    res.map(Some)
+}
+
+/// Runs the blocking call `f`, handling being called from an async runtime as configured.
+pub(crate) fn blocking<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "tokio")]
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        use tokio::runtime::RuntimeFlavor;
+
+        let policy = match &*OPTIONS.lock().unwrap() {
+            Some(options) => options.async_calls,
+            None => AsyncCallPolicy::default(),
+        };
+        match policy {
+            AsyncCallPolicy::Offload if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                return tokio::task::block_in_place(f);
+            }
+            AsyncCallPolicy::Ignore => {}
+            _ => warn_async_call(),
+        }
+    }
+    f()
+}
+
+/// Warns once that a blocking call was made from an async runtime.
+#[cfg(feature = "tokio")]
+fn warn_async_call() {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| {
+        log::warn!(
+            "a blocking function was called from a Tokio runtime, which stalls its other tasks; \
+             call it from spawn_blocking or use showfile::nonblocking instead"
+        );
+    });
+}
+
+/// Runs `f` on a thread of its own, giving up on it with [`Error::TimedOut`] after `timeout`.
+///
+/// The thread is started even if the maximum number of threads is running, so that the call is
+/// never made on the calling thread or queued behind other calls. If the deadline passes before
+/// the thread gets to `f`, `f` isn't called. Otherwise the thread keeps running `f` until it
+/// returns, so backends should time out their own calls at the same deadline to free it. If `f`
+/// panics, the panic is resumed on the calling thread.
+pub(crate) fn with_deadline<T: Send + 'static>(
+    timeout: Duration,
+    f: impl FnOnce() -> Result<T, Error> + Send + 'static,
+) -> Result<T, Error> {
+    let deadline = Instant::now() + timeout;
+    let (tx, rx) = mpsc::sync_channel(1);
+    spawn("deadline", true, move || {
+        if Instant::now() < deadline {
+            let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(f)));
+        }
+    })
+    .map_err(Error::backend)?;
+    match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        Ok(Ok(result)) => result,
+        Ok(Err(payload)) => panic::resume_unwind(payload),
+        Err(_) => Err(Error::TimedOut),
+    }
+}
+
+/// A call to make on a worker thread.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The workers that run calls in the background, started as needed.
+struct Pool {
+    jobs: VecDeque<Job>,
+    workers: usize,
+    idle: usize,
+}
+
+static POOL: Mutex<Pool> = Mutex::new(Pool {
+    jobs: VecDeque::new(),
+    workers: 0,
+    idle: 0,
+});
+static JOB_QUEUED: Condvar = Condvar::new();
+
+/// How long a worker waits for more calls before exiting.
+const WORKER_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Runs `job` on a worker thread.
+///
+/// A new worker is started if none is idle. If none can be started and there are no workers to
+/// queue it for, `job` runs on the calling thread instead.
+pub(crate) fn execute(job: impl FnOnce() + Send + 'static) {
+    let mut pool = POOL.lock().unwrap();
+    pool.jobs.push_back(Box::new(job));
+    if pool.idle > 0 {
+        JOB_QUEUED.notify_one();
+        return;
+    }
+    match spawn("worker", false, work) {
+        Ok(Some(_)) => pool.workers += 1,
+        _ if pool.workers > 0 => {}
+        _ => {
+            let job = pool.jobs.pop_back();
+            drop(pool);
+            if let Some(job) = job {
+                job();
+            }
+        }
+    }
+}
+
+/// Runs queued calls until none have come in for a while.
+fn work() {
+    let mut pool = POOL.lock().unwrap();
+    loop {
+        if let Some(job) = pool.jobs.pop_front() {
+            drop(pool);
+            // Don't lose the worker if the call panics
+            let _ = panic::catch_unwind(AssertUnwindSafe(job));
+            pool = POOL.lock().unwrap();
+            continue;
+        }
+        pool.idle += 1;
+        let (guard, wait) = JOB_QUEUED.wait_timeout(pool, WORKER_IDLE_TIMEOUT).unwrap();
+        pool = guard;
+        pool.idle -= 1;
+        if wait.timed_out() && pool.jobs.is_empty() {
+            pool.workers -= 1;
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    #[test]
+    fn deadline_passes() {
+        let res = with_deadline(Duration::from_millis(20), || {
+            thread::sleep(Duration::from_secs(2));
+            Ok(())
+        });
+        assert!(matches!(res, Err(Error::TimedOut)));
+    }
+
+    #[test]
+    fn expired_call_never_runs() {
+        let ran = Arc::new(AtomicBool::new(false));
+        let flag = ran.clone();
+        let res = with_deadline(Duration::ZERO, move || {
+            flag.store(true, Ordering::SeqCst);
+            Ok(())
+        });
+        assert!(matches!(res, Err(Error::TimedOut)));
+        thread::sleep(Duration::from_millis(50));
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn deadline_from_worker() {
+        let (tx, rx) = mpsc::channel();
+        execute(move || {
+            let _ = tx.send(with_deadline(Duration::from_secs(5), || Ok(1)));
+        });
+        let res = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(matches!(res, Ok(1)));
+    }
+}
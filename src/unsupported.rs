@@ -0,0 +1,97 @@
+//! Targets this crate doesn't know how to show files on, such as WASI or Fuchsia.
+//!
+//! Everything builds so that applications don't need their own `cfg` around this crate, but every
+//! call that would show or open something fails with [`Error::Unsupported`].
+
+use std::path::Path;
+
+use crate::{
+    BackendInfo, Capabilities, Diagnostics, Error, ErrorKind, LocationStatus, Portal, SandboxKind,
+    Selection, ShowOptions, ShowOutcome,
+};
+
+pub(crate) fn show_path(_path: &Path, _options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    Err(Error::Unsupported)
+}
+
+pub(crate) fn show_uri(_uri: &str, _options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    Err(Error::Unsupported)
+}
+
+/// The state of a [`RevealSession`](crate::RevealSession), which is empty since nothing can be
+/// shown.
+#[derive(Debug, Default)]
+pub(crate) struct RevealState;
+
+pub(crate) fn reveal_path(
+    _state: &mut RevealState,
+    path: &Path,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    show_path(path, options)
+}
+
+pub(crate) fn reveal_uri(
+    _state: &mut RevealState,
+    uri: &str,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    show_uri(uri, options)
+}
+
+pub(crate) fn show_selection(
+    _selection: &Selection,
+    _options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    Err(Error::Unsupported)
+}
+
+pub(crate) fn open_path(_path: &Path) -> Result<(), Error> {
+    Err(Error::Unsupported)
+}
+
+pub(crate) fn open_path_with(_app: &str, _path: &Path) -> Result<(), Error> {
+    Err(Error::Unsupported)
+}
+
+pub(crate) fn show_properties(_path: &Path, _options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    Err(Error::Unsupported)
+}
+
+pub(crate) fn backend_error_kind(_err: &(dyn std::error::Error + 'static)) -> Option<ErrorKind> {
+    None
+}
+
+pub(crate) fn diagnose(_diagnostics: &mut Diagnostics) {}
+
+pub(crate) fn backends() -> Vec<BackendInfo> {
+    Vec::new()
+}
+
+pub(crate) fn capabilities() -> Capabilities {
+    Capabilities::default()
+}
+
+pub(crate) fn supports_uri_scheme(_scheme: &str) -> bool {
+    false
+}
+
+pub(crate) fn path_location_status(_path: &Path) -> LocationStatus {
+    LocationStatus::Local
+}
+
+pub(crate) fn uri_location_status(_uri: &str) -> LocationStatus {
+    LocationStatus::Unknown
+}
+
+pub(crate) fn sandbox_kind() -> Option<SandboxKind> {
+    None
+}
+
+pub(crate) fn portals() -> Result<Vec<Portal>, Error> {
+    Ok(Vec::new())
+}
+
+pub(crate) fn file_manager_available() -> bool {
+    false
+}
@@ -0,0 +1,672 @@
+use std::{
+    ffi::OsStr,
+    fmt,
+    path::{Component, Path, PathBuf, Prefix},
+    str::FromStr,
+    sync::Arc,
+};
+
+use crate::Error;
+
+/// A `file:` URI that has been checked to be well-formed.
+///
+/// File managers tend to silently ignore URIs they can't parse, so constructing a `FileUri` first
+/// catches those mistakes with a useful error. It can be passed anywhere a URI is accepted.
+///
+/// ```
+/// use showfile::FileUri;
+///
+/// let uri = FileUri::parse("file:///home/alice/hello%20world.txt")?;
+/// assert_eq!(uri.as_str(), "file:///home/alice/hello%20world.txt");
+/// assert!(FileUri::parse("file:///home/alice/hello world.txt").is_err());
+/// # Ok::<(), showfile::FileUriError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileUri(String);
+
+/// An error returned when a [`FileUri`] could not be created.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FileUriError {
+    /// The URI does not use the `file` scheme.
+    NotFileScheme,
+    /// The URI has a character that must be percent-encoded, at this byte offset.
+    UnencodedChar(usize),
+    /// The URI has a `%` that is not followed by two hexadecimal digits, at this byte offset.
+    InvalidPercentEncoding(usize),
+    /// The URI has a query or fragment, starting at this byte offset. These have no meaning in
+    /// file URIs and are rejected by most file managers.
+    QueryOrFragment(usize),
+    /// The path is empty or not absolute.
+    RelativePath,
+    /// The path can't be represented as a URI.
+    InvalidPath,
+}
+
+impl fmt::Display for FileUriError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFileScheme => f.write_str("URI does not use the file scheme"),
+            Self::UnencodedChar(pos) => {
+                write!(f, "character at offset {pos} must be percent-encoded")
+            }
+            Self::InvalidPercentEncoding(pos) => {
+                write!(f, "invalid percent-encoding at offset {pos}")
+            }
+            Self::QueryOrFragment(pos) => {
+                write!(f, "file URI has a query or fragment at offset {pos}")
+            }
+            Self::RelativePath => f.write_str("path is not absolute"),
+            Self::InvalidPath => f.write_str("path can't be represented as a URI"),
+        }
+    }
+}
+
+impl std::error::Error for FileUriError {}
+
+impl From<FileUriError> for Error {
+    fn from(_err: FileUriError) -> Self {
+        Self::InvalidPath
+    }
+}
+
+/// Builds `file:` URIs for paths in a buffer that is reused from one path to the next.
+///
+/// [`FileUri::from_path`] allocates a new string for each URI. Applications that build URIs at a
+/// high rate, such as a file sync client revealing each file as it changes, can keep an encoder
+/// around instead, which only allocates when a URI is longer than any before it. The URI it
+/// returns can be passed to [`RevealSession::reveal_uri`](crate::RevealSession::reveal_uri) or
+/// [`ShowOptions::show_uri`](crate::ShowOptions::show_uri).
+///
+/// ```
+/// # #[cfg(unix)]
+/// # {
+/// let mut encoder = showfile::UriEncoder::new();
+/// for path in ["/home/alice/a b.txt", "/home/alice/c.txt"] {
+///     let uri = encoder.encode(path)?;
+///     assert!(uri.starts_with("file:///home/alice/"));
+/// }
+/// # }
+/// # Ok::<(), showfile::FileUriError>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UriEncoder {
+    buf: String,
+}
+
+impl UriEncoder {
+    /// Creates an encoder with an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an encoder whose buffer has room for a URI of `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: String::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the URI for an absolute path, encoded as in [`FileUri::from_path`].
+    ///
+    /// The URI borrows the encoder's buffer, and is overwritten by the next call.
+    pub fn encode(&mut self, path: impl AsRef<Path>) -> Result<&str, FileUriError> {
+        self.buf.clear();
+        write_path(path.as_ref(), &mut self.buf)?;
+        Ok(&self.buf)
+    }
+}
+
+/// Converts paths into the URIs passed to the file manager, and URIs back into paths, as set with
+/// [`ShowOptions::uri_codec`](crate::ShowOptions::uri_codec).
+///
+/// Both methods default to the standard `file:` URIs of [`FileUri`], so an implementation only
+/// needs to override what differs for its file manager.
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use showfile::{Error, FileUri, ShowOptions, UriCodec};
+///
+/// /// Passes paths to a file manager that only takes its own `vfs:` scheme.
+/// struct VfsCodec;
+///
+/// impl UriCodec for VfsCodec {
+///     fn encode(&self, path: &Path) -> Result<String, Error> {
+///         let uri = FileUri::from_path(path)?;
+///         Ok(format!("vfs:{}", &uri.as_str()["file:".len()..]))
+///     }
+/// }
+///
+/// ShowOptions::new()
+///     .uri_codec(VfsCodec)
+///     .show_path("/home/alice/hello.txt")?;
+/// # Ok::<(), Error>(())
+/// ```
+pub trait UriCodec: Send + Sync {
+    /// Converts the absolute `path` into the URI to pass to the file manager.
+    fn encode(&self, path: &Path) -> Result<String, Error> {
+        Ok(FileUri::from_path(path)?.into_string())
+    }
+
+    /// Converts `uri` into the local path it refers to, or returns `None` if it doesn't refer to
+    /// a local file.
+    fn decode(&self, uri: &str) -> Option<PathBuf> {
+        FileUri::parse(uri).ok()?.to_path()
+    }
+}
+
+/// The standard `file:` URIs, used unless another [`UriCodec`] is set.
+pub(crate) struct DefaultCodec;
+
+impl UriCodec for DefaultCodec {}
+
+/// A [`UriCodec`] shared by the clones of the options it was set in.
+#[derive(Clone)]
+pub(crate) struct SharedCodec(pub(crate) Arc<dyn UriCodec>);
+
+impl fmt::Debug for SharedCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UriCodec")
+    }
+}
+
+/// Checks whether `s` is a valid URI scheme: a letter followed by letters, digits, `+`, `-` or
+/// `.`.
+pub(crate) fn is_scheme(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Checks whether `b` can appear in a URI path without being percent-encoded.
+fn is_path_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@/".contains(&b)
+}
+
+/// Percent-encodes a path component.
+///
+/// This leaves the same characters unencoded as `g_filename_to_uri`: unreserved characters and
+/// the sub-delimiters other than `;`, which are safe in a path on every platform. Everything else,
+/// including `%`, `#`, `?` and the bytes of non-ASCII characters, is encoded, so a file name that
+/// looks like it is already encoded is not mistaken for one.
+fn encode(bytes: &[u8], out: &mut String) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    for &b in bytes {
+        if b.is_ascii_alphanumeric() || b"-._~!$&'()*+,=:@".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push('%');
+            out.push(HEX[usize::from(b >> 4)] as char);
+            out.push(HEX[usize::from(b & 0xf)] as char);
+        }
+    }
+}
+
+/// Decodes percent-encoded bytes, failing on encoded `/` or nul bytes that would change the
+/// meaning of the path.
+fn decode(s: &str) -> Option<Vec<u8>> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            // Two hex digits, so always ASCII and in range
+            let b = u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?;
+            if b == b'/' || b == 0 {
+                return None;
+            }
+            out.push(b);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Some(out)
+}
+
+/// Appends the URI for the absolute `path` to `uri`, as in [`FileUri::from_path`].
+fn write_path(path: &Path, uri: &mut String) -> Result<(), FileUriError> {
+    if !path.is_absolute() {
+        return Err(FileUriError::RelativePath);
+    }
+    let mut components = path.components().peekable();
+    let prefix = match components.peek() {
+        Some(Component::Prefix(prefix)) => Some(prefix.kind()),
+        _ => None,
+    };
+    if prefix.is_some() {
+        components.next();
+    }
+    let root = components.next_if_eq(&Component::RootDir).is_some();
+    write_parts(prefix, root, components.map(Component::as_os_str), uri)
+}
+
+/// Appends the URI for a path to `uri` from its parts: its Windows prefix if any, whether it has
+/// a root, and the names that follow.
+///
+/// Drive letters are kept in the path, as in `file:///C:/Users`, and the server of UNC paths
+/// becomes the host, as in `file://server/share/report.pdf`.
+fn write_parts<'a>(
+    prefix: Option<Prefix<'a>>,
+    root: bool,
+    names: impl IntoIterator<Item = &'a OsStr>,
+    uri: &mut String,
+) -> Result<(), FileUriError> {
+    uri.push_str("file://");
+    match prefix {
+        Some(Prefix::Disk(letter) | Prefix::VerbatimDisk(letter)) => {
+            uri.push('/');
+            uri.push(letter as char);
+            uri.push(':');
+        }
+        Some(Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share)) => {
+            let server = server.to_str().ok_or(FileUriError::InvalidPath)?;
+            let share = share.to_str().ok_or(FileUriError::InvalidPath)?;
+            encode(server.as_bytes(), uri);
+            uri.push('/');
+            encode(share.as_bytes(), uri);
+        }
+        Some(_) => return Err(FileUriError::InvalidPath),
+        None => {}
+    }
+    if root {
+        uri.push('/');
+    }
+    let mut names = names.into_iter().peekable();
+    while let Some(name) = names.next() {
+        #[cfg(unix)]
+        let bytes = name.as_encoded_bytes();
+        #[cfg(not(unix))]
+        let bytes = name.to_str().ok_or(FileUriError::InvalidPath)?.as_bytes();
+        encode(bytes, uri);
+        if names.peek().is_some() {
+            uri.push('/');
+        }
+    }
+    Ok(())
+}
+
+/// Returns the Windows path for the URI with the percent-encoded `host` and `path`, as in
+/// [`FileUri::to_path`]: a UNC path if there is a host, and otherwise a path with a drive letter
+/// if it starts with one.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn windows_path(host: &str, path: &str) -> Option<String> {
+    let host = String::from_utf8(decode(host)?).ok()?;
+    let path = String::from_utf8(decode(path)?).ok()?.replace('/', "\\");
+    let bytes = path.as_bytes();
+    if !host.is_empty() {
+        Some(format!("\\\\{host}{path}"))
+    } else if bytes.len() >= 3 && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        Some(path[1..].to_owned())
+    } else {
+        Some(path)
+    }
+}
+
+impl FileUri {
+    /// Checks that `uri` is a well-formed `file:` URI.
+    ///
+    /// Both the `file:///path` and `file://host/path` forms are accepted, including Windows drive
+    /// letters as in `file:///C:/Users/alice/report.pdf`. Characters outside of
+    /// those allowed by [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986) must be
+    /// percent-encoded.
+    pub fn parse(uri: impl Into<String>) -> Result<Self, FileUriError> {
+        let uri = uri.into();
+        if !uri
+            .get(..5)
+            .is_some_and(|s| s.eq_ignore_ascii_case("file:"))
+        {
+            return Err(FileUriError::NotFileScheme);
+        }
+        let bytes = uri.as_bytes();
+        let mut pos = 5;
+        if bytes[pos..].starts_with(b"//") {
+            pos += 2;
+            // Skip the host, which is checked with the rest of the URI
+            pos += bytes[pos..]
+                .iter()
+                .position(|&b| b == b'/')
+                .unwrap_or(bytes.len() - pos);
+        }
+        if bytes.get(pos) != Some(&b'/') {
+            return Err(FileUriError::RelativePath);
+        }
+        let mut i = 5;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'%' => {
+                    let hex = bytes.get(i + 1..i + 3);
+                    if !hex.is_some_and(|h| h.iter().all(u8::is_ascii_hexdigit)) {
+                        return Err(FileUriError::InvalidPercentEncoding(i));
+                    }
+                    i += 3;
+                    continue;
+                }
+                b'?' | b'#' => return Err(FileUriError::QueryOrFragment(i)),
+                b if !is_path_char(b) => return Err(FileUriError::UnencodedChar(i)),
+                _ => {}
+            }
+            i += 1;
+        }
+        Ok(Self(uri))
+    }
+
+    /// Creates a URI for an absolute path, percent-encoding it as needed.
+    ///
+    /// Characters are encoded the same way as GIO does, with non-ASCII characters encoded as
+    /// UTF-8. On Windows, drive letters are kept as in `file:///C:/Users/alice/report.pdf`, and
+    /// UNC paths use the server as the host, as in `file://server/share/report.pdf`.
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// let uri = showfile::FileUri::from_path("/home/alice/50% off #1?.txt")?;
+    /// assert_eq!(uri.as_str(), "file:///home/alice/50%25%20off%20%231%3F.txt");
+    /// let uri = showfile::FileUri::from_path("/home/alice/résumé (final).pdf")?;
+    /// assert_eq!(uri.as_str(), "file:///home/alice/r%C3%A9sum%C3%A9%20(final).pdf");
+    /// # }
+    /// # Ok::<(), showfile::FileUriError>(())
+    /// ```
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, FileUriError> {
+        let path = path.as_ref();
+        let mut uri = String::with_capacity(path.as_os_str().len() + 8);
+        write_path(path, &mut uri)?;
+        Ok(Self(uri))
+    }
+
+    /// Converts the URI back into a path.
+    ///
+    /// On Windows, drive letters and UNC paths are converted back, so that
+    /// `file:///C:/Users/alice/report.pdf` becomes `C:\Users\alice\report.pdf` and
+    /// `file://server/share/report.pdf` becomes `\\server\share\report.pdf`. On other platforms,
+    /// returns `None` if the URI has a host other than `localhost`. Also returns `None` if the
+    /// path has an encoded `/` or nul byte, or on Windows, if it isn't valid UTF-8.
+    ///
+    /// ```
+    /// # use std::path::Path;
+    /// let uri = showfile::FileUri::parse("file:///C:/Users/alice/report%201.pdf")?;
+    /// # #[cfg(windows)]
+    /// assert_eq!(uri.to_path().as_deref(), Some(Path::new(r"C:\Users\alice\report 1.pdf")));
+    /// # Ok::<(), showfile::FileUriError>(())
+    /// ```
+    pub fn to_path(&self) -> Option<PathBuf> {
+        let (host, path) = self.host_and_path();
+        #[cfg(windows)]
+        {
+            windows_path(host, path).map(PathBuf::from)
+        }
+        #[cfg(unix)]
+        {
+            use std::{ffi::OsString, os::unix::ffi::OsStringExt};
+
+            if !host.is_empty() {
+                return None;
+            }
+            Some(PathBuf::from(OsString::from_vec(decode(path)?)))
+        }
+        // Such as WASI, where paths are strings
+        #[cfg(not(any(unix, windows)))]
+        {
+            if !host.is_empty() {
+                return None;
+            }
+            Some(PathBuf::from(String::from_utf8(decode(path)?).ok()?))
+        }
+    }
+
+    /// Returns the host, which is empty for `localhost`, and the path, both still encoded.
+    fn host_and_path(&self) -> (&str, &str) {
+        let rest = &self.0[5..];
+        let (host, path) = match rest.strip_prefix("//") {
+            Some(rest) => rest.split_at(rest.find('/').unwrap_or(rest.len())),
+            None => ("", rest),
+        };
+        if host.eq_ignore_ascii_case("localhost") {
+            ("", path)
+        } else {
+            (host, path)
+        }
+    }
+
+    /// Returns the URI as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Converts the URI into a string.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for FileUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for FileUri {
+    type Err = FileUriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for FileUri {
+    type Error = FileUriError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<&str> for FileUri {
+    type Error = FileUriError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Self::parse(s)
+    }
+}
+
+impl AsRef<str> for FileUri {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<FileUri> for String {
+    fn from(uri: FileUri) -> Self {
+        uri.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_valid() {
+        for uri in [
+            "file:///home/alice/hello%20world.txt",
+            "file://localhost/home/alice",
+            "FILE:///home/alice",
+            "file://server/share/report.pdf",
+            "file:///C:/Users/alice",
+            "file:///a~b!$&'()*+,;=:@",
+            "file:///",
+        ] {
+            assert_eq!(FileUri::parse(uri).unwrap().as_str(), uri);
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = |uri: &str| FileUri::parse(uri).unwrap_err();
+        assert_eq!(err("http://example.com/"), FileUriError::NotFileScheme);
+        assert_eq!(err("fil"), FileUriError::NotFileScheme);
+        assert_eq!(err("file:///a b"), FileUriError::UnencodedChar(9));
+        assert_eq!(err("file:///résumé"), FileUriError::UnencodedChar(9));
+        assert_eq!(err("file:///a\nb"), FileUriError::UnencodedChar(9));
+        assert_eq!(err("file:///a\\b"), FileUriError::UnencodedChar(9));
+        assert_eq!(err("file:///a%2"), FileUriError::InvalidPercentEncoding(9));
+        assert_eq!(err("file:///a%zz"), FileUriError::InvalidPercentEncoding(9));
+        assert_eq!(err("file:///%"), FileUriError::InvalidPercentEncoding(8));
+        assert_eq!(err("file:///a?b=c"), FileUriError::QueryOrFragment(9));
+        assert_eq!(err("file:///a#b"), FileUriError::QueryOrFragment(9));
+        assert_eq!(err("file:"), FileUriError::RelativePath);
+        assert_eq!(err("file:home/alice"), FileUriError::RelativePath);
+        assert_eq!(err("file://server"), FileUriError::RelativePath);
+    }
+
+    #[test]
+    fn decode_escapes() {
+        assert_eq!(decode("a%20b").unwrap(), b"a b");
+        assert_eq!(decode("r%C3%A9sum%c3%a9").unwrap(), "résumé".as_bytes());
+        assert_eq!(decode("50%25").unwrap(), b"50%");
+        assert_eq!(decode("%FF").unwrap(), b"\xff");
+        assert_eq!(decode("").unwrap(), b"");
+    }
+
+    #[test]
+    fn decode_rejects() {
+        // An encoded `/` or nul byte would change which file the path refers to
+        assert_eq!(decode("a%2Fb"), None);
+        assert_eq!(decode("a%2fb"), None);
+        assert_eq!(decode("a%00"), None);
+        assert_eq!(decode("a%2"), None);
+        assert_eq!(decode("a%"), None);
+        assert_eq!(decode("%zz"), None);
+        assert_eq!(decode("%+1"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn to_path_hosts() {
+        let to_path = |uri: &str| FileUri::parse(uri).unwrap().to_path();
+        let path = Some(PathBuf::from("/home/alice/a b"));
+        assert_eq!(to_path("file:///home/alice/a%20b"), path);
+        assert_eq!(to_path("file://localhost/home/alice/a%20b"), path);
+        assert_eq!(to_path("file://LocalHost/home/alice/a%20b"), path);
+        assert_eq!(to_path("file://server/home/alice/a%20b"), None);
+        assert_eq!(to_path("file://127.0.0.1/home/alice"), None);
+        assert_eq!(to_path("file:///home/alice/a%2Fb"), None);
+        assert_eq!(to_path("file:///home/alice/a%00"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn round_trip() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        for path in [
+            "/home/alice/50% off #1?.txt",
+            "/home/alice/résumé (final).pdf",
+            "/tmp/%2F not a slash",
+            "/tmp/line\nbreak\\",
+            "/",
+        ] {
+            let uri = FileUri::from_path(path).unwrap();
+            assert_eq!(FileUri::parse(uri.as_str()).as_ref(), Ok(&uri));
+            assert_eq!(uri.to_path().as_deref(), Some(Path::new(path)));
+        }
+        let path = Path::new(OsStr::from_bytes(b"/tmp/\xff\xfe"));
+        let uri = FileUri::from_path(path).unwrap();
+        assert_eq!(uri.as_str(), "file:///tmp/%FF%FE");
+        assert_eq!(uri.to_path().as_deref(), Some(path));
+        assert_eq!(
+            FileUri::from_path("relative/path"),
+            Err(FileUriError::RelativePath)
+        );
+    }
+
+    /// Builds a URI with [`write_parts`] for a Windows path with `prefix` and a root.
+    fn windows_uri(prefix: Prefix<'_>, names: &[&str]) -> Result<String, FileUriError> {
+        let mut uri = String::new();
+        write_parts(Some(prefix), true, names.iter().map(OsStr::new), &mut uri)?;
+        Ok(uri)
+    }
+
+    /// Converts `uri` with [`windows_path`], as on Windows.
+    fn to_windows_path(uri: &str) -> Option<String> {
+        let uri = FileUri::parse(uri).unwrap();
+        let (host, path) = uri.host_and_path();
+        windows_path(host, path)
+    }
+
+    #[test]
+    fn windows_paths_to_uris() {
+        let disk = Prefix::Disk(b'C');
+        let unc = Prefix::UNC(OsStr::new("server"), OsStr::new("share"));
+        assert_eq!(
+            windows_uri(disk, &["Users", "alice", "report 1.pdf"]).unwrap(),
+            "file:///C:/Users/alice/report%201.pdf"
+        );
+        assert_eq!(
+            windows_uri(Prefix::VerbatimDisk(b'D'), &["data"]).unwrap(),
+            "file:///D:/data"
+        );
+        assert_eq!(windows_uri(disk, &[]).unwrap(), "file:///C:/");
+        assert_eq!(
+            windows_uri(unc, &["docs", "résumé.pdf"]).unwrap(),
+            "file://server/share/docs/r%C3%A9sum%C3%A9.pdf"
+        );
+        assert_eq!(
+            windows_uri(
+                Prefix::VerbatimUNC(OsStr::new("server"), OsStr::new("share")),
+                &["a#b"]
+            )
+            .unwrap(),
+            "file://server/share/a%23b"
+        );
+        assert_eq!(
+            windows_uri(Prefix::DeviceNS(OsStr::new("COM1")), &[]),
+            Err(FileUriError::InvalidPath)
+        );
+    }
+
+    #[test]
+    fn uris_to_windows_paths() {
+        let path = |path: &str| Some(path.to_owned());
+        assert_eq!(
+            to_windows_path("file:///C:/Users/alice/report%201.pdf"),
+            path(r"C:\Users\alice\report 1.pdf")
+        );
+        assert_eq!(to_windows_path("file://localhost/c:/"), path(r"c:\"));
+        assert_eq!(
+            to_windows_path("file://server/share/docs/report.pdf"),
+            path(r"\\server\share\docs\report.pdf")
+        );
+        assert_eq!(
+            to_windows_path("file://my%20server/share/"),
+            path(r"\\my server\share\")
+        );
+        assert_eq!(to_windows_path("file:///C:/a%2Fb"), None);
+        assert_eq!(to_windows_path("file:///C:/%FF"), None);
+    }
+
+    #[test]
+    fn windows_round_trip() {
+        let disk = Prefix::Disk(b'C');
+        let unc = Prefix::UNC(OsStr::new("server"), OsStr::new("share"));
+        for (prefix, names, path) in [
+            (disk, &["50% off #1?.txt"][..], r"C:\50% off #1?.txt"),
+            (
+                disk,
+                &["Users", "résumé (final).pdf"],
+                r"C:\Users\résumé (final).pdf",
+            ),
+            (unc, &["docs", "a b"], r"\\server\share\docs\a b"),
+        ] {
+            let uri = windows_uri(prefix, names).unwrap();
+            assert_eq!(to_windows_path(&uri).as_deref(), Some(path));
+        }
+    }
+}
@@ -0,0 +1,1429 @@
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    ffi::{OsStr, OsString},
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    panic::AssertUnwindSafe,
+    path::{Component, Path, PathBuf, Prefix},
+    sync::{mpsc, OnceLock},
+    time::{Duration, Instant},
+};
+use windows::{
+    core::{w, ComInterface, Result as WinResult, HSTRING, PCWSTR, PWSTR},
+    Win32::{
+        Foundation::{
+            ERROR_CONNECTION_UNAVAIL, ERROR_FILE_NOT_FOUND, ERROR_MORE_DATA, ERROR_NOT_SUPPORTED,
+            ERROR_NO_ASSOCIATION, ERROR_PATH_NOT_FOUND, ERROR_TIMEOUT, E_ACCESSDENIED, E_FAIL,
+            E_NOTIMPL, HANDLE, HWND, LPARAM, MAX_PATH, WPARAM,
+        },
+        Graphics::Gdi::{
+            GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+            MONITOR_DEFAULTTONULL,
+        },
+        NetworkManagement::WNet::WNetGetConnectionW,
+        Storage::{
+            FileSystem::{
+                GetDriveTypeW, GetFileAttributesW, FILE_ATTRIBUTE_OFFLINE,
+                FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS, FILE_ATTRIBUTE_RECALL_ON_OPEN,
+                INVALID_FILE_ATTRIBUTES,
+            },
+            Packaging::Appx::GetCurrentPackageFamilyName,
+        },
+        System::{
+            Com::*,
+            StationsAndDesktops::{
+                GetProcessWindowStation, GetUserObjectInformationW, UOI_FLAGS, USEROBJECTFLAGS,
+            },
+            Threading::GetCurrentThreadId,
+            Variant::{VARIANT, VT_I4},
+            WindowsProgramming::{DRIVE_CDROM, DRIVE_NO_ROOT_DIR, DRIVE_REMOTE, DRIVE_REMOVABLE},
+        },
+        UI::{
+            Shell::{Common::ITEMIDLIST, *},
+            WindowsAndMessaging::{
+                DispatchMessageW, GetMessageW, GetWindowPlacement, PeekMessageW,
+                PostThreadMessageW, SetForegroundWindow, SetWindowPlacement, TranslateMessage, MSG,
+                PM_NOREMOVE, SW_SHOWNORMAL, WINDOWPLACEMENT, WM_APP, WM_USER,
+            },
+        },
+    },
+};
+
+use crate::{
+    args, location, selection::Item, threads, Action, BackendInfo, Capabilities, Diagnostics,
+    Error, ErrorKind, Handler, LocationStatus, Portal, SandboxKind, Selection, ShowOptions,
+    ShowOutcome, Warning,
+};
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::RawWindowHandle;
+
+struct ComHandle(());
+impl ComHandle {
+    fn new() -> WinResult<Self> {
+        unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED)? };
+        Ok(Self(()))
+    }
+}
+impl Drop for ComHandle {
+    fn drop(&mut self) {
+        unsafe {
+            CoUninitialize();
+        }
+    }
+}
+
+/// COM state kept for the lifetime of a thread that has made shell calls.
+struct ComThread {
+    // Dropped first, since the folders must be released before COM is uninitialized
+    folders: RefCell<Vec<CachedFolder>>,
+    _handle: ComHandle,
+}
+
+std::thread_local! {
+    static COM_THREAD: WinResult<ComThread> = ComHandle::new().map(|handle| ComThread {
+        folders: RefCell::new(Vec::new()),
+        _handle: handle,
+    });
+}
+
+fn init_com() -> Result<(), Error> {
+    COM_THREAD.with(|r| {
+        r.as_ref()
+            .map(|_| ())
+            .map_err(|e| Error::backend(e.clone()))
+    })
+}
+
+/// A call to make on the shell thread.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The thread message that wakes up the shell thread to run its queued jobs.
+const WM_RUN_JOBS: u32 = WM_APP;
+
+/// The internal thread that all shell calls are made on.
+///
+/// Shell calls need a single-threaded COM apartment that pumps messages, which the calling thread
+/// may not be, such as when it has already joined the multithreaded apartment or belongs to an
+/// async runtime. Jobs are queued on a channel and the thread is woken up with a thread message,
+/// so that it keeps pumping messages while idle. COM stays initialized for as long as the process
+/// runs.
+struct ShellThread {
+    jobs: mpsc::Sender<Job>,
+    thread_id: u32,
+}
+
+impl ShellThread {
+    fn get() -> Result<&'static Self, Error> {
+        static THREAD: OnceLock<Option<ShellThread>> = OnceLock::new();
+        THREAD
+            .get_or_init(Self::spawn)
+            .as_ref()
+            .ok_or_else(|| Error::backend("failed to start the shell thread"))
+    }
+
+    fn spawn() -> Option<Self> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let (started, thread_id) = mpsc::channel();
+        threads::spawn("shell", true, move || unsafe {
+            let mut msg = MSG::default();
+            // Creates the message queue, so that thread messages can be posted to it
+            PeekMessageW(&mut msg, None, WM_USER, WM_USER, PM_NOREMOVE);
+            if started.send(GetCurrentThreadId()).is_err() {
+                return;
+            }
+            while GetMessageW(&mut msg, None, 0, 0).0 > 0 {
+                if msg.hwnd == HWND::default() && msg.message == WM_RUN_JOBS {
+                    while let Ok(job) = queue.try_recv() {
+                        job();
+                    }
+                    continue;
+                }
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        })
+        .ok()
+        .flatten()?;
+        Some(Self {
+            jobs,
+            thread_id: thread_id.recv().ok()?,
+        })
+    }
+}
+
+/// Runs `f` on the shell thread, with COM initialized, and waits for it to return.
+///
+/// Panics in `f` are resumed on the calling thread.
+fn on_shell_thread<R: Send + 'static>(
+    f: impl FnOnce() -> Result<R, Error> + Send + 'static,
+) -> Result<R, Error> {
+    let thread = ShellThread::get()?;
+    let (result, wait) = mpsc::sync_channel(1);
+    let job = Box::new(move || {
+        let res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            init_com()?;
+            f()
+        }));
+        let _ = result.send(res);
+    });
+    let exited = || Error::backend("the shell thread has exited");
+    thread.jobs.send(job).map_err(|_| exited())?;
+    unsafe { PostThreadMessageW(thread.thread_id, WM_RUN_JOBS, WPARAM(0), LPARAM(0)) }
+        .map_err(Error::backend)?;
+    match wait.recv() {
+        Ok(Ok(res)) => res,
+        Ok(Err(panic)) => std::panic::resume_unwind(panic),
+        Err(_) => Err(exited()),
+    }
+}
+
+/// A recently parsed folder, kept so that showing more items in it doesn't parse the whole path
+/// again.
+struct CachedFolder {
+    path: PathBuf,
+    idlist: IdList,
+    folder: IShellFolder,
+    parsed: Instant,
+}
+
+/// How long a parsed folder is reused, so that renamed or remounted folders are picked up.
+const FOLDER_CACHE_TTL: Duration = Duration::from_secs(30);
+/// How many parsed folders are kept per thread.
+const FOLDER_CACHE_SIZE: usize = 8;
+
+/// Runs `f` with the folder cache of the current thread, if COM is initialized on it.
+fn with_folder_cache<R>(f: impl FnOnce(&mut Vec<CachedFolder>) -> R) -> Option<R> {
+    COM_THREAD.with(|r| {
+        let com = r.as_ref().ok()?;
+        let mut folders = com.folders.borrow_mut();
+        folders.retain(|cached| cached.parsed.elapsed() < FOLDER_CACHE_TTL);
+        Some(f(&mut folders))
+    })
+}
+
+/// Checks whether the process window station is visible to the user.
+///
+/// Services and other processes in session 0 run on a non-interactive window station, where
+/// shell calls may succeed without anything ever appearing on screen.
+fn is_interactive_session() -> bool {
+    const WSF_VISIBLE: u32 = 1;
+
+    unsafe {
+        let Ok(winsta) = GetProcessWindowStation() else {
+            return true;
+        };
+        let mut flags = USEROBJECTFLAGS::default();
+        let res = GetUserObjectInformationW(
+            HANDLE(winsta.0),
+            UOI_FLAGS,
+            Some(&mut flags as *mut _ as *mut _),
+            std::mem::size_of::<USEROBJECTFLAGS>() as u32,
+            None,
+        );
+        res.is_err() || flags.dwFlags & WSF_VISIBLE != 0
+    }
+}
+
+/// An owned item ID list, freed when dropped.
+struct IdList(*mut ITEMIDLIST);
+
+impl IdList {
+    unsafe fn from_shell_name(name: &HSTRING) -> Result<Self, Error> {
+        let mut idlist = std::ptr::null_mut();
+        SHParseDisplayName(name, None::<&IBindCtx>, &mut idlist, 0, None)
+            .map_err(Error::backend)?;
+        if idlist.is_null() {
+            return Err(Error::InvalidPath);
+        }
+        Ok(Self(idlist))
+    }
+
+    unsafe fn from_path(path: &HSTRING) -> Result<Self, Error> {
+        let mut idlist = std::ptr::null_mut();
+        let res = SHParseDisplayName(path, None::<&IBindCtx>, &mut idlist, 0, None);
+        if res.is_ok() && !idlist.is_null() {
+            return Ok(Self(idlist));
+        }
+        // SHParseDisplayName rejects some valid paths, such as ones handled by certain namespace
+        // extensions or network providers, so try the simpler constructors before giving up
+        let idlist = ILCreateFromPathW(path);
+        if !idlist.is_null() {
+            return Ok(Self(idlist));
+        }
+        let idlist = SHSimpleIDListFromPath(path);
+        if !idlist.is_null() {
+            return Ok(Self(idlist));
+        }
+        Err(match res {
+            Err(e) => Error::backend(e),
+            Ok(()) => Error::InvalidPath,
+        })
+    }
+
+    /// Parses a path that is too long for `SHParseDisplayName`.
+    ///
+    /// The deepest ancestor that fits within `MAX_PATH` is parsed normally, then the remaining
+    /// components are parsed relative to that folder and the two lists are combined.
+    unsafe fn from_long_path(path: &Path, owner: HWND) -> Result<Self, Error> {
+        let ancestor = path
+            .ancestors()
+            .skip(1)
+            .find(|p| wide_len(p) < MAX_PATH as usize)
+            .filter(|p| !p.as_os_str().is_empty())
+            .ok_or(Error::InvalidPath)?;
+        let rest = path
+            .strip_prefix(ancestor)
+            .map_err(|_| Error::InvalidPath)?;
+        let parent = Self::from_path(&HSTRING::from(ancestor.as_os_str()))?;
+        let folder = parent.bind_folder()?;
+        parent.child(&folder, rest.as_os_str(), owner)
+    }
+
+    /// Parses a path that goes through an archive, such as `C:\data\archive.zip\inner\file.txt`.
+    ///
+    /// The archive is parsed as a file, then each component inside it is parsed relative to the
+    /// folder the shell's ZIP folder extension provides for the one before. This gives a list
+    /// Explorer can browse to without anything being extracted.
+    unsafe fn from_archive_path(path: &Path, archive: &Path, owner: HWND) -> Result<Self, Error> {
+        let rest = path.strip_prefix(archive).map_err(|_| Error::InvalidPath)?;
+        let mut idlist = Self::from_path(&HSTRING::from(archive.as_os_str()))?;
+        for name in rest.iter() {
+            let folder = idlist.bind_folder()?;
+            idlist = idlist.child(&folder, name, owner)?;
+        }
+        Ok(idlist)
+    }
+
+    /// Parses a path in a folder that was parsed recently, if there is one.
+    unsafe fn from_cached_folder(path: &Path, owner: HWND) -> Option<Self> {
+        let (parent, name) = (path.parent()?, path.file_name()?);
+        with_folder_cache(|folders| {
+            let cached = folders.iter().find(|cached| cached.path == parent)?;
+            cached.idlist.child(&cached.folder, name, owner).ok()
+        })
+        .flatten()
+    }
+}
+
+impl IdList {
+    /// Binds to the shell folder this list refers to.
+    unsafe fn bind_folder(&self) -> Result<IShellFolder, Error> {
+        SHBindToObject(None::<&IShellFolder>, self.0, None::<&IBindCtx>).map_err(Error::backend)
+    }
+
+    /// Parses `name` relative to this folder, which is bound to `folder`. Any dialogs shown while
+    /// parsing are owned by `owner`.
+    unsafe fn child(
+        &self,
+        folder: &IShellFolder,
+        name: &OsStr,
+        owner: HWND,
+    ) -> Result<Self, Error> {
+        let mut child = std::ptr::null_mut();
+        folder
+            .ParseDisplayName(
+                owner,
+                None::<&IBindCtx>,
+                &HSTRING::from(name),
+                None,
+                &mut child,
+                std::ptr::null_mut(),
+            )
+            .map_err(Error::backend)?;
+        let child = Self(child);
+        let idlist = ILCombine(Some(self.0 as *const _), Some(child.0 as *const _));
+        if idlist.is_null() {
+            return Err(Error::InvalidPath);
+        }
+        Ok(Self(idlist))
+    }
+}
+
+impl IdList {
+    /// Returns the list for the folder containing this item.
+    unsafe fn parent(&self) -> Result<Self, Error> {
+        let parent = ILClone(self.0);
+        if parent.is_null() {
+            return Err(Error::InvalidPath);
+        }
+        let parent = Self(parent);
+        ILRemoveLastID(Some(parent.0));
+        Ok(parent)
+    }
+
+    /// Returns the name the item is parsed from, which is its path for file system items.
+    unsafe fn parsing_name(&self) -> String {
+        let Ok(name) = SHGetNameFromIDList(self.0, SIGDN_DESKTOPABSOLUTEPARSING) else {
+            return String::new();
+        };
+        let parsing_name = String::from_utf16_lossy(name.as_wide());
+        CoTaskMemFree(Some(name.0 as *const _));
+        parsing_name
+    }
+}
+
+impl Drop for IdList {
+    fn drop(&mut self) {
+        unsafe {
+            CoTaskMemFree(Some(self.0 as *const _));
+        }
+    }
+}
+
+fn wide_len(path: &Path) -> usize {
+    path.as_os_str().encode_wide().count()
+}
+
+/// Converts verbatim (`\\?\`) paths back into regular paths.
+///
+/// The shell namespace does not understand verbatim paths, so they are rebuilt from their
+/// components. Paths that end up longer than `MAX_PATH` are handled by
+/// [`IdList::from_long_path`].
+fn normalize(path: &Path) -> Cow<'_, Path> {
+    let mut components = path.components();
+    let Some(Component::Prefix(prefix)) = components.next() else {
+        return Cow::Borrowed(path);
+    };
+    let mut normalized = OsString::new();
+    match prefix.kind() {
+        Prefix::VerbatimUNC(server, share) => {
+            normalized.push("\\\\");
+            normalized.push(server);
+            normalized.push("\\");
+            normalized.push(share);
+        }
+        Prefix::VerbatimDisk(disk) => normalized.push(format!("{}:", disk as char)),
+        Prefix::Verbatim(prefix) => {
+            normalized.push("\\\\");
+            normalized.push(prefix);
+        }
+        _ => return Cow::Borrowed(path),
+    }
+    // The rest starts with the root, unless there is nothing after the prefix
+    let rest = components.as_path();
+    if rest.as_os_str().is_empty() {
+        normalized.push("\\");
+    } else {
+        normalized.push(rest);
+    }
+    Cow::Owned(PathBuf::from(normalized))
+}
+
+/// Returns the UNC path for a path on a disconnected network drive mapping.
+///
+/// Items on a remembered drive mapping can't be shown while it is disconnected, even if the share
+/// itself is reachable, so the drive letter is replaced with the remote name.
+fn disconnected_drive_to_unc(path: &Path) -> Option<PathBuf> {
+    let mut components = path.components();
+    let Some(Component::Prefix(prefix)) = components.next() else {
+        return None;
+    };
+    let Prefix::Disk(disk) = prefix.kind() else {
+        return None;
+    };
+    let local = HSTRING::from(format!("{}:", disk as char));
+    let mut remote = vec![0u16; MAX_PATH as usize];
+    loop {
+        let mut len = remote.len() as u32;
+        let res = unsafe { WNetGetConnectionW(&local, PWSTR(remote.as_mut_ptr()), &mut len) };
+        match res {
+            Err(e) if e.code() == ERROR_MORE_DATA.to_hresult() => {
+                remote.resize(len as usize, 0);
+            }
+            Err(e) if e.code() == ERROR_CONNECTION_UNAVAIL.to_hresult() => break,
+            _ => return None,
+        }
+    }
+    let len = remote.iter().position(|&c| c == 0).unwrap_or(remote.len());
+    if len == 0 {
+        return None;
+    }
+    let mut unc = PathBuf::from(OsString::from_wide(&remote[..len]));
+    unc.extend(components.filter(|c| !matches!(c, Component::RootDir)));
+    Some(unc)
+}
+
+/// Returns the package family name if running as a packaged (MSIX) app.
+fn package_family_name() -> Option<&'static OsString> {
+    static FAMILY_NAME: OnceLock<Option<OsString>> = OnceLock::new();
+    FAMILY_NAME
+        .get_or_init(|| {
+            let mut name = [0u16; 256];
+            let mut len = name.len() as u32;
+            unsafe { GetCurrentPackageFamilyName(&mut len, PWSTR(name.as_mut_ptr())) }.ok()?;
+            let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+            Some(OsString::from_wide(&name[..len]))
+        })
+        .as_ref()
+}
+
+/// Maps a path in a packaged app's virtualized view of `AppData` to where the file actually is.
+///
+/// Writes by packaged apps to `%LOCALAPPDATA%` and `%APPDATA%` are redirected into the package's
+/// `LocalCache` folder. The app still sees them at the original location, but the file manager
+/// runs outside the package and only sees the redirected copy.
+fn devirtualize_package_path(path: &Path) -> Option<PathBuf> {
+    let family = package_family_name()?;
+    let local = PathBuf::from(std::env::var_os("LOCALAPPDATA")?);
+    let packages = local.join("Packages");
+    if path.starts_with(&packages) {
+        return None;
+    }
+    let cache = packages.join(family).join("LocalCache");
+    let redirected = match path.strip_prefix(&local) {
+        Ok(rest) => cache.join("Local").join(rest),
+        Err(_) => {
+            let roaming = std::env::var_os("APPDATA")?;
+            cache.join("Roaming").join(path.strip_prefix(roaming).ok()?)
+        }
+    };
+    redirected.symlink_metadata().is_ok().then_some(redirected)
+}
+
+/// Maps `path` to a location that processes outside of this one can use.
+fn prepare_path(path: &Path) -> Cow<'_, Path> {
+    // SHParseDisplayName seems to fail with UNC paths, so convert them back
+    let path = normalize(path);
+    let path = match devirtualize_package_path(&path) {
+        Some(real) => Cow::Owned(real),
+        None => path,
+    };
+    match disconnected_drive_to_unc(&path) {
+        Some(unc) => Cow::Owned(unc),
+        None => path,
+    }
+}
+
+/// Converts `path` into an item ID list, mapping it to a location Explorer can show first.
+///
+/// The containing folder is cached, so that items revealed one after another in the same folder
+/// only need their name parsed. Any dialogs shown while parsing are owned by `owner`.
+fn path_to_idlist(path: &Path, owner: HWND) -> Result<IdList, Error> {
+    let path = prepare_path(path);
+    unsafe {
+        if let Some(idlist) = IdList::from_cached_folder(&path, owner) {
+            return Ok(idlist);
+        }
+        let idlist = parse_prepared_path(&path, owner)?;
+        cache_folder(&path, &idlist);
+        Ok(idlist)
+    }
+}
+
+/// Converts `path` into an item ID list like [`path_to_idlist`], without the folder cache.
+///
+/// The cache is kept with the COM state of the thread, which initializes COM on it if it isn't
+/// yet, so this is for threads whose COM initialization belongs to the application.
+fn path_to_idlist_uncached(path: &Path, owner: HWND) -> Result<IdList, Error> {
+    let path = prepare_path(path);
+    unsafe { parse_prepared_path(&path, owner) }
+}
+
+/// Parses a path returned by [`prepare_path`], whichever way the shell can parse it.
+unsafe fn parse_prepared_path(path: &Path, owner: HWND) -> Result<IdList, Error> {
+    if let Some(archive) = location::archive_ancestor(path) {
+        IdList::from_archive_path(path, archive, owner)
+    } else if wide_len(path) < MAX_PATH as usize {
+        IdList::from_path(&HSTRING::from(path.as_os_str()))
+    } else {
+        IdList::from_long_path(path, owner)
+    }
+}
+
+/// Returns the file system path of a shell item, if it has one.
+unsafe fn item_path(item: &IShellItem) -> Option<PathBuf> {
+    let name = item.GetDisplayName(SIGDN_FILESYSPATH).ok()?;
+    let path = OsString::from_wide(name.as_wide());
+    CoTaskMemFree(Some(name.0 as *const _));
+    Some(PathBuf::from(path))
+}
+
+/// Returns the folders included in the shell library `library`.
+unsafe fn library_folders(library: &IShellItem) -> Option<Vec<PathBuf>> {
+    let shell_library: IShellLibrary =
+        CoCreateInstance(&ShellLibrary, None, CLSCTX_INPROC_SERVER).ok()?;
+    shell_library
+        .LoadLibraryFromItem(library, STGM_READ.0)
+        .ok()?;
+    let folders: IShellItemArray = shell_library.GetFolders(LFF_FORCEFILESYSTEM).ok()?;
+    let count = folders.GetCount().ok()?;
+    Some(
+        (0..count)
+            .filter_map(|i| item_path(&folders.GetItemAt(i).ok()?))
+            .collect(),
+    )
+}
+
+/// Parses `path` within the shell library `library`, if one of the library's folders contains it.
+unsafe fn library_child(library: &IShellItem, path: &Path, owner: HWND) -> Option<IdList> {
+    let rest = library_folders(library)?.into_iter().find_map(|folder| {
+        let rest = path.strip_prefix(folder).ok()?;
+        (!rest.as_os_str().is_empty()).then(|| rest.to_owned())
+    })?;
+    let idlist = IdList(SHGetIDListFromObject(library).ok()?);
+    let folder = idlist.bind_folder().ok()?;
+    idlist.child(&folder, rest.as_os_str(), owner).ok()
+}
+
+/// Converts `path` into an item ID list within the shell library that includes its folder, such
+/// as Documents, so that Explorer shows it in the library rather than in the physical folder.
+///
+/// Returns `None` if no library includes the folder.
+fn library_idlist(path: &Path, owner: HWND) -> Option<IdList> {
+    let path = prepare_path(path);
+    unsafe {
+        let libraries: IShellItem =
+            SHGetKnownFolderItem(&FOLDERID_Libraries, KF_FLAG_DEFAULT, HANDLE::default()).ok()?;
+        let items: IEnumShellItems = libraries
+            .BindToHandler(None::<&IBindCtx>, &BHID_EnumItems)
+            .ok()?;
+        let mut item = [None];
+        while items.Next(&mut item, None).is_ok() {
+            let library = item[0].take()?;
+            if let Some(idlist) = library_child(&library, &path, owner) {
+                return Some(idlist);
+            }
+        }
+        None
+    }
+}
+
+/// Converts the path of an item to show into an item ID list, within its shell library if
+/// [`ShowOptions::library_view`] is set and a library includes its folder.
+fn item_idlist(path: &Path, options: &ShowOptions) -> Result<IdList, Error> {
+    let owner = owner_window(options);
+    if options.library_view {
+        if let Some(idlist) = library_idlist(path, owner) {
+            return Ok(idlist);
+        }
+    }
+    path_to_idlist(path, owner)
+}
+
+/// Caches the folder containing `path`, whose item ID list is `idlist`.
+unsafe fn cache_folder(path: &Path, idlist: &IdList) {
+    let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) else {
+        return;
+    };
+    let Ok(parent_idlist) = idlist.parent() else {
+        return;
+    };
+    let Ok(folder) = parent_idlist.bind_folder() else {
+        return;
+    };
+    with_folder_cache(|folders| {
+        folders.retain(|cached| cached.path != parent);
+        if folders.len() >= FOLDER_CACHE_SIZE {
+            folders.remove(0);
+        }
+        folders.push(CachedFolder {
+            path: parent.to_owned(),
+            idlist: parent_idlist,
+            folder,
+            parsed: Instant::now(),
+        });
+    });
+}
+
+/// How long to wait for Explorer windows to show a folder.
+const FOLDER_WINDOW_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Returns the browser of an Explorer window.
+unsafe fn window_browser(windows: &IShellWindows, index: i32) -> Option<IShellBrowser> {
+    let mut variant = VARIANT::default();
+    (*variant.Anonymous.Anonymous).vt = VT_I4;
+    (*variant.Anonymous.Anonymous).Anonymous.lVal = index;
+    let window = windows.Item(variant).ok()?;
+    window
+        .cast::<IServiceProvider>()
+        .ok()?
+        .QueryService(&SID_STopLevelBrowser)
+        .ok()
+}
+
+/// Returns the folder shown by an Explorer window's browser.
+unsafe fn browser_folder(browser: &IShellBrowser) -> Option<IdList> {
+    let view: IFolderView = browser.QueryActiveShellView().ok()?.cast().ok()?;
+    let folder: IPersistFolder2 = view.GetFolder().ok()?;
+    let idlist = folder.GetCurFolder().ok()?;
+    (!idlist.is_null()).then_some(IdList(idlist))
+}
+
+/// Checks whether an Explorer window's browser shows `folder`.
+unsafe fn browser_shows(browser: &IShellBrowser, folder: &IdList) -> bool {
+    browser_folder(browser).is_some_and(|shown| ILIsEqual(folder.0, shown.0).as_bool())
+}
+
+/// Polls the open Explorer windows until one shows one of `folders`, and returns its handle.
+///
+/// Returns `None` if the shell windows can't be enumerated, or `Some(None)` if no window showed
+/// up in time.
+fn find_folder_window(folders: &[IdList]) -> Option<Option<HWND>> {
+    let windows: IShellWindows =
+        unsafe { CoCreateInstance(&ShellWindows, None, CLSCTX_ALL) }.ok()?;
+    let start = Instant::now();
+    loop {
+        let found = unsafe {
+            let count = windows.Count().unwrap_or(0);
+            (0..count)
+                .filter_map(|i| window_browser(&windows, i))
+                .find(|browser| folders.iter().any(|folder| browser_shows(browser, folder)))
+        };
+        if let Some(browser) = found {
+            return Some(Some(unsafe { browser.GetWindow() }.unwrap_or_default()));
+        }
+        if start.elapsed() >= FOLDER_WINDOW_TIMEOUT {
+            return Some(None);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Selects `idlist` in the Explorer window `window`, navigating it to `folder` first if needed.
+///
+/// Returns `false` if the window has been closed, or it couldn't be navigated.
+unsafe fn select_in_window(
+    window: HWND,
+    folder: &IdList,
+    idlist: &IdList,
+    options: &ShowOptions,
+) -> bool {
+    const FLAGS: i32 =
+        SVSI_SELECT.0 | SVSI_DESELECTOTHERS.0 | SVSI_ENSUREVISIBLE.0 | SVSI_FOCUSED.0;
+
+    let shell_windows: WinResult<IShellWindows> = CoCreateInstance(&ShellWindows, None, CLSCTX_ALL);
+    let Ok(shell_windows) = shell_windows else {
+        return false;
+    };
+    let count = shell_windows.Count().unwrap_or(0);
+    let browser = (0..count)
+        .filter_map(|i| window_browser(&shell_windows, i))
+        .find(|browser| browser.GetWindow().is_ok_and(|hwnd| hwnd == window));
+    let Some(browser) = browser else {
+        return false;
+    };
+    if !browser_shows(&browser, folder) {
+        if browser
+            .BrowseObject(folder.0, SBSP_SAMEBROWSER | SBSP_ABSOLUTE)
+            .is_err()
+        {
+            return false;
+        }
+        // Navigation finishes asynchronously, and the new view has to exist to select in it
+        let start = Instant::now();
+        while !browser_shows(&browser, folder) {
+            if start.elapsed() >= FOLDER_WINDOW_TIMEOUT {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+    let Ok(view) = browser.QueryActiveShellView() else {
+        return false;
+    };
+    let flags = if options.rename {
+        FLAGS | SVSI_EDIT.0
+    } else {
+        FLAGS
+    };
+    if view
+        .SelectItem(ILFindLastID(idlist.0), flags as u32)
+        .is_err()
+    {
+        return false;
+    }
+    if options.activate {
+        SetForegroundWindow(window);
+    }
+    true
+}
+
+/// Returns the executable name of the application registered to open folders.
+///
+/// This is `explorer.exe` unless a replacement file manager has taken over folders.
+fn folder_handler() -> String {
+    assoc_executable(w!("Folder"), ASSOCF_NOTRUNCATE).unwrap_or_else(|| "explorer.exe".to_owned())
+}
+
+/// Returns the executable name of the application registered for `assoc`.
+fn assoc_executable(assoc: PCWSTR, flags: ASSOCF) -> Option<String> {
+    let mut name = [0u16; MAX_PATH as usize];
+    let mut len = name.len() as u32;
+    let res = unsafe {
+        AssocQueryStringW(
+            flags,
+            ASSOCSTR_EXECUTABLE,
+            assoc,
+            PCWSTR::null(),
+            PWSTR(name.as_mut_ptr()),
+            &mut len,
+        )
+    };
+    if res.is_err() {
+        return None;
+    }
+    let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+    let path = PathBuf::from(OsString::from_wide(&name[..len]));
+    Some(path.file_name()?.to_string_lossy().into_owned())
+}
+
+/// Returns the window set with [`ShowOptions::parent_window`], to own any dialogs the shell shows.
+#[cfg_attr(not(feature = "raw-window-handle"), allow(unused_variables))]
+fn owner_window(options: &ShowOptions) -> HWND {
+    #[cfg(feature = "raw-window-handle")]
+    if let Some(RawWindowHandle::Win32(handle)) = options.parent.window {
+        return HWND(handle.hwnd.get());
+    }
+    HWND::default()
+}
+
+/// Returns the window whose monitor the folder window should be moved to, as set with
+/// [`ShowOptions::same_monitor`].
+#[cfg_attr(not(feature = "raw-window-handle"), allow(unused_variables))]
+fn monitor_window(options: &ShowOptions) -> Option<HWND> {
+    #[cfg(feature = "raw-window-handle")]
+    if options.parent.same_monitor {
+        return Some(owner_window(options)).filter(|window| window.0 != 0);
+    }
+    None
+}
+
+/// Moves `window` to the monitor `target` is on, if it is on another one.
+///
+/// The window keeps its size and its offset from the corner of the work area, moved back in if
+/// that would leave it past the right or bottom edge. Its normal position is changed rather than
+/// where it is now, so that a maximized window is maximized on the other monitor.
+unsafe fn move_to_monitor_of(window: HWND, target: HWND) {
+    let to = MonitorFromWindow(target, MONITOR_DEFAULTTONULL);
+    let from = MonitorFromWindow(window, MONITOR_DEFAULTTONEAREST);
+    if to.is_invalid() || to == from {
+        return;
+    }
+    let work_area = |monitor| {
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        GetMonitorInfoW(monitor, &mut info)
+            .as_bool()
+            .then_some(info.rcWork)
+    };
+    let (Some(from), Some(to)) = (work_area(from), work_area(to)) else {
+        return;
+    };
+    let mut placement = WINDOWPLACEMENT {
+        length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+        ..Default::default()
+    };
+    if GetWindowPlacement(window, &mut placement).is_err() {
+        return;
+    }
+    let rect = &mut placement.rcNormalPosition;
+    let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+    rect.left = (rect.left - from.left + to.left)
+        .min(to.right - width)
+        .max(to.left);
+    rect.top = (rect.top - from.top + to.top)
+        .min(to.bottom - height)
+        .max(to.top);
+    rect.right = rect.left + width;
+    rect.bottom = rect.top + height;
+    let _ = SetWindowPlacement(window, &placement);
+}
+
+/// The shell function that opens a folder window with items selected, as reported in
+/// [`ShowOutcome::actions`].
+const OPEN_FOLDER: &str = "SHOpenFolderAndSelectItems";
+
+/// Returns the outcome of showing items in `folders` with `actions`, with the details requested
+/// in `options`, moving the folder window if asked to.
+fn outcome(folders: &[IdList], options: &ShowOptions, actions: Vec<Action>) -> ShowOutcome {
+    let target = monitor_window(options);
+    let window = (options.verify || target.is_some())
+        .then(|| find_folder_window(folders))
+        .flatten();
+    if let (Some(Some(window)), Some(target)) = (window, target) {
+        unsafe { move_to_monitor_of(window, target) };
+    }
+    ShowOutcome {
+        window_appeared: if options.verify {
+            window.map(|window| window.is_some())
+        } else {
+            None
+        },
+        handler: options
+            .identify_handler
+            .then(|| Handler::new(folder_handler())),
+        items: Vec::new(),
+        actions,
+        warnings: Vec::new(),
+    }
+}
+
+/// Opens the folder `folder` with `ShellExecuteEx`, without selecting anything.
+unsafe fn shell_execute_folder(folder: &IdList) -> WinResult<Action> {
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+        fMask: SEE_MASK_IDLIST | SEE_MASK_NOASYNC | SEE_MASK_FLAG_NO_UI,
+        lpIDList: folder.0 as *mut _,
+        nShow: SW_SHOWNORMAL.0,
+        ..Default::default()
+    };
+    ShellExecuteExW(&mut info)?;
+    Ok(Action::call("ShellExecuteEx", [folder.parsing_name()]))
+}
+
+/// Selects `children` in the folder `idlist`, or the item `idlist` in its folder if there are
+/// none, with `SHOpenFolderAndSelectItems`, recording `items` in the action. With `rename`, the
+/// name of the first item is edited.
+///
+/// Some shell extensions make it fail with `E_FAIL` even for items that exist, so the folder is
+/// then opened with `ShellExecuteEx` instead. Returns whether the items were selected.
+unsafe fn open_folder_and_select(
+    idlist: &IdList,
+    children: Option<&[*const ITEMIDLIST]>,
+    items: impl IntoIterator<Item = String>,
+    rename: bool,
+) -> WinResult<(Action, bool)> {
+    let flags = if rename { OFASI_EDIT } else { 0 };
+    match SHOpenFolderAndSelectItems(idlist.0, children, flags) {
+        Ok(()) => Ok((Action::call(OPEN_FOLDER, items), true)),
+        Err(e) if e.code() == E_FAIL => {
+            // The original failure is reported if the folder can't be opened either
+            let parent = match children {
+                Some(_) => None,
+                None => Some(idlist.parent().map_err(|_| e.clone())?),
+            };
+            let action = shell_execute_folder(parent.as_ref().unwrap_or(idlist)).map_err(|_| e)?;
+            Ok((action, false))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub(crate) fn show_path(path: &Path, options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    if !is_interactive_session() {
+        return Err(Error::NoInteractiveSession);
+    }
+    let (path, options) = (path.to_owned(), options.clone());
+    on_shell_thread(move || {
+        let idlist = item_idlist(&path, &options)?;
+        unsafe {
+            // Taken first, so that failing to get it doesn't turn a shown item into an error
+            let parent = idlist.parent().ok();
+            let items = [path.display().to_string()];
+            let (action, selected) = open_folder_and_select(&idlist, None, items, options.rename)
+                .map_err(Error::backend)?;
+            let folder = parent.as_ref().unwrap_or(&idlist);
+            let mut outcome = outcome(std::slice::from_ref(folder), &options, vec![action]);
+            if !selected {
+                outcome.warnings.push(Warning::NotSelected);
+            }
+            Ok(outcome)
+        }
+    })
+}
+
+/// Converts a `file:` URI into a path with the codec of `options`, so it gets the same handling as
+/// paths. Other URIs are left for the shell to parse.
+fn uri_to_path<'a>(uri: &'a str, options: &ShowOptions) -> Cow<'a, Path> {
+    match options.codec().decode(uri) {
+        Some(path) => Cow::Owned(path),
+        None => Cow::Borrowed(Path::new(uri)),
+    }
+}
+
+pub(crate) fn show_uri(uri: &str, options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    show_path(&uri_to_path(uri, options), options)
+}
+
+/// The Explorer window a [`RevealSession`](crate::RevealSession) is bound to, once it has been
+/// opened.
+#[derive(Debug, Default)]
+pub(crate) struct RevealState {
+    window: Option<HWND>,
+}
+
+pub(crate) fn reveal_path(
+    state: &mut RevealState,
+    path: &Path,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    if !is_interactive_session() {
+        return Err(Error::NoInteractiveSession);
+    }
+    let (path, options, window) = (path.to_owned(), options.clone(), state.window);
+    let (outcome, window) = on_shell_thread(move || unsafe {
+        let idlist = item_idlist(&path, &options)?;
+        let folder = idlist.parent()?;
+        if let Some(window) = window {
+            if select_in_window(window, &folder, &idlist, &options) {
+                let action = Action::call("IShellView::SelectItem", [path.display().to_string()]);
+                return Ok((outcome(&[folder], &options, vec![action]), Some(window)));
+            }
+        }
+        let items = [path.display().to_string()];
+        let (action, selected) =
+            open_folder_and_select(&idlist, None, items, options.rename).map_err(Error::backend)?;
+        let window = find_folder_window(std::slice::from_ref(&folder)).flatten();
+        let mut outcome = outcome(&[folder], &options, vec![action]);
+        if !selected {
+            outcome.warnings.push(Warning::NotSelected);
+        }
+        Ok((outcome, window))
+    })?;
+    state.window = window;
+    Ok(outcome)
+}
+
+pub(crate) fn reveal_uri(
+    state: &mut RevealState,
+    uri: &str,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    reveal_path(state, &uri_to_path(uri, options), options)
+}
+
+pub(crate) fn show_selection(
+    selection: &Selection,
+    options: &ShowOptions,
+) -> Result<ShowOutcome, Error> {
+    if !is_interactive_session() {
+        return Err(Error::NoInteractiveSession);
+    }
+    let (selection, options) = (selection.clone(), options.clone());
+    on_shell_thread(move || {
+        let owner = owner_window(&options);
+        let (idlists, mut results) = selection.prepare(|item| match item {
+            Item::Path(path) => path_to_idlist(path, owner),
+            Item::Uri(uri) => path_to_idlist(&uri_to_path(uri, &options), owner),
+        })?;
+        unsafe {
+            // SHOpenFolderAndSelectItems can only select items within one folder, so open a window
+            // for each folder
+            let mut folders: Vec<(IdList, Vec<usize>, Vec<*const ITEMIDLIST>)> = Vec::new();
+            for (i, idlist) in &idlists {
+                let parent = match idlist.parent() {
+                    Ok(parent) => parent,
+                    Err(e) => {
+                        results.fail(*i, e);
+                        continue;
+                    }
+                };
+                let child = ILFindLastID(idlist.0) as *const _;
+                match folders
+                    .iter_mut()
+                    .find(|(folder, ..)| ILIsEqual(folder.0, parent.0).as_bool())
+                {
+                    Some((_, indices, children)) => {
+                        indices.push(*i);
+                        children.push(child);
+                    }
+                    None => folders.push((parent, vec![*i], vec![child])),
+                }
+            }
+            // Only a single item can have its name edited
+            let rename = options.rename && idlists.len() == 1;
+            let mut shown = Vec::with_capacity(folders.len());
+            let mut actions = Vec::with_capacity(folders.len());
+            let mut all_selected = true;
+            for (folder, indices, children) in folders {
+                let items = indices.iter().map(|&i| match &selection.items[i] {
+                    Item::Path(path) => path.display().to_string(),
+                    Item::Uri(uri) => uri.clone(),
+                });
+                match open_folder_and_select(&folder, Some(&children), items, rename) {
+                    Ok((action, selected)) => {
+                        shown.push(folder);
+                        actions.push(action);
+                        all_selected &= selected;
+                    }
+                    Err(e) => {
+                        for i in indices {
+                            results.fail(i, Error::backend(e.clone()));
+                        }
+                    }
+                }
+            }
+            let items = results.finish()?;
+            let mut outcome = outcome(&shown, &options, actions);
+            if !all_selected {
+                outcome.warnings.push(Warning::NotSelected);
+            }
+            Ok(ShowOutcome { items, ..outcome })
+        }
+    })
+}
+
+pub(crate) fn show_properties(path: &Path, options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    if !is_interactive_session() {
+        return Err(Error::NoInteractiveSession);
+    }
+    let (path, options) = (path.to_owned(), options.clone());
+    on_shell_thread(move || {
+        let path = prepare_path(&path);
+        let shown = unsafe {
+            SHObjectProperties(
+                owner_window(&options),
+                SHOP_FILEPATH,
+                &HSTRING::from(path.as_os_str()),
+                PCWSTR::null(),
+            )
+        };
+        if !shown.as_bool() {
+            return Err(Error::backend(windows::core::Error::from_win32()));
+        }
+        Ok(ShowOutcome {
+            handler: options
+                .identify_handler
+                .then(|| Handler::new(folder_handler())),
+            actions: vec![Action::call(
+                "SHObjectProperties",
+                [path.display().to_string()],
+            )],
+            ..ShowOutcome::default()
+        })
+    })
+}
+
+/// Runs `file` with `ShellExecuteExW`, waiting until it has been launched.
+fn shell_execute(file: &HSTRING, parameters: Option<&HSTRING>) -> Result<(), Error> {
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+        fMask: SEE_MASK_NOASYNC | SEE_MASK_FLAG_NO_UI,
+        lpFile: PCWSTR(file.as_ptr()),
+        lpParameters: parameters.map_or(PCWSTR::null(), |p| PCWSTR(p.as_ptr())),
+        nShow: SW_SHOWNORMAL.0,
+        ..Default::default()
+    };
+    unsafe { ShellExecuteExW(&mut info) }.map_err(|e| {
+        if e.code() == ERROR_NO_ASSOCIATION.to_hresult() {
+            Error::NoApplication
+        } else if e.code() == ERROR_FILE_NOT_FOUND.to_hresult()
+            || e.code() == ERROR_PATH_NOT_FOUND.to_hresult()
+        {
+            Error::InvalidPath
+        } else {
+            Error::backend(e)
+        }
+    })
+}
+
+pub(crate) fn open_path(path: &Path) -> Result<(), Error> {
+    if !is_interactive_session() {
+        return Err(Error::NoInteractiveSession);
+    }
+    let path = path.to_owned();
+    on_shell_thread(move || {
+        let path = prepare_path(&path);
+        shell_execute(&HSTRING::from(path.as_os_str()), None)
+    })
+}
+
+pub(crate) fn open_path_with(app: &str, path: &Path) -> Result<(), Error> {
+    if !is_interactive_session() {
+        return Err(Error::NoInteractiveSession);
+    }
+    // ShellExecuteEx reports a missing file the same way as a missing app
+    if !path.exists() {
+        return Err(Error::InvalidPath);
+    }
+    let (app, path) = (app.to_owned(), path.to_owned());
+    on_shell_thread(move || {
+        let path = prepare_path(&path);
+        let parameters = args::windows_arg(&args::path(&*path));
+        shell_execute(
+            &HSTRING::from(app.as_str()),
+            Some(&HSTRING::from(parameters)),
+        )
+        .map_err(|e| match e {
+            Error::InvalidPath => Error::NoApplication,
+            e => e,
+        })
+    })
+}
+
+pub(crate) fn backend_error_kind(err: &(dyn std::error::Error + 'static)) -> Option<ErrorKind> {
+    let code = err.downcast_ref::<windows::core::Error>()?.code();
+    if code == E_NOTIMPL || code == ERROR_NOT_SUPPORTED.to_hresult() {
+        Some(ErrorKind::Unsupported)
+    } else if code == ERROR_TIMEOUT.to_hresult() {
+        Some(ErrorKind::TimedOut)
+    } else if code == E_ACCESSDENIED {
+        Some(ErrorKind::PermissionDenied)
+    } else if code == ERROR_FILE_NOT_FOUND.to_hresult() || code == ERROR_PATH_NOT_FOUND.to_hresult()
+    {
+        Some(ErrorKind::NotFound)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn diagnose(diagnostics: &mut Diagnostics) {
+    // Jobs on the shell thread only run once COM is initialized there
+    let res = on_shell_thread(|| Ok(()));
+    diagnostics.com_initialized = Some(res.is_ok());
+    if let Err(e) = res {
+        diagnostics
+            .problems
+            .push(format!("COM: {}", crate::error::report(&e)));
+    }
+}
+
+pub(crate) fn backends() -> Vec<BackendInfo> {
+    // The version is the one in Cargo.toml
+    ["SHOpenFolderAndSelectItems", "ShellExecuteEx"]
+        .map(|name| BackendInfo::new(name, Some(("windows", "0.52"))))
+        .into()
+}
+
+pub(crate) fn capabilities() -> Capabilities {
+    // Explorer supports everything SHOpenFolderAndSelectItems and SHObjectProperties can do, but
+    // only for file system paths
+    Capabilities {
+        multi_select: true,
+        open_directory: true,
+        rename_mode: true,
+        properties_dialog: true,
+        uri_reveal: false,
+    }
+}
+
+pub(crate) fn supports_uri_scheme(scheme: &str) -> bool {
+    // Only schemes that open in the file manager itself can be browsed there
+    let scheme = HSTRING::from(scheme);
+    assoc_executable(
+        PCWSTR(scheme.as_ptr()),
+        ASSOCF_NOTRUNCATE | ASSOCF_IS_PROTOCOL,
+    )
+    .is_some_and(|exe| exe.eq_ignore_ascii_case(&folder_handler()))
+}
+
+/// Returns whether the volume `path` is on is connected, from the type of its drive or share.
+fn volume_status(path: &Path) -> LocationStatus {
+    // A remembered drive mapping that isn't connected
+    if disconnected_drive_to_unc(path).is_some() {
+        return LocationStatus::NetworkUnmounted;
+    }
+    let Some(Component::Prefix(prefix)) = path.components().next() else {
+        return LocationStatus::Local;
+    };
+    match prefix.kind() {
+        Prefix::Disk(disk) => {
+            let root = format!("{}:\\", disk as char);
+            match unsafe { GetDriveTypeW(&HSTRING::from(&root)) } {
+                DRIVE_NO_ROOT_DIR => LocationStatus::RemovableMissing,
+                DRIVE_REMOTE => LocationStatus::NetworkMounted,
+                // A card reader or optical drive without a disk in it
+                DRIVE_REMOVABLE | DRIVE_CDROM if !Path::new(&root).exists() => {
+                    LocationStatus::RemovableMissing
+                }
+                _ => LocationStatus::Local,
+            }
+        }
+        Prefix::UNC(server, share) => {
+            if Path::new("\\\\").join(server).join(share).exists() {
+                LocationStatus::NetworkMounted
+            } else {
+                LocationStatus::NetworkUnmounted
+            }
+        }
+        _ => LocationStatus::Local,
+    }
+}
+
+pub(crate) fn path_location_status(path: &Path) -> LocationStatus {
+    let path = normalize(path);
+    let status = volume_status(&path);
+    if !matches!(
+        status,
+        LocationStatus::Local | LocationStatus::NetworkMounted
+    ) {
+        return status;
+    }
+    // Files that cloud storage providers such as OneDrive haven't downloaded are recalled when
+    // they are opened
+    let attributes = unsafe { GetFileAttributesW(&HSTRING::from(path.as_os_str())) };
+    let recall = FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS.0
+        | FILE_ATTRIBUTE_RECALL_ON_OPEN.0
+        | FILE_ATTRIBUTE_OFFLINE.0;
+    if attributes != INVALID_FILE_ATTRIBUTES && attributes & recall != 0 {
+        return LocationStatus::CloudPlaceholder;
+    }
+    status
+}
+
+pub(crate) fn uri_location_status(_uri: &str) -> LocationStatus {
+    // Explorer connects to other locations itself, network shares have UNC paths instead
+    LocationStatus::NetworkMounted
+}
+
+pub(crate) fn sandbox_kind() -> Option<SandboxKind> {
+    None
+}
+
+pub(crate) fn portals() -> Result<Vec<Portal>, Error> {
+    Ok(Vec::new())
+}
+
+pub(crate) fn file_manager_available() -> bool {
+    is_interactive_session()
+}
+
+pub(crate) fn show_shell_location(name: &str, options: &ShowOptions) -> Result<ShowOutcome, Error> {
+    if !is_interactive_session() {
+        return Err(Error::NoInteractiveSession);
+    }
+    let (name, options) = (name.to_owned(), options.clone());
+    on_shell_thread(move || unsafe {
+        let idlist = IdList::from_shell_name(&HSTRING::from(name.as_str()))?;
+        SHOpenFolderAndSelectItems(idlist.0, None, 0).map_err(Error::backend)?;
+        let actions = vec![Action::call(OPEN_FOLDER, [name])];
+        Ok(outcome(&[idlist], &options, actions))
+    })
+}
+
+/// The shell calls, for [`showfile::raw`](crate::raw).
+pub(crate) mod raw {
+    use std::{ffi::c_void, fmt, path::Path};
+
+    use windows::Win32::{Foundation::HWND, UI::Shell::Common::ITEMIDLIST};
+
+    use super::{open_folder_and_select, path_to_idlist_uncached, IdList};
+    use crate::Error;
+
+    /// An item ID list (PIDL), which is how the shell refers to an item, freed when dropped.
+    ///
+    /// COM must be initialized on the thread it is created and used on, as for the shell functions
+    /// it calls.
+    pub struct ItemIdList(IdList);
+
+    impl ItemIdList {
+        /// Parses `path` the way this crate does before showing it, including the paths that
+        /// `SHParseDisplayName` rejects, paths longer than `MAX_PATH`, and paths inside ZIP
+        /// archives, which Explorer can browse to without extracting them. Unlike when showing items,
+        /// the folders parsed are not cached, and COM is left as the calling thread initialized it.
+        pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+            path_to_idlist_uncached(path.as_ref(), HWND::default()).map(Self)
+        }
+
+        /// Returns the list of the folder that contains the item.
+        pub fn parent(&self) -> Result<Self, Error> {
+            unsafe { self.0.parent() }.map(Self)
+        }
+
+        /// Returns the name the item is parsed from, which is its path for file system items.
+        pub fn parsing_name(&self) -> String {
+            unsafe { self.0.parsing_name() }
+        }
+
+        /// Returns the `PCIDLIST_ABSOLUTE` pointer, which stays valid until the list is dropped.
+        pub fn as_ptr(&self) -> *const c_void {
+            self.0 .0 as *const c_void
+        }
+    }
+
+    impl fmt::Debug for ItemIdList {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("ItemIdList")
+                .field(&self.parsing_name())
+                .finish()
+        }
+    }
+
+    /// Opens `folder` in Explorer and selects `items` in it with `SHOpenFolderAndSelectItems`, or
+    /// selects `folder` itself in its parent if `items` is empty. With `rename`, the name of the
+    /// first item is edited.
+    ///
+    /// Some shell extensions make the call fail with `E_FAIL` even for items that exist, so the
+    /// folder is then opened with `ShellExecuteEx` instead, as when showing items with the rest of
+    /// the crate. Returns whether the items were selected. The call is made on the calling thread,
+    /// which needs COM initialized.
+    pub fn open_folder_and_select_items(
+        folder: &ItemIdList,
+        items: &[&ItemIdList],
+        rename: bool,
+    ) -> Result<bool, Error> {
+        let children = items
+            .iter()
+            .map(|item| item.0 .0 as *const ITEMIDLIST)
+            .collect::<Vec<_>>();
+        let (children, names) = if items.is_empty() {
+            (None, vec![folder.parsing_name()])
+        } else {
+            let names = items.iter().map(|item| item.parsing_name()).collect();
+            (Some(children.as_slice()), names)
+        };
+        unsafe { open_folder_and_select(&folder.0, children, names, rename) }
+            .map(|(_, selected)| selected)
+            .map_err(Error::backend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn verbatim(path: &Path) -> PathBuf {
+        let mut verbatim = OsString::from(r"\\?\");
+        verbatim.push(path);
+        PathBuf::from(verbatim)
+    }
+
+    #[test]
+    fn normalize_verbatim_disk() {
+        assert_eq!(
+            normalize(Path::new(r"\\?\C:\Users\alice\report.pdf")),
+            Path::new(r"C:\Users\alice\report.pdf")
+        );
+        assert_eq!(normalize(Path::new(r"\\?\D:\")), Path::new(r"D:\"));
+    }
+
+    #[test]
+    fn normalize_verbatim_unc() {
+        assert_eq!(
+            normalize(Path::new(r"\\?\UNC\server\share\docs\report.pdf")),
+            Path::new(r"\\server\share\docs\report.pdf")
+        );
+        assert_eq!(
+            normalize(Path::new(r"\\?\UNC\server\share")),
+            Path::new(r"\\server\share\")
+        );
+    }
+
+    #[test]
+    fn normalize_regular_paths() {
+        for path in [
+            r"C:\Users\alice",
+            r"\\server\share\docs",
+            r"docs\report.pdf",
+        ] {
+            assert!(matches!(normalize(Path::new(path)), Cow::Borrowed(p) if p == Path::new(path)));
+        }
+    }
+
+    #[test]
+    fn long_path() {
+        let root = std::env::temp_dir().join(format!("showfile-long-path-{}", std::process::id()));
+        // Ten folders of 40 characters each take the path well over MAX_PATH
+        let tail = (0..10)
+            .map(|i| format!("{i}{}", "x".repeat(39)))
+            .collect::<PathBuf>();
+        let path = root.join(&tail).join("report.txt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, b"").unwrap();
+        assert!(wide_len(&path) > MAX_PATH as usize);
+
+        let long = verbatim(&path);
+        assert_eq!(normalize(&long), path);
+        let parsed = on_shell_thread(move || unsafe {
+            Ok(path_to_idlist(&long, HWND::default())?.parsing_name())
+        });
+        let _ = fs::remove_dir_all(&root);
+        // The temporary folder may be reported with its long name rather than its 8.3 one
+        let expected = tail.join("report.txt");
+        let expected = expected.to_str().unwrap().to_lowercase();
+        assert!(parsed.unwrap().to_lowercase().ends_with(&expected));
+    }
+}